@@ -0,0 +1,84 @@
+//! Throughput of instruction dispatch, for weighing changes to `step` and
+//! to decoding. Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use chip8rs::asm::assemble;
+use chip8rs::{disasm, Chip8, Config, Quirks};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// A loop touching most kinds of instruction: ALU ops, skips, a call and
+/// return, a font lookup and a draw
+const WORKLOAD: &str = "
+    loop:
+        ADD V0, 1
+        LD V1, V0
+        AND V1, V2
+        XOR V2, V0
+        SHR V1
+        SE V0, 0x80
+        CALL sub
+        LD F, V0
+        DRW V3, V4, 5
+        SKNP V0
+        JP loop
+        JP loop
+    sub:
+        ADD V3, 3
+        ADD V4, 1
+        RET
+";
+
+/// Decoding an opcode should take at most this long on average. Well over
+/// it points to a regression rather than noise, so the bench fails
+const DECODE_BUDGET_NS: f64 = 50.0;
+
+fn bench_step(c: &mut Criterion) {
+    let config = Config::builder()
+        .rom_path("bench")
+        .headless(true)
+        .quirks(Quirks {
+            display_wait: false,
+            ..Quirks::default()
+        })
+        .build()
+        .unwrap();
+    let mut chip8 = Chip8::from_rom(&config, &assemble(WORKLOAD).unwrap()).unwrap();
+
+    c.bench_function("step", |b| b.iter(|| black_box(chip8.step().unwrap())));
+}
+
+fn bench_decode(c: &mut Criterion) {
+    // Each iteration is a pass over the whole opcode space, which is also
+    // checked against the per opcode budget
+    let opcodes = u16::MAX as u32 + 1;
+    c.bench_function("decode (whole space)", |b| {
+        b.iter_custom(|iterations| {
+            let start = Instant::now();
+            for _ in 0..iterations {
+                for opcode in 0..=u16::MAX {
+                    black_box(disasm::mnemonic(black_box(opcode)));
+                }
+            }
+            let elapsed = start.elapsed();
+
+            let per_opcode = elapsed.as_nanos() as f64 / (iterations as f64 * opcodes as f64);
+            assert!(
+                per_opcode <= DECODE_BUDGET_NS,
+                "decode took {:.1} ns per opcode, over the {} ns budget",
+                per_opcode,
+                DECODE_BUDGET_NS
+            );
+
+            elapsed
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(3));
+    targets = bench_step, bench_decode
+}
+criterion_main!(benches);
@@ -0,0 +1,33 @@
+; Splash screen run when chip8rs is started without a ROM. Draws "C8" in
+; the middle of the screen with a bar sweeping back and forth underneath
+start:
+    LD V0, 0x0C
+    LD F, V0
+    LD V1, 27
+    LD V2, 10
+    DRW V1, V2, 5
+    LD V0, 0x08
+    LD F, V0
+    LD V1, 33
+    DRW V1, V2, 5
+    LD I, bar
+    LD V3, 0        ; x of the bar
+    LD V4, 20       ; y of the bar
+    LD V5, 1        ; added to x each step, 1 or -1
+loop:
+    DRW V3, V4, 1
+    LD V6, 2
+    LD DT, V6
+wait:
+    LD V6, DT
+    SE V6, 0
+    JP wait
+    DRW V3, V4, 1   ; erase the bar before moving it
+    ADD V3, V5
+    SNE V3, 56
+    LD V5, 0xFF
+    SNE V3, 0
+    LD V5, 1
+    JP loop
+bar:
+    DW 0xFF00
@@ -0,0 +1,236 @@
+//! A tiny assembler for writing test programs. It accepts the same syntax the
+//! disassembler produces, one instruction per line, e.g.
+//!
+//! ```text
+//! start:
+//!     LD V0, 0x05     ; comments start with a semicolon
+//!     LD F, V0
+//!     DRW V1, V2, 5
+//!     JP start
+//! ```
+//!
+//! Numbers can be decimal or hex with a `0x` prefix. Labels end with a colon
+//! and can be used anywhere an address is expected. `DW` emits a raw word.
+//! Programs are assumed to be loaded at PROGRAM_START.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::PROGRAM_START;
+
+/// A line of source that couldn't be assembled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    /// Line number, starting from 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+/// An instruction operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    V(u16),
+    I,
+    /// `[I]`, the memory pointed to by I
+    IndirectI,
+    Dt,
+    St,
+    K,
+    F,
+    Hf,
+    B,
+    Number(u16),
+}
+
+/// Assembles a program into bytes ready to be loaded at PROGRAM_START
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    // The first pass finds the address of every label, so jumps can refer
+    // to labels further down
+    let mut labels = HashMap::new();
+    let mut instructions = Vec::new();
+    let mut address = PROGRAM_START as u16;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut text = line.split(';').next().unwrap_or("").trim();
+
+        while let Some(colon) = text.find(':') {
+            let label = text[..colon].trim();
+            if !is_label(label) {
+                return Err(AsmError {
+                    line: line_number,
+                    message: format!("Invalid label: {}", label),
+                });
+            }
+
+            if labels.insert(label.to_string(), address).is_some() {
+                return Err(AsmError {
+                    line: line_number,
+                    message: format!("Label defined twice: {}", label),
+                });
+            }
+
+            text = text[colon + 1..].trim();
+        }
+
+        if !text.is_empty() {
+            instructions.push((line_number, text));
+            address = address.wrapping_add(2);
+        }
+    }
+
+    let mut bytes = Vec::new();
+    for (line_number, text) in instructions {
+        let opcode = encode(text, &labels).map_err(|message| AsmError {
+            line: line_number,
+            message,
+        })?;
+
+        bytes.extend_from_slice(&opcode.to_be_bytes());
+    }
+
+    Ok(bytes)
+}
+
+/// Labels start with a letter or underscore and contain only letters,
+/// digits and underscores
+fn is_label(text: &str) -> bool {
+    let mut chars = text.chars();
+
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Assembles a single instruction
+fn encode(text: &str, labels: &HashMap<String, u16>) -> Result<u16, String> {
+    let (mnemonic, rest) = match text.find(char::is_whitespace) {
+        Some(split) => (&text[..split], text[split..].trim()),
+        None => (text, ""),
+    };
+    let mnemonic = mnemonic.to_ascii_uppercase();
+
+    let operands = if rest.is_empty() {
+        Vec::new()
+    } else {
+        rest.split(',')
+            .map(|operand| parse_operand(operand.trim(), labels))
+            .collect::<Result<Vec<Operand>, String>>()?
+    };
+
+    use Operand::*;
+    let opcode = match (mnemonic.as_str(), operands.as_slice()) {
+        ("CLS", []) => 0x00E0,
+        ("RET", []) => 0x00EE,
+        ("LOW", []) => 0x00FE,
+        ("HIGH", []) => 0x00FF,
+        ("SYS", [Number(nnn)]) => address(*nnn)?,
+        ("JP", [Number(nnn)]) => 0x1000 | address(*nnn)?,
+        ("JP", [V(0), Number(nnn)]) => 0xB000 | address(*nnn)?,
+        ("CALL", [Number(nnn)]) => 0x2000 | address(*nnn)?,
+        ("SE", [V(x), Number(kk)]) => 0x3000 | x << 8 | byte(*kk)?,
+        ("SE", [V(x), V(y)]) => 0x5000 | x << 8 | y << 4,
+        ("SNE", [V(x), Number(kk)]) => 0x4000 | x << 8 | byte(*kk)?,
+        ("SNE", [V(x), V(y)]) => 0x9000 | x << 8 | y << 4,
+        ("LD", [V(x), Number(kk)]) => 0x6000 | x << 8 | byte(*kk)?,
+        ("LD", [V(x), V(y)]) => 0x8000 | x << 8 | y << 4,
+        ("LD", [I, Number(nnn)]) => 0xA000 | address(*nnn)?,
+        ("LD", [V(x), Dt]) => 0xF007 | x << 8,
+        ("LD", [V(x), K]) => 0xF00A | x << 8,
+        ("LD", [Dt, V(x)]) => 0xF015 | x << 8,
+        ("LD", [St, V(x)]) => 0xF018 | x << 8,
+        ("LD", [F, V(x)]) => 0xF029 | x << 8,
+        ("LD", [Hf, V(x)]) => 0xF030 | x << 8,
+        ("LD", [B, V(x)]) => 0xF033 | x << 8,
+        ("LD", [IndirectI, V(x)]) => 0xF055 | x << 8,
+        ("LD", [V(x), IndirectI]) => 0xF065 | x << 8,
+        ("ADD", [V(x), Number(kk)]) => 0x7000 | x << 8 | byte(*kk)?,
+        ("ADD", [V(x), V(y)]) => 0x8004 | x << 8 | y << 4,
+        ("ADD", [I, V(x)]) => 0xF01E | x << 8,
+        ("OR", [V(x), V(y)]) => 0x8001 | x << 8 | y << 4,
+        ("AND", [V(x), V(y)]) => 0x8002 | x << 8 | y << 4,
+        ("XOR", [V(x), V(y)]) => 0x8003 | x << 8 | y << 4,
+        ("SUB", [V(x), V(y)]) => 0x8005 | x << 8 | y << 4,
+        ("SHR", [V(x)]) => 0x8006 | x << 8,
+        ("SHR", [V(x), V(y)]) => 0x8006 | x << 8 | y << 4,
+        ("SUBN", [V(x), V(y)]) => 0x8007 | x << 8 | y << 4,
+        ("SHL", [V(x)]) => 0x800E | x << 8,
+        ("SHL", [V(x), V(y)]) => 0x800E | x << 8 | y << 4,
+        ("RND", [V(x), Number(kk)]) => 0xC000 | x << 8 | byte(*kk)?,
+        ("DRW", [V(x), V(y), Number(n)]) => 0xD000 | x << 8 | y << 4 | nibble(*n)?,
+        ("SKP", [V(x)]) => 0xE09E | x << 8,
+        ("SKNP", [V(x)]) => 0xE0A1 | x << 8,
+        ("PLANE", [Number(n)]) => 0xF001 | nibble(*n)? << 8,
+        ("DW", [Number(word)]) => *word,
+        _ => return Err(format!("Unknown instruction: {}", text)),
+    };
+
+    Ok(opcode)
+}
+
+fn parse_operand(text: &str, labels: &HashMap<String, u16>) -> Result<Operand, String> {
+    let upper = text.to_ascii_uppercase();
+
+    let operand = match upper.as_str() {
+        "I" => Operand::I,
+        "[I]" => Operand::IndirectI,
+        "DT" => Operand::Dt,
+        "ST" => Operand::St,
+        "K" => Operand::K,
+        "F" => Operand::F,
+        "HF" => Operand::Hf,
+        "B" => Operand::B,
+        _ if upper.len() == 2 && upper.starts_with('V') => {
+            match u16::from_str_radix(&upper[1..], 16) {
+                Ok(x) => Operand::V(x),
+                Err(_) => return Err(format!("Invalid register: {}", text)),
+            }
+        }
+        _ if upper.starts_with("0X") => match u16::from_str_radix(&upper[2..], 16) {
+            Ok(number) => Operand::Number(number),
+            Err(_) => return Err(format!("Invalid number: {}", text)),
+        },
+        _ if upper.starts_with(|c: char| c.is_ascii_digit()) => match upper.parse() {
+            Ok(number) => Operand::Number(number),
+            Err(_) => return Err(format!("Invalid number: {}", text)),
+        },
+        _ => match labels.get(text) {
+            Some(address) => Operand::Number(*address),
+            None => return Err(format!("Unknown label: {}", text)),
+        },
+    };
+
+    Ok(operand)
+}
+
+fn address(value: u16) -> Result<u16, String> {
+    check_range(value, 0xFFF, "Address")
+}
+
+fn byte(value: u16) -> Result<u16, String> {
+    check_range(value, 0xFF, "Byte")
+}
+
+fn nibble(value: u16) -> Result<u16, String> {
+    check_range(value, 0xF, "Nibble")
+}
+
+fn check_range(value: u16, max: u16, kind: &str) -> Result<u16, String> {
+    if value > max {
+        return Err(format!("{} out of range: {:#X}", kind, value));
+    }
+
+    Ok(value)
+}
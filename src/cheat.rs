@@ -0,0 +1,111 @@
+//! Game Genie style cheats that poke values into RAM. A cheat file has one
+//! cheat per line, e.g.
+//!
+//! ```text
+//! # Start with 9 lives
+//! 0x3A0=9
+//! # Never lose any
+//! freeze 0x3A0=9
+//! ```
+//!
+//! Addresses and values can be decimal or hex with a `0x` prefix. A plain
+//! cheat is poked once when the ROM starts. A `freeze` cheat is poked again
+//! after every instruction, so the program can never change the byte. Blank
+//! lines and lines starting with `#` are ignored.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::RAM_SIZE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cheat {
+    pub address: u16,
+    pub value: u8,
+    /// Poke the value after every instruction rather than once
+    pub freeze: bool,
+}
+
+/// A line of a cheat file that couldn't be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheatError {
+    /// Line number, starting from 1
+    pub line: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for CheatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CheatError {}
+
+/// Parses a cheat file in the format described above
+///
+/// ```
+/// use chip8rs::{parse_cheats, Cheat};
+///
+/// let cheats = parse_cheats("0x300=5\nfreeze 0x301=0xFF").unwrap();
+/// assert_eq!(
+///     cheats,
+///     [
+///         Cheat { address: 0x300, value: 5, freeze: false },
+///         Cheat { address: 0x301, value: 0xFF, freeze: true },
+///     ]
+/// );
+/// ```
+pub fn parse_cheats(text: &str) -> Result<Vec<Cheat>, CheatError> {
+    let mut cheats = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let error = |message| CheatError {
+            line: index + 1,
+            message,
+        };
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (freeze, poke) = match line.strip_prefix("freeze ") {
+            Some(poke) => (true, poke.trim()),
+            None => (false, line),
+        };
+
+        let mut parts = poke.splitn(2, '=');
+        let address = parts.next().and_then(|text| parse_number(text.trim()));
+        let value = parts.next().and_then(|text| parse_number(text.trim()));
+
+        let (address, value) = match (address, value) {
+            (Some(address), Some(value)) => (address, value),
+            _ => return Err(error("Expected address=value")),
+        };
+
+        if address as usize >= RAM_SIZE {
+            return Err(error("Address is outside RAM"));
+        }
+
+        if value > 0xFF {
+            return Err(error("Value doesn't fit in a byte"));
+        }
+
+        cheats.push(Cheat {
+            address,
+            value: value as u8,
+            freeze,
+        });
+    }
+
+    Ok(cheats)
+}
+
+fn parse_number(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
@@ -1,7 +1,128 @@
-use chip8rs::{Config, Display, Ram};
+#[cfg(feature = "chip8x")]
+use crate::chip8x::{self, ColorLayer};
+use crate::disasm;
+#[cfg(feature = "gamepad")]
+use crate::gamepad::{GamepadBinding, GamepadKeyboard, GamepadSource};
+use crate::hash;
+use crate::keyboard::{Keyboard, Keypad};
+#[cfg(feature = "std")]
+use crate::keyboard::{WindowKeyboard, COSMAC_KEYMAP, NUMERIC_KEYMAP};
+use crate::random::{RandomSource, SeededRandom};
+#[cfg(feature = "std")]
+use crate::replay::{Replay, ReplayFrame};
+use crate::romdb;
+use crate::timing;
+#[cfg(feature = "std")]
+use crate::timing::SyncMode;
+#[cfg(feature = "std")]
+use crate::timing::SystemClock;
+use crate::timing::{Clock, CostModel};
+#[cfg(feature = "tui")]
+use crate::tui::TerminalBackend;
+#[cfg(feature = "std")]
+use crate::WindowBackend;
+use crate::{
+    AccessKind, Backend, BackendKind, Cheat, Chip8Error, Chip8State, Config, Display, FontLayout,
+    MemoryAccess, Quirks, Ram, PROGRAM_START,
+};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::time::Duration;
+#[cfg(feature = "std")]
 use minifb::Key;
-use std::thread;
-use std::time::Duration;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+/// Progress of an Fx0A instruction waiting for a key
+#[derive(Debug, Default, Clone, Copy)]
+struct KeyWait {
+    /// The key that was pressed, once there is one
+    key: Option<u8>,
+    /// Timer ticks that have happened while the key was held
+    ticks_seen: u32,
+}
+
+/// Where `Config::capture_dir` frames are written
+#[cfg(feature = "std")]
+struct FrameCapture {
+    dir: PathBuf,
+    /// Number the next frame is saved under
+    frame: u64,
+    /// The timer tick the last frame was captured on, so at most one frame
+    /// is written per tick
+    last_tick: Option<u64>,
+}
+
+/// What happened during a `step`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// The instruction ran with nothing to report
+    Executed,
+    /// The instruction wrote to a watched address. Holds the address and its
+    /// value before and after the write
+    WatchpointHit(u16, u8, u8),
+    /// The word at the PC isn't an instruction, and `Config::break_on_unknown`
+    /// is set. The machine is halted and paused with the PC left on the word
+    UnknownOpcode(u16),
+}
+
+/// Why `run_until` stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// The predicate returned true
+    Reached,
+    /// The cycle budget ran out first
+    BudgetExhausted,
+}
+
+/// A register read before anything was written to it. See
+/// `Config::strict_uninit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UninitRead {
+    /// Address of the instruction that made the read
+    pub pc: u16,
+    /// Index of the register that was read
+    pub register: u8,
+}
+
+/// A sprite drawn from reserved memory outside the fonts. See
+/// `Config::warn_draw_reserved`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReservedDraw {
+    /// Address of the DRW instruction
+    pub pc: u16,
+    /// Value of I when the sprite was drawn
+    pub i: u16,
+}
+
+/// What an instruction did, as reported to the callback registered with
+/// `Chip8::on_exec`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecEvent {
+    /// Address the instruction was fetched from
+    pub pc: u16,
+    pub opcode: u16,
+    /// See `disasm::mnemonic`
+    pub mnemonic: &'static str,
+    /// Where the PC pointed once the instruction had run
+    pub pc_after: u16,
+    /// The instruction changed VF
+    pub vf_changed: bool,
+}
+
+/// Time spent running each kind of instruction. See `Config::profile`
+struct Profiler {
+    clock: Box<dyn Clock>,
+    /// Total time spent and number of instructions run, by mnemonic
+    totals: BTreeMap<&'static str, (Duration, u64)>,
+}
 
 pub struct Chip8 {
     vx: [u8; 16],
@@ -9,73 +130,1025 @@ pub struct Chip8 {
     pc: u16,
     sp: u8,
     dt: u8,
-    stack: [u16; 16],
+    st: u8,
+    /// One slot per return address, `Config::stack_size` long
+    stack: Vec<u16>,
     ram: Ram,
     display: Display,
+    #[cfg(feature = "std")]
     keymap: Vec<Key>,
+    /// Read alongside the window by `poll_keyboard`. See `set_gamepad`
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<(Box<dyn GamepadSource>, GamepadBinding)>,
+    keypad: Keypad,
+    /// The keypad as it was on the previous frame, for spotting new presses
+    previous_keypad: Keypad,
+    /// Keys pressed with `set_key` since the last poll, even if they have
+    /// been released again
+    pressed_since_poll: Keypad,
+    /// Keys that were down at some point before the current frame's poll.
+    /// SKP and SKNP count these as down, so a tap between polls isn't lost
+    latched_keypad: Keypad,
+    /// Number of polls in a row each key has been down for
+    held_frames: [u32; 16],
+    /// Set while Fx0A is waiting for a key
+    key_wait: Option<KeyWait>,
+    /// Full timer ticks a key must be held before Fx0A accepts it
+    key_debounce_ticks: u32,
+    rng: Box<dyn RandomSource>,
+    /// Session being recorded, and where it will be saved
+    #[cfg(feature = "std")]
+    recording: Option<(PathBuf, Replay)>,
+    /// Session being played back, and the index of the current frame
+    #[cfg(feature = "std")]
+    playback: Option<(Replay, usize)>,
+    total_collisions: u64,
+    /// Pixels erased by draws since the last render
+    erasures: u32,
+    /// Pixels erased by draws in the frame before the last render
+    erasures_last_frame: u32,
+    /// Instructions executed since the machine started
+    cycle_count: u64,
+    /// Instructions `step_frame` runs, from `Config::cpu_hz`
+    cycles_per_frame: u32,
+    /// Timer ticks since the machine started
+    tick_count: u64,
+    #[cfg(feature = "std")]
+    capture: Option<FrameCapture>,
+    /// See `Config::font_layout`
+    font_layout: FontLayout,
+    /// See `Config::cost_model`
+    cost_model: CostModel,
+    opcode_counts: BTreeMap<&'static str, u64>,
+    /// None unless profiling is enabled, so it costs nothing otherwise
+    profiler: Option<Profiler>,
+    /// Which registers have been written to. None unless
+    /// `Config::strict_uninit` is set
+    written_registers: Option<[bool; 16]>,
+    /// Reads of registers that hadn't been written to, in order
+    uninit_reads: Vec<UninitRead>,
+    /// Memory below PROGRAM_START that holds a font and so can be drawn
+    /// from. None unless `Config::warn_draw_reserved` is set
+    font_ranges: Option<Vec<Range<usize>>>,
+    /// Draws from reserved memory outside the fonts, in order
+    reserved_draws: Vec<ReservedDraw>,
+    halted: bool,
+    paused: bool,
+    quirks: Quirks,
+    /// Quirks for ROMs loaded later that aren't in the ROM database. None
+    /// if the quirks were chosen explicitly and so always apply
+    default_quirks: Option<Quirks>,
+    /// Set by Dxyn when the display wait quirk is enabled. No instructions
+    /// run until the next frame starts, which is the next timer tick. Ticks
+    /// only come from emulated time (`run_frame`, `advance_time` or the
+    /// timer clock), never the wall clock, so a replay waits exactly as
+    /// long as the recorded run did
+    waiting_for_vblank: bool,
+    /// Emulated time counted towards the next timer tick. Kept in
+    /// nanoseconds multiplied by the tick rate so no precision is lost
+    timer_time: u128,
+    /// See `Config::allowed_opcodes`
+    allowed_opcodes: Option<fn(u16) -> bool>,
+    /// Cheats from the config. Freeze cheats are poked after every step
+    cheats: Vec<Cheat>,
+    /// Clock that `sync_timers` follows, with its reading at the last sync
+    timer_clock: Option<(Box<dyn Clock>, Duration)>,
+    /// Where execution starts after a ROM is loaded. See
+    /// `Config::entry_point`
+    entry_point: u16,
+    /// Addresses that report a write through `step`
+    watchpoints: Vec<u16>,
+    /// The first watchpoint hit by the instruction being executed
+    watchpoint_hit: Option<(u16, u8, u8)>,
+    /// Called with the new beep state whenever it changes
+    sound_callback: Option<Box<dyn FnMut(bool)>>,
+    /// Called after each instruction. See `on_exec`
+    exec_callback: Option<Box<dyn FnMut(ExecEvent)>>,
+    /// The beep state last passed to the sound callback
+    sound_reported: bool,
+    /// States from before the most recent instructions, oldest first, for
+    /// `step_back`
+    history: VecDeque<Chip8State>,
+    /// Most states kept in the history. See `Config::history_size`
+    history_size: usize,
+    /// Only show the display from `render`. See `Config::coalesce_draws`
+    coalesce_draws: bool,
+    /// See `Config::break_on_unknown`
+    break_on_unknown: bool,
+    /// The CHIP-8X colors, when `Config::chip8x` is set
+    #[cfg(feature = "chip8x")]
+    colors: Option<ColorLayer>,
 }
 
 impl Chip8 {
-    pub fn new(config: &Config) -> Chip8 {
-        Chip8 {
+    /// Builds a machine running the ROM at `config.rom_path`, or SPLASH_ROM
+    /// if `config.embedded_rom` is set. Fails if the ROM or data file can't
+    /// be read or loaded, or the window or terminal can't be opened
+    #[cfg(feature = "std")]
+    pub fn new(config: &Config) -> Result<Chip8, Chip8Error> {
+        Chip8::with_ram(config, Ram::new(config)?)
+    }
+
+    /// Builds a machine running a ROM that is already in memory.
+    /// `config.rom_path` isn't used
+    pub fn from_rom(config: &Config, rom: &[u8]) -> Result<Chip8, Chip8Error> {
+        let mut ram = Ram::with_fonts(config);
+        ram.load_rom(rom)?;
+
+        Chip8::with_ram(config, ram)
+    }
+
+    /// Builds a machine from a previously captured (or hand made) state.
+    /// The state already holds the full memory and registers, so the config
+    /// settings that would fill them in are ignored: the ROM, data file and
+    /// font settings, the load address and the entry point. There is no ROM
+    /// to look up in the ROM database either, so the quirks are the config's.
+    ///
+    /// Everything else in the config is used as it is by `new`, including
+    /// the display settings, the history, the RAM size, which the state's
+    /// memory is cut or padded to, and the stack size, which the
+    /// state's stack is cut or padded to and its stack pointer must fit.
+    /// strict_uninit counts every register as already written. Cheats are
+    /// written over the state's memory.
+    ///
+    /// This is mainly intended for tests and fuzzers that need to start
+    /// execution from an arbitrary state. Fails, like `new`, if the config
+    /// asks for a window that can't be opened.
+    pub fn from_state(config: &Config, state: Chip8State) -> Result<Chip8, Chip8Error> {
+        let mut chip8 = Chip8::with_ram(
+            config,
+            Ram::from_memory_sized(&state.memory, config.ram_size),
+        )?;
+        chip8.restore_registers(&state)?;
+
+        // The registers were given values by whoever made the state
+        if let Some(written) = &mut chip8.written_registers {
+            *written = [true; 16];
+        }
+
+        Ok(chip8)
+    }
+
+    /// Saves the registers and RAM in the format described by
+    /// `Chip8State::to_bytes`
+    pub fn save_state(&self) -> Vec<u8> {
+        self.state().to_bytes()
+    }
+
+    /// Restores registers and RAM from a save state made by `save_state`.
+    /// Nothing is changed if the save state is invalid, its RAM doesn't
+    /// match the saved checksum, its stack pointer is past the configured
+    /// stack, or its RAM is a different size
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), Chip8Error> {
+        let state = Chip8State::from_bytes(bytes)?;
+        if state.memory.len() != self.ram.size() {
+            return Err(Chip8Error::InvalidState);
+        }
+
+        self.restore_registers(&state)?;
+        self.ram.write_data(0x0, &state.memory);
+        self.halted = false;
+
+        Ok(())
+    }
+
+    /// CRC-32 of the full RAM, for spotting runs that have diverged
+    pub fn ram_checksum(&self) -> u32 {
+        hash::crc32(self.ram.memory())
+    }
+
+    /// Fails with InvalidState, changing nothing, if the state's stack
+    /// pointer is past the configured stack
+    fn restore_registers(&mut self, state: &Chip8State) -> Result<(), Chip8Error> {
+        if state.sp as usize > self.stack.len() {
+            return Err(Chip8Error::InvalidState);
+        }
+
+        self.vx = state.vx;
+        self.i = state.i;
+        self.pc = state.pc;
+        self.sp = state.sp;
+        self.dt = state.dt;
+        self.st = state.st;
+        // The stack keeps the configured depth, whatever the state's is
+        let depth = self.stack.len();
+        self.stack = state.stack.clone();
+        self.stack.resize(depth, 0x0);
+
+        Ok(())
+    }
+
+    fn with_ram(config: &Config, mut ram: Ram) -> Result<Chip8, Chip8Error> {
+        for cheat in &config.cheats {
+            ram.write_byte(cheat.address as usize, cheat.value)?;
+        }
+
+        if config.trace_memory {
+            ram.enable_access_log();
+        }
+
+        let mut display = if config.headless {
+            Display::headless()
+        } else {
+            match config.backend {
+                #[cfg(feature = "std")]
+                BackendKind::Window => {
+                    let mut backend = if config.show_grid {
+                        WindowBackend::with_grid(config.scale)?
+                    } else {
+                        WindowBackend::new(config.scale)?
+                    };
+                    backend.set_palette(config.palette);
+                    if config.sync_mode == SyncMode::Vsync {
+                        backend.limit_fps(config.target_fps);
+                    }
+
+                    Display::with_backend(Box::new(backend))
+                }
+                // There is no window without std. Backends can be added with
+                // set_backend
+                #[cfg(not(feature = "std"))]
+                BackendKind::Window => Display::headless(),
+                #[cfg(feature = "tui")]
+                BackendKind::Terminal => Display::with_backend(Box::new(TerminalBackend::new()?)),
+                // Rejected when the config is built
+                #[cfg(not(feature = "tui"))]
+                BackendKind::Terminal => unreachable!(),
+            }
+        };
+        let quirks = romdb::select_quirks(romdb::KNOWN_ROMS, ram.rom_hash(), config);
+        display.set_wrapping(quirks.wrap_x, quirks.wrap_y);
+        display.set_palette(config.palette);
+        let entry_point = config.entry_point.unwrap_or(config.load_address);
+
+        #[cfg(feature = "chip8x")]
+        let colors = if config.chip8x {
+            Some(ColorLayer::new())
+        } else {
+            None
+        };
+        #[cfg(feature = "chip8x")]
+        if let Some(colors) = &colors {
+            display.set_render_hook(colors.render_hook());
+        }
+
+        let chip8 = Chip8 {
             vx: [0x0; 16],
             i: 0x0,
-            pc: 0x200,
+            pc: entry_point,
             sp: 0x0,
             dt: 0x0,
-            stack: [0x0; 16],
-            ram: Ram::new(&config.rom_path),
-            display: Display::new(),
-            keymap: vec![
-                Key::X,
-                Key::Key1,
-                Key::Key2,
-                Key::Key3,
-                Key::Q,
-                Key::W,
-                Key::E,
-                Key::A,
-                Key::S,
-                Key::D,
-                Key::Z,
-                Key::C,
-                Key::Key4,
-                Key::R,
-                Key::F,
-                Key::V,
-            ],
+            st: 0x0,
+            stack: vec![0x0; config.stack_size],
+            ram,
+            display,
+            #[cfg(feature = "std")]
+            keymap: if config.numeric_keypad {
+                NUMERIC_KEYMAP.to_vec()
+            } else {
+                COSMAC_KEYMAP.to_vec()
+            },
+            #[cfg(feature = "gamepad")]
+            gamepad: None,
+            keypad: Keypad::new(),
+            previous_keypad: Keypad::new(),
+            pressed_since_poll: Keypad::new(),
+            latched_keypad: Keypad::new(),
+            held_frames: [0; 16],
+            key_wait: None,
+            key_debounce_ticks: config.key_debounce_ticks,
+            rng: match config.seed {
+                Some(seed) => Box::new(SeededRandom::new(seed)),
+                #[cfg(feature = "std")]
+                None => Box::new(SeededRandom::from_entropy()),
+                // Without std there is no entropy source to seed from
+                #[cfg(not(feature = "std"))]
+                None => Box::new(SeededRandom::new(0)),
+            },
+            #[cfg(feature = "std")]
+            recording: None,
+            #[cfg(feature = "std")]
+            playback: None,
+            total_collisions: 0,
+            erasures: 0,
+            erasures_last_frame: 0,
+            cycle_count: 0,
+            cycles_per_frame: timing::cycles_per_frame(config.cpu_hz, 1),
+            tick_count: 0,
+            #[cfg(feature = "std")]
+            capture: match &config.capture_dir {
+                Some(dir) => {
+                    std::fs::create_dir_all(dir).map_err(|_| Chip8Error::CaptureFailed)?;
+                    Some(FrameCapture {
+                        dir: PathBuf::from(dir),
+                        frame: 0,
+                        last_tick: None,
+                    })
+                }
+                None => None,
+            },
+            font_layout: config.font_layout,
+            cost_model: config.cost_model,
+            opcode_counts: BTreeMap::new(),
+            #[cfg(feature = "std")]
+            profiler: if config.profile {
+                Some(Profiler {
+                    clock: Box::new(SystemClock::new()),
+                    totals: BTreeMap::new(),
+                })
+            } else {
+                None
+            },
+            // There is no clock without std. One can be supplied with
+            // set_profile_clock
+            #[cfg(not(feature = "std"))]
+            profiler: None,
+            written_registers: if config.strict_uninit {
+                Some([false; 16])
+            } else {
+                None
+            },
+            uninit_reads: Vec::new(),
+            font_ranges: if config.warn_draw_reserved {
+                Some(font_ranges(config))
+            } else {
+                None
+            },
+            reserved_draws: Vec::new(),
+            halted: false,
+            paused: config.debug,
+            quirks,
+            default_quirks: if config.quirks_explicit {
+                None
+            } else {
+                Some(config.quirks)
+            },
+            waiting_for_vblank: false,
+            timer_time: 0,
+            timer_clock: None,
+            cheats: config.cheats.clone(),
+            allowed_opcodes: config.allowed_opcodes,
+            entry_point,
+            watchpoints: Vec::new(),
+            watchpoint_hit: None,
+            sound_callback: None,
+            exec_callback: None,
+            sound_reported: false,
+            history: VecDeque::new(),
+            history_size: config.history_size,
+            coalesce_draws: config.coalesce_draws,
+            break_on_unknown: config.break_on_unknown,
+            #[cfg(feature = "chip8x")]
+            colors,
+        };
+
+        Ok(chip8)
+    }
+
+    /// True if execution starts outside the loaded ROM, which usually means
+    /// the entry point is wrong. Always false when built from a state, as
+    /// there is no ROM to check against
+    pub fn entry_point_outside_rom(&self) -> bool {
+        let rom_size = match self.ram.rom_size() {
+            Some(rom_size) => rom_size,
+            None => return false,
+        };
+
+        let entry_point = self.entry_point as usize;
+        let load_address = self.ram.load_address();
+        entry_point < load_address || entry_point >= load_address + rom_size
+    }
+
+    /// Swaps in a new program without restarting. The machine is reset as if
+    /// it had just been turned on, apart from the fonts, and the ROM is
+    /// loaded at the configured load address, PROGRAM_START by default.
+    /// The display is blanked, the data file and cheats are written again,
+    /// and execution starts from the configured entry point, which defaults
+    /// to the load address. Unless the quirks were chosen explicitly they
+    /// are picked again from the ROM database for the new ROM.
+    ///
+    /// ```
+    /// use chip8rs::{Chip8, Config, PROGRAM_START};
+    ///
+    /// let config = Config::builder()
+    ///     .rom_path("unused.ch8")
+    ///     .headless(true)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut chip8 = Chip8::from_rom(&config, &[0x60, 0x01, 0x61, 0x02, 0x62, 0x03]).unwrap();
+    /// chip8.step().unwrap();
+    /// chip8.step().unwrap();
+    ///
+    /// chip8.load_rom(&[0x12, 0x00]).unwrap();
+    /// let state = chip8.state();
+    /// assert_eq!(state.pc as usize, PROGRAM_START);
+    /// assert_eq!(state.vx[0], 0);
+    /// assert_eq!(&state.memory[PROGRAM_START..PROGRAM_START + 4], &[0x12, 0x00, 0x00, 0x00]);
+    /// ```
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), Chip8Error> {
+        self.ram.load_rom(rom)?;
+        for cheat in &self.cheats {
+            self.ram.write_byte(cheat.address as usize, cheat.value)?;
+        }
+
+        self.vx = [0x0; 16];
+        self.i = 0x0;
+        self.pc = self.entry_point;
+        self.sp = 0x0;
+        self.dt = 0x0;
+        self.st = 0x0;
+        self.stack.iter_mut().for_each(|slot| *slot = 0x0);
+        self.display.set_plane_mask(0x3);
+        self.display.clear();
+        self.display.set_plane_mask(0x1);
+        self.display.set_hires(false);
+        self.display.present();
+
+        self.key_wait = None;
+        self.halted = false;
+        self.waiting_for_vblank = false;
+        self.timer_time = 0;
+        self.total_collisions = 0;
+        self.erasures = 0;
+        self.erasures_last_frame = 0;
+        self.cycle_count = 0;
+        self.opcode_counts.clear();
+        if let Some(profiler) = &mut self.profiler {
+            profiler.totals.clear();
+        }
+        self.history.clear();
+        if let Some(written) = &mut self.written_registers {
+            *written = [false; 16];
+        }
+        self.uninit_reads.clear();
+        #[cfg(feature = "chip8x")]
+        if let Some(colors) = &mut self.colors {
+            *colors = ColorLayer::new();
+            self.show_colors();
+        }
+        self.reserved_draws.clear();
+
+        if let Some(default_quirks) = self.default_quirks {
+            self.quirks = match romdb::find(romdb::KNOWN_ROMS, romdb::rom_hash(rom)) {
+                Some(known) => (known.quirks)(),
+                None => default_quirks,
+            };
+            self.display
+                .set_wrapping(self.quirks.wrap_x, self.quirks.wrap_y);
+        }
+
+        Ok(())
+    }
+
+    /// Captures the current state of the machine
+    pub fn state(&self) -> Chip8State {
+        Chip8State {
+            vx: self.vx,
+            i: self.i,
+            pc: self.pc,
+            sp: self.sp,
+            dt: self.dt,
+            st: self.st,
+            stack: self.stack.clone(),
+            memory: self.ram.memory().to_vec(),
         }
     }
 
     pub fn window_is_open(&self) -> bool {
-        self.display.window.is_open()
+        self.display.is_open()
     }
 
+    #[cfg(feature = "std")]
     pub fn window_is_key_down(&self, key: Key) -> bool {
-        self.display.window.is_key_down(key)
+        self.display.is_key_down(key)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn window_is_key_pressed(&self, key: Key) -> bool {
+        self.display.is_key_pressed(key)
+    }
+
+    /// Presses or releases a CHIP-8 key (0x0 - 0xF) directly, for front ends
+    /// and tests that don't use a window. With a window open the key is
+    /// overwritten by the window's state on the next `poll_keyboard`.
+    ///
+    /// A key pressed and released again before the next `poll_keyboard` is
+    /// still seen as down by SKP and SKNP for the frame after that poll.
+    pub fn set_key(&mut self, index: u8, down: bool) {
+        self.keypad.set(index & 0x0F, down);
+        if down {
+            self.pressed_since_poll.set(index & 0x0F, true);
+        }
+    }
+
+    /// Pauses the machine. While paused `run_frame` does nothing, but `step`
+    /// can still be used to execute one instruction at a time
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Handles the debug mode keys on the window. F5 toggles between running
+    /// and paused, and while paused Space runs one instruction, F6 runs one
+    /// frame and Backspace undoes the last instruction. Returns true if the
+    /// machine moved, so the caller can show the new state
+    #[cfg(feature = "std")]
+    pub fn handle_debug_keys(&mut self) -> Result<bool, Chip8Error> {
+        if self.display.is_key_pressed(Key::F5) {
+            self.paused = !self.paused;
+        }
+
+        if !self.paused {
+            return Ok(false);
+        }
+
+        let mut moved = false;
+        if self.display.is_key_pressed(Key::Space) {
+            self.step()?;
+            moved = true;
+        }
+        if self.display.is_key_pressed(Key::F6) {
+            self.step_frame()?;
+            moved = true;
+        }
+        if self.display.is_key_pressed(Key::Backspace) {
+            self.step_back()?;
+            moved = true;
+        }
+
+        Ok(moved)
+    }
+
+    /// Runs a frame's worth of instructions and ticks the timers, unless the
+    /// machine is paused. `cycles` is spent against `Config::cost_model`, so
+    /// with the default flat model it is the number of instructions
+    pub fn run_frame(&mut self, cycles: u32) -> Result<(), Chip8Error> {
+        if self.paused {
+            return Ok(());
+        }
+
+        self.run_cycles(cycles)?;
+
+        self.tick_timers();
+
+        Ok(())
+    }
+
+    /// Runs one frame at the configured clock speed, ticks the timers and
+    /// renders. Unlike `run_frame` this also works while paused, so a paused
+    /// program can be watched one frame at a time
+    pub fn step_frame(&mut self) -> Result<(), Chip8Error> {
+        self.run_cycles(self.cycles_per_frame)?;
+
+        self.tick_timers();
+        self.render()
+    }
+
+    /// Steps until at least `cycles` machine cycles have been spent, with
+    /// each instruction costing what the cost model says. A draw held back
+    /// by display wait costs 1, since nothing runs
+    fn run_cycles(&mut self, cycles: u32) -> Result<(), Chip8Error> {
+        let mut spent: u32 = 0;
+        while spent < cycles {
+            spent = spent.saturating_add(self.next_cost());
+            if let StepOutcome::UnknownOpcode(_) = self.step()? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Cycles the next call to `step` will take
+    fn next_cost(&self) -> u32 {
+        if self.waiting_for_vblank {
+            return 1;
+        }
+
+        // A fetch outside RAM fails in step, whatever it costs
+        self.ram
+            .read_word_checked(self.pc as usize)
+            .map_or(1, |opcode| self.cost_model.cost(opcode))
+    }
+
+    /// Steps until `predicate` returns true or `max_cycles` instructions have
+    /// run, whichever comes first. The predicate is checked before every
+    /// instruction, so nothing runs if it is already true. Timers aren't
+    /// ticked, which keeps the result independent of wall-clock time.
+    ///
+    /// This is meant for running ROMs that might never finish, e.g. in tests:
+    ///
+    /// ```no_run
+    /// # use chip8rs::{Chip8, Config, RunOutcome};
+    /// # let config = Config::builder().rom_path("unused.ch8").headless(true).build().unwrap();
+    /// # let rom = [0x00, 0xFD];
+    /// let mut chip8 = Chip8::from_rom(&config, &rom).unwrap();
+    /// let outcome = chip8.run_until(|chip8| chip8.is_halted(), 100_000).unwrap();
+    /// assert_eq!(outcome, RunOutcome::Reached);
+    /// ```
+    pub fn run_until<F>(
+        &mut self,
+        mut predicate: F,
+        max_cycles: u64,
+    ) -> Result<RunOutcome, Chip8Error>
+    where
+        F: FnMut(&Chip8) -> bool,
+    {
+        for _ in 0..max_cycles {
+            if predicate(self) {
+                return Ok(RunOutcome::Reached);
+            }
+
+            self.step()?;
+        }
+
+        if predicate(self) {
+            Ok(RunOutcome::Reached)
+        } else {
+            Ok(RunOutcome::BudgetExhausted)
+        }
+    }
+
+    /// Runs `cycles` worth of instructions, then advances the timers by
+    /// `elapsed`.
+    /// See `advance_time`.
+    ///
+    /// Recordings and replays store instructions per frame but not timer
+    /// ticks, so while either is active the timers tick exactly once per
+    /// call, the same as `run_frame`.
+    pub fn run_for(&mut self, cycles: u32, elapsed: Duration) -> Result<(), Chip8Error> {
+        #[cfg(feature = "std")]
+        {
+            if self.recording.is_some() || self.playback.is_some() {
+                return self.run_frame(cycles);
+            }
+        }
+
+        if self.paused {
+            return Ok(());
+        }
+
+        self.run_cycles(cycles)?;
+
+        self.advance_time(elapsed);
+
+        Ok(())
+    }
+
+    /// Advances emulated time, ticking the timers once for every full 1/60 s
+    /// that has passed. Time short of a whole tick carries over to the next
+    /// call, so the timers keep exact pace however uneven the calls are.
+    /// Returns the number of ticks.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use chip8rs::{Chip8, Chip8State, Config, RAM_SIZE};
+    ///
+    /// let config = Config::builder()
+    ///     .rom_path("unused.ch8")
+    ///     .headless(true)
+    ///     .build()
+    ///     .unwrap();
+    /// let state = Chip8State {
+    ///     vx: [0; 16],
+    ///     i: 0,
+    ///     pc: 0x200,
+    ///     sp: 0,
+    ///     dt: 60,
+    ///     st: 0,
+    ///     stack: vec![0; 16],
+    ///     memory: vec![0; RAM_SIZE],
+    /// };
+    /// let mut chip8 = Chip8::from_state(&config, state).unwrap();
+    ///
+    /// // A second in uneven steps is exactly 60 ticks
+    /// let mut ticks = 0;
+    /// for _ in 0..3 {
+    ///     ticks += chip8.advance_time(Duration::from_millis(333));
+    /// }
+    /// assert_eq!(ticks, 59);
+    /// assert_eq!(chip8.state().dt, 1);
+    ///
+    /// ticks += chip8.advance_time(Duration::from_millis(1));
+    /// assert_eq!(ticks, 60);
+    /// assert_eq!(chip8.state().dt, 0);
+    /// ```
+    pub fn advance_time(&mut self, elapsed: Duration) -> u32 {
+        let tick_length = timing::NANOS_PER_SEC;
+
+        self.timer_time += elapsed.as_nanos() * timing::FRAME_RATE as u128;
+        let ticks = self.timer_time / tick_length;
+        self.timer_time %= tick_length;
+
+        // Past 256 ticks both timers are at zero, so the rest can be skipped
+        for _ in 0..ticks.min(256) {
+            self.tick_timers();
+        }
+
+        ticks as u32
+    }
+
+    /// Has `sync_timers` follow `clock`, e.g. a fake one in tests. Time
+    /// counts from the clock's reading now
+    pub fn set_timer_clock(&mut self, clock: Box<dyn Clock>) {
+        let now = clock.now();
+        self.timer_clock = Some((clock, now));
+    }
+
+    /// Advances the timers by however much time the timer clock says has
+    /// passed since the last sync, as `advance_time` would. Returns the
+    /// number of ticks, which is always 0 without a timer clock
+    pub fn sync_timers(&mut self) -> u32 {
+        let elapsed = match &mut self.timer_clock {
+            Some((clock, last)) => {
+                let now = clock.now();
+                let elapsed = now.saturating_sub(*last);
+                *last = now;
+                elapsed
+            }
+            None => return 0,
+        };
+
+        self.advance_time(elapsed)
+    }
+
+    /// Updates the keypad for the coming frame. During playback the recorded
+    /// keys are used instead of the window, and the keys are saved to the
+    /// recording if one is active.
+    pub fn poll_keyboard(&mut self) {
+        self.previous_keypad = self.keypad;
+        self.latched_keypad = self.pressed_since_poll;
+        self.pressed_since_poll = Keypad::new();
+
+        #[cfg(feature = "std")]
+        self.poll_input_sources();
+
+        for (key, frames) in self.held_frames.iter_mut().enumerate() {
+            *frames = if self.keypad.is_down(key as u8) {
+                frames.saturating_add(1)
+            } else {
+                0
+            };
+        }
+    }
+
+    /// Number of frames in a row a CHIP-8 key (0x0 - 0xF) has been held
+    /// down, counted by `poll_keyboard`. 0 if the key is up
+    pub fn key_held_frames(&self, index: u8) -> u32 {
+        self.held_frames[(index & 0x0F) as usize]
+    }
+
+    /// Reads gamepad buttons from now on, through `binding`. A CHIP-8 key
+    /// is down if it is held on the keyboard or the gamepad. The gamepad is
+    /// read even when headless, replacing keys set with `set_key`
+    #[cfg(feature = "gamepad")]
+    pub fn set_gamepad(&mut self, source: Box<dyn GamepadSource>, binding: GamepadBinding) {
+        self.gamepad = Some((source, binding));
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn has_gamepad(&self) -> bool {
+        self.gamepad.is_some()
+    }
+
+    #[cfg(all(feature = "std", not(feature = "gamepad")))]
+    fn has_gamepad(&self) -> bool {
+        false
+    }
+
+    /// Reads the keypad from the replay, or the window and gamepad, and
+    /// records it
+    #[cfg(feature = "std")]
+    fn poll_input_sources(&mut self) {
+        if let Some((replay, frame)) = &mut self.playback {
+            *frame += 1;
+
+            match replay.frames.get(*frame) {
+                Some(recorded) => self.keypad = Keypad::from_bits(recorded.keys),
+                None => self.playback = None,
+            }
+        } else if !self.display.is_headless() || self.has_gamepad() {
+            let mut keypad = Keypad::new();
+            if !self.display.is_headless() {
+                keypad.read_from(&WindowKeyboard::new(&self.display, &self.keymap));
+            }
+
+            #[cfg(feature = "gamepad")]
+            if let Some((source, binding)) = &mut self.gamepad {
+                source.update();
+
+                let mut buttons = Keypad::new();
+                buttons.read_from(&GamepadKeyboard::new(source.as_ref(), binding));
+                keypad = keypad.union(&buttons);
+            }
+
+            self.keypad = keypad;
+        }
+
+        if let Some((_, replay)) = &mut self.recording {
+            replay.frames.push(ReplayFrame {
+                keys: self.keypad.bits(),
+                ..ReplayFrame::default()
+            });
+        }
+    }
+
+    /// Starts recording input, timing and random numbers. The RNG is
+    /// reseeded so the recording can be played back from the same seed.
+    #[cfg(feature = "std")]
+    pub fn start_recording(&mut self, path: &Path) {
+        let mut seed_bytes = [0; 8];
+        seed_bytes
+            .iter_mut()
+            .for_each(|byte| *byte = self.rng.next_byte());
+        let seed = u64::from_be_bytes(seed_bytes);
+        self.rng = Box::new(SeededRandom::new(seed));
+
+        let mut replay = Replay::new(seed);
+        replay.frames.push(ReplayFrame {
+            keys: self.keypad.bits(),
+            ..ReplayFrame::default()
+        });
+
+        self.recording = Some((path.to_path_buf(), replay));
+    }
+
+    /// Stops recording and saves the replay to the path given when it was
+    /// started. Does nothing if no recording is active
+    #[cfg(feature = "std")]
+    pub fn stop_recording(&mut self) -> io::Result<()> {
+        match self.recording.take() {
+            Some((path, replay)) => replay.save(&path),
+            None => Ok(()),
+        }
+    }
+
+    /// Finishes the session before the emulator exits. Any active recording
+    /// is saved, playback is stopped, and the window or terminal is closed
+    /// so the terminal is usable again. The machine carries on headless if
+    /// it is run afterwards.
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use chip8rs::{Chip8, Config, Replay};
+    ///
+    /// let dir = std::env::temp_dir();
+    /// let rom_path = dir.join("shutdown_doctest.ch8");
+    /// let replay_path = dir.join("shutdown_doctest.c8r");
+    /// std::fs::write(&rom_path, [0x12, 0x00]).unwrap();
+    /// let _ = std::fs::remove_file(&replay_path);
+    ///
+    /// let config = Config::builder()
+    ///     .rom_path(rom_path.to_str().unwrap())
+    ///     .headless(true)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut chip8 = Chip8::new(&config).unwrap();
+    /// chip8.start_recording(&replay_path);
+    /// chip8.poll_keyboard();
+    /// chip8.run_frame(10).unwrap();
+    ///
+    /// chip8.shutdown().unwrap();
+    /// let replay = Replay::load(Path::new(&replay_path)).unwrap();
+    /// assert_eq!(replay.frames.len(), 2);
+    /// assert_eq!(replay.frames[1].cycles, 10);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn shutdown(&mut self) -> io::Result<()> {
+        self.playback = None;
+        self.display.close();
+
+        self.stop_recording()
+    }
+
+    /// Saves the session being recorded, or else the one being played back,
+    /// as CSV. See `Replay::to_csv` for the format. Fails if there is
+    /// neither
+    #[cfg(feature = "std")]
+    pub fn export_trace_csv(&self, path: &Path) -> io::Result<()> {
+        let replay = match (&self.recording, &self.playback) {
+            (Some((_, replay)), _) | (None, Some((replay, _))) => replay,
+            (None, None) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "Nothing is being recorded or played back",
+                ))
+            }
+        };
+
+        replay.save_csv(path)
+    }
+
+    /// Plays back a recorded session. The keypad follows the recording
+    /// until it runs out, after which live input is used again.
+    #[cfg(feature = "std")]
+    pub fn start_playback(&mut self, replay: Replay) {
+        self.rng = Box::new(SeededRandom::new(replay.seed));
+
+        if let Some(first) = replay.frames.first() {
+            self.keypad = Keypad::from_bits(first.keys);
+        }
+
+        self.playback = Some((replay, 0));
+    }
+
+    /// Number of instructions that ran during the current frame of the
+    /// replay being played back. The main loop should run exactly this many
+    /// instead of following real time, so the replay stays in sync.
+    #[cfg(feature = "std")]
+    pub fn playback_cycles(&self) -> Option<u32> {
+        self.playback
+            .as_ref()
+            .and_then(|(replay, frame)| replay.frames.get(*frame))
+            .map(|recorded| recorded.cycles)
     }
 
-    pub fn run_instruction(&mut self) {
+    /// Fetches and executes a single instruction. Timers and the display are
+    /// updated separately, once per frame.
+    ///
+    /// If the instruction wrote to a watched address the write happens as
+    /// normal and is reported in the outcome. Only `step` reports
+    /// watchpoints; the other run functions carry on past them.
+    pub fn step(&mut self) -> Result<StepOutcome, Chip8Error> {
+        if self.waiting_for_vblank {
+            return Ok(StepOutcome::Executed);
+        }
+
+        self.watchpoint_hit = None;
+
+        // Both bytes of the instruction need to be inside RAM
+        if self.pc as usize + 1 >= self.ram.size() {
+            return Err(Chip8Error::PcOutOfBounds(self.pc));
+        }
+
+        if self.quirks.strict_alignment && self.pc & 0x1 != 0 {
+            return Err(Chip8Error::MisalignedPc(self.pc));
+        }
+
+        let snapshot = if self.history_size > 0 {
+            Some(self.state())
+        } else {
+            None
+        };
+
+        let started = self.profiler.as_ref().map(|profiler| profiler.clock.now());
+        let (pc_before, vf_before) = (self.pc, self.vx[0xF]);
         let current_instruction = self.ram.read_word(self.pc as usize);
-        println!("Current Instruction: {:#06X}", current_instruction);
+        self.ram
+            .record_access(self.pc as usize, 2, false, AccessKind::Fetch);
 
-        if current_instruction == 0x00E0 {
+        if let Some(allowed) = self.allowed_opcodes {
+            if !allowed(current_instruction) {
+                return Err(Chip8Error::DisallowedOpcode(current_instruction));
+            }
+        }
+
+        if self.written_registers.is_some() {
+            self.check_register_reads(current_instruction);
+        }
+
+        #[cfg(feature = "chip8x")]
+        let is_chip8x = self.colors.is_some() && chip8x::is_color_opcode(current_instruction);
+        #[cfg(not(feature = "chip8x"))]
+        let is_chip8x = false;
+
+        if is_chip8x {
+            #[cfg(feature = "chip8x")]
+            self.chip8x_color(current_instruction);
+        } else if current_instruction == 0x00E0 {
             self.cls();
         } else if current_instruction == 0x00EE {
-            self.ret();
+            self.ret()?;
+        } else if current_instruction == 0x00FE {
+            self.low();
+        } else if current_instruction == 0x00FF {
+            self.high();
+        } else if current_instruction >> 12 == 0x0 {
+            // 0nnn
+            if self.quirks.strict_sys {
+                return self.unknown_opcode(current_instruction);
+            }
+            self.sys_addr();
         } else if current_instruction >> 12 == 0x1 {
             // 1nnn
             self.jp_addr(current_instruction);
         } else if current_instruction >> 12 == 0x2 {
             // 2nnn
-            self.call_addr(current_instruction);
+            self.call_addr(current_instruction)?;
         } else if current_instruction >> 12 == 0x3 {
             // 3xkk
             self.se_vx_byte(current_instruction);
         } else if current_instruction >> 12 == 0x4 {
             // 4xkk
             self.sne_vx_byte(current_instruction);
-        } else if current_instruction >> 12 == 0x5 {
+        } else if current_instruction & 0xF00F == 0x5000 {
             // 5xy0
             self.se_vx_vy(current_instruction);
         } else if current_instruction >> 12 == 0x6 {
@@ -87,75 +1160,555 @@ impl Chip8 {
         } else if current_instruction & 0xF00F == 0x8000 {
             // 8xy0
             self.ld_vx_vy(current_instruction);
-        } else if current_instruction >> 12 == 0x9 {
+        } else if current_instruction & 0xF00F == 0x8001 {
+            // 8xy1
+            self.or_vx_vy(current_instruction);
+        } else if current_instruction & 0xF00F == 0x8002 {
+            // 8xy2
+            self.and_vx_vy(current_instruction);
+        } else if current_instruction & 0xF00F == 0x8003 {
+            // 8xy3
+            self.xor_vx_vy(current_instruction);
+        } else if current_instruction & 0xF00F == 0x8004 {
+            // 8xy4
+            self.add_vx_vy(current_instruction);
+        } else if current_instruction & 0xF00F == 0x8005 {
+            // 8xy5
+            self.sub_vx_vy(current_instruction);
+        } else if current_instruction & 0xF00F == 0x8006 {
+            // 8xy6
+            self.shr_vx(current_instruction);
+        } else if current_instruction & 0xF00F == 0x8007 {
+            // 8xy7
+            self.subn_vx_vy(current_instruction);
+        } else if current_instruction & 0xF00F == 0x800E {
+            // 8xyE
+            self.shl_vx(current_instruction);
+        } else if current_instruction & 0xF00F == 0x9000 {
             // 9xy0
             self.sne_vx_vy(current_instruction);
         } else if current_instruction >> 12 == 0xA {
             // Annn
             self.ld_i_addr(current_instruction);
+        } else if current_instruction >> 12 == 0xB {
+            // Bnnn
+            self.jp_v0_addr(current_instruction);
+        } else if current_instruction >> 12 == 0xC {
+            // Cxkk
+            self.rnd_vx_byte(current_instruction);
         } else if current_instruction >> 12 == 0xD {
             // Dxyn
-            self.drw_vx_vy_nibble(current_instruction);
-        } else if current_instruction >> 12 == 0xE {
+            self.drw_vx_vy_nibble(current_instruction)?;
+        } else if current_instruction & 0xF0FF == 0xE09E {
+            // Ex9E
+            self.skp_vx(current_instruction);
+        } else if current_instruction & 0xF0FF == 0xE0A1 {
             // ExA1
             self.sknp_vx(current_instruction);
+        } else if current_instruction & 0xF0FF == 0xF001 {
+            // Fn01
+            self.plane_n(current_instruction);
         } else if current_instruction & 0xF0FF == 0xF007 {
             // Fx07
             self.ld_vx_dt(current_instruction);
+        } else if current_instruction & 0xF0FF == 0xF00A {
+            // Fx0A
+            self.ld_vx_k(current_instruction);
         } else if current_instruction & 0xF0FF == 0xF015 {
             // Fx15
             self.ld_dt_vx(current_instruction);
+        } else if current_instruction & 0xF0FF == 0xF018 {
+            // Fx18
+            self.ld_st_vx(current_instruction);
         } else if current_instruction & 0xF0FF == 0xF01E {
             // Fx1E
             self.add_i_vx(current_instruction);
         } else if current_instruction & 0xF0FF == 0xF029 {
             // Fx29
             self.ld_f_vx(current_instruction);
+        } else if current_instruction & 0xF0FF == 0xF030 {
+            // Fx30
+            self.ld_hf_vx(current_instruction);
         } else if current_instruction & 0xF0FF == 0xF033 {
             // Fx33
-            self.ld_b_vx(current_instruction);
+            self.ld_b_vx(current_instruction)?;
+        } else if current_instruction & 0xF0FF == 0xF055 {
+            // Fx55
+            self.ld_i_vx(current_instruction)?;
         } else if current_instruction & 0xF0FF == 0xF065 {
             // Fx65
-            self.ld_vx_i(current_instruction);
+            self.ld_vx_i(current_instruction)?;
+        } else {
+            return self.unknown_opcode(current_instruction);
+        }
+
+        // Only instructions that ran are kept, so step_back never undoes one
+        // that failed or was refused
+        if let Some(snapshot) = snapshot {
+            if self.history.len() == self.history_size {
+                self.history.pop_front();
+            }
+            self.history.push_back(snapshot);
+        }
+
+        self.cycle_count += 1;
+        if let Some(mnemonic) = disasm::mnemonic(current_instruction) {
+            *self.opcode_counts.entry(mnemonic).or_insert(0) += 1;
+
+            if let (Some(profiler), Some(started)) = (&mut self.profiler, started) {
+                let elapsed = profiler.clock.now() - started;
+                let total = profiler.totals.entry(mnemonic).or_default();
+                total.0 += elapsed;
+                total.1 += 1;
+            }
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(frame) = self.recording_frame() {
+            frame.cycles += 1;
+        }
+
+        for cheat in self.cheats.iter().filter(|cheat| cheat.freeze) {
+            self.ram.write_byte(cheat.address as usize, cheat.value)?;
+        }
+
+        if let Some(callback) = &mut self.exec_callback {
+            callback(ExecEvent {
+                pc: pc_before,
+                opcode: current_instruction,
+                mnemonic: disasm::mnemonic(current_instruction).unwrap_or("DW"),
+                pc_after: self.pc,
+                vf_changed: self.vx[0xF] != vf_before,
+            });
+        }
+
+        match self.watchpoint_hit {
+            Some((address, old, new)) => Ok(StepOutcome::WatchpointHit(address, old, new)),
+            None => Ok(StepOutcome::Executed),
+        }
+    }
+
+    /// Undoes the most recent instruction by restoring the state from
+    /// before it ran. Up to `Config::history_size` instructions can be
+    /// undone in a row.
+    ///
+    /// Only what `state` captures is restored. The display in particular is
+    /// left as it is, so sprites drawn by undone instructions stay on screen
+    pub fn step_back(&mut self) -> Result<(), Chip8Error> {
+        let state = self.history.pop_back().ok_or(Chip8Error::NoHistory)?;
+
+        self.restore_registers(&state)?;
+        self.ram.write_data(0x0, &state.memory);
+        self.halted = false;
+        self.cycle_count = self.cycle_count.saturating_sub(1);
+
+        Ok(())
+    }
+
+    /// Records a warning for each register the instruction reads that
+    /// hasn't been written to, then marks the registers it writes. Each
+    /// register is only warned about once
+    fn check_register_reads(&mut self, opcode: u16) {
+        let (reads, writes) = register_usage(opcode, &self.quirks);
+        let written = match &mut self.written_registers {
+            Some(written) => written,
+            None => return,
+        };
+
+        for (register, written) in written.iter_mut().enumerate() {
+            if reads & (1 << register) != 0 && !*written {
+                let read = UninitRead {
+                    pc: self.pc,
+                    register: register as u8,
+                };
+                self.uninit_reads.push(read);
+                *written = true;
+            }
+
+            if writes & (1 << register) != 0 {
+                *written = true;
+            }
+        }
+    }
+
+    /// Every read of a register that hadn't been written to yet. Always
+    /// empty unless `Config::strict_uninit` is set
+    pub fn uninit_reads(&self) -> &[UninitRead] {
+        &self.uninit_reads
+    }
+
+    /// Records a warning if any of the `size` sprite bytes at I are below
+    /// PROGRAM_START and outside the fonts
+    fn check_reserved_draw(&mut self, size: usize) {
+        let font_ranges = match &self.font_ranges {
+            Some(font_ranges) => font_ranges,
+            None => return,
+        };
+
+        let start = self.i as usize;
+        let reserved = (start..(start + size).min(PROGRAM_START))
+            .any(|address| !font_ranges.iter().any(|range| range.contains(&address)));
+        if !reserved {
+            return;
+        }
+
+        let draw = ReservedDraw {
+            pc: self.pc,
+            i: self.i,
+        };
+        self.reserved_draws.push(draw);
+    }
+
+    /// Every draw that read sprite bytes from reserved memory outside the
+    /// fonts. Always empty unless `Config::warn_draw_reserved` is set
+    pub fn reserved_draws(&self) -> &[ReservedDraw] {
+        &self.reserved_draws
+    }
+
+    /// Makes `step` report any write an instruction makes to `addr`
+    pub fn watch_address(&mut self, addr: u16) {
+        if !self.watchpoints.contains(&addr) {
+            self.watchpoints.push(addr);
+        }
+    }
+
+    /// Removes a watchpoint added by `watch_address`
+    pub fn unwatch_address(&mut self, addr: u16) {
+        self.watchpoints.retain(|watched| *watched != addr);
+    }
+
+    /// Writes data for an instruction, failing rather than wrapping if it
+    /// runs past the end of RAM. Writes to watched addresses are noted for
+    /// `step` to report
+    fn store(&mut self, address: usize, data: &[u8]) -> Result<(), Chip8Error> {
+        // The read checks the bounds before anything is written. The old
+        // bytes are only copied when a watchpoint could use them
+        let old = self.ram.read_bytes(address, data.len())?;
+        let old = if !self.watchpoints.is_empty() && self.watchpoint_hit.is_none() {
+            Some(old.to_vec())
         } else {
-            thread::sleep(Duration::from_millis(1000));
-            panic!("Invalid Instruction: {:#02x}", current_instruction)
+            None
+        };
+        self.ram.write_data(address, data);
+        self.ram
+            .record_access(address, data.len(), true, AccessKind::Data);
+
+        if let Some(old) = old {
+            self.watchpoint_hit = self
+                .watchpoints
+                .iter()
+                .map(|watched| *watched as usize)
+                .filter(|watched| (address..address + data.len()).contains(watched))
+                .min()
+                .map(|watched| {
+                    let offset = watched - address;
+                    (watched as u16, old[offset], data[offset])
+                });
+        }
+
+        Ok(())
+    }
+
+    /// Decrements the timers. This should be called at 60 Hz, independent of
+    /// the CPU clock speed
+    pub fn tick_timers(&mut self) {
+        // A new frame is starting, so a draw waiting for it can continue
+        self.waiting_for_vblank = false;
+        self.tick_count += 1;
+
+        if let Some(wait) = &mut self.key_wait {
+            if let Some(key) = wait.key {
+                if self.keypad.is_down(key) {
+                    wait.ticks_seen += 1;
+                } else {
+                    *wait = KeyWait::default();
+                }
+            }
         }
 
-        // Decrement Timers
         if self.dt > 0 {
             self.dt -= 1;
         }
 
-        self.display.update();
+        // A beep started by Fx18 since the last tick is reported before the
+        // timer counts down, so even a one tick beep is seen starting and
+        // stopping
+        self.report_sound();
+
+        if self.st > 0 {
+            self.st -= 1;
+        }
+
+        self.report_sound();
     }
 
-    // /// 0nnn - SYS addr
-    // /// Jump to a machine code routine at nnn.
-    // ///
-    // /// This instruction is only used on the old computers on which Chip-8 was
-    // /// originally implemented. It is ignored by modern interpreters.
-    // fn sys_addr() {
-    //     // NOOP
-    // }
+    /// Registers a function to be called whenever the beep state changes,
+    /// with true when a tone should start and false when it should stop.
+    /// Changes are spotted as the timers tick. Replaces any previous
+    /// callback.
+    pub fn on_sound_change(&mut self, f: impl FnMut(bool) + 'static) {
+        self.sound_callback = Some(Box::new(f));
+    }
+
+    /// Registers a function to be called after every instruction `step`
+    /// runs, e.g. to show a live trace in a debugger. Nothing is reported
+    /// while a draw waits for the next frame. Replaces any previous
+    /// callback.
+    pub fn on_exec(&mut self, f: impl FnMut(ExecEvent) + 'static) {
+        self.exec_callback = Some(Box::new(f));
+    }
+
+    /// Calls the sound callback if the beep state has changed since it was
+    /// last called
+    fn report_sound(&mut self) {
+        let beeping = self.is_beeping();
+        if beeping == self.sound_reported {
+            return;
+        }
+
+        self.sound_reported = beeping;
+        if let Some(callback) = &mut self.sound_callback {
+            callback(beeping);
+        }
+    }
+
+    /// A tone should be played for as long as the sound timer is non-zero
+    pub fn is_beeping(&self) -> bool {
+        self.st > 0
+    }
+
+    /// Ticks left on the sound timer
+    pub fn sound_remaining(&self) -> u8 {
+        self.st
+    }
+
+    /// Shows everything drawn so far, by swapping it to the display's front
+    /// buffer and updating the window. If nothing has changed since the
+    /// last render the window only handles its events. With
+    /// `Config::capture_dir` set the frame is also saved, unless one was
+    /// already saved since the timers last ticked
+    pub fn render(&mut self) -> Result<(), Chip8Error> {
+        self.erasures_last_frame = self.erasures;
+        self.erasures = 0;
+
+        self.display.present();
+        #[cfg(feature = "std")]
+        self.capture_frame()?;
+        self.display.update_if_dirty()
+    }
+
+    /// True if the display has changed since the last `render`, so the next
+    /// one will show a new frame
+    pub fn is_dirty(&self) -> bool {
+        self.display.is_dirty()
+    }
+
+    /// The CHIP-8X colors. None unless `Config::chip8x` is set
+    #[cfg(feature = "chip8x")]
+    pub fn color_layer(&self) -> Option<&ColorLayer> {
+        self.colors.as_ref()
+    }
+
+    /// Writes the display to the next numbered PPM in the capture
+    /// directory, if capturing and this tick hasn't been captured yet
+    #[cfg(feature = "std")]
+    fn capture_frame(&mut self) -> Result<(), Chip8Error> {
+        let capture = match &mut self.capture {
+            Some(capture) if capture.last_tick != Some(self.tick_count) => capture,
+            _ => return Ok(()),
+        };
+
+        let image = crate::ppm(
+            &self.display.render_buffer(),
+            self.display.width(),
+            self.display.height(),
+        );
+        let path = capture.dir.join(format!("frame_{:06}.ppm", capture.frame));
+        std::fs::write(path, image).map_err(|_| Chip8Error::CaptureFailed)?;
+
+        capture.frame += 1;
+        capture.last_tick = Some(self.tick_count);
+        Ok(())
+    }
+
+    /// Replaces where RND gets its random bytes from, e.g. with a
+    /// `FixedSequence` in tests. Starting a recording or playback switches
+    /// back to a seeded generator
+    pub fn set_random_source(&mut self, source: Box<dyn RandomSource>) {
+        self.rng = source;
+    }
+
+    /// Number of times the display has been composed and shown, by `render`
+    /// or by draws when `Config::coalesce_draws` is off
+    pub fn render_count(&self) -> u64 {
+        self.display.update_count()
+    }
+
+    /// Post-processes the display before it is shown, e.g. to apply a CRT
+    /// effect. The hook is given whether each pixel is lit, row by row, with
+    /// the width and height, and returns one 0RGB color per pixel. This
+    /// replaces the palette
+    pub fn set_render_hook(&mut self, hook: impl Fn(&[bool], usize, usize) -> Vec<u32> + 'static) {
+        self.display.set_render_hook(Box::new(hook));
+    }
+
+    /// The display as 0RGB colors, one per pixel, row by row, exactly as
+    /// `render` would show it in a window
+    pub fn render_buffer(&self) -> Vec<u32> {
+        self.display.render_buffer()
+    }
+
+    /// The display as RGBA8 bytes for GPU textures or image files, with
+    /// each pixel scaled up `scale` times. See `Display::render_rgba`
+    pub fn render_rgba(&self, scale: usize) -> Vec<u8> {
+        self.display.render_rgba(scale)
+    }
+
+    /// A hash of everything on the display, for comparing output against a
+    /// known good run. It only depends on which pixels are lit, so it is the
+    /// same whichever backend is in use.
+    pub fn framebuffer_hash(&self) -> u64 {
+        hash::fnv1a(self.display.pixels())
+    }
+
+    /// True if the pixel at (x, y) in the active resolution is lit on any
+    /// plane. Pixels off the display are never lit
+    pub fn is_pixel_lit(&self, x: usize, y: usize) -> bool {
+        let width = self.display.width();
+        if x >= width || y >= self.display.height() {
+            return false;
+        }
+
+        self.display.pixels()[y * width + x] != 0
+    }
+
+    /// Draws the display as text for logs and test failures, with `#` for a
+    /// lit pixel and `.` for an unlit one. Each row of the active resolution
+    /// is one line, separated with `\n`. A pixel lit on any plane counts as
+    /// lit.
+    pub fn framebuffer_ascii(&self) -> String {
+        self.display
+            .pixels()
+            .chunks(self.display.width())
+            .map(|row| {
+                row.iter()
+                    .map(|planes| if *planes != 0 { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Reports a word that isn't an instruction, as an error or, with
+    /// `Config::break_on_unknown`, by halting on it
+    fn unknown_opcode(&mut self, opcode: u16) -> Result<StepOutcome, Chip8Error> {
+        if !self.break_on_unknown {
+            return Err(Chip8Error::UnknownOpcode(opcode));
+        }
+
+        self.halted = true;
+        self.paused = true;
+        Ok(StepOutcome::UnknownOpcode(opcode))
+    }
+
+    /// 0nnn - SYS addr
+    /// Jump to a machine code routine at nnn.
+    ///
+    /// This instruction is only used on the old computers on which Chip-8 was
+    /// originally implemented. It is ignored by modern interpreters, unless
+    /// the strict SYS quirk makes it unknown, which `step` checks for.
+    fn sys_addr(&mut self) {
+        self.pc += 2;
+    }
+
+    /// 02A0, Bxy0 and Bxyn - the CHIP-8X color instructions
+    /// Step the background color, or color zones or rows of the display.
+    ///
+    /// See the `chip8x` module for what each one does.
+    #[cfg(feature = "chip8x")]
+    fn chip8x_color(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+        let y = ((command & 0x00F0) >> 4) as usize;
+        let n = (command & 0x000F) as usize;
+
+        if let Some(colors) = &mut self.colors {
+            if command == 0x02A0 {
+                colors.step_background();
+            } else if n == 0 {
+                colors.set_zones(self.vx[x], self.vx[(x + 1) & 0xF], self.vx[y]);
+            } else {
+                colors.set_rows(self.vx[x], self.vx[y], n, self.vx[(x + 1) & 0xF]);
+            }
+        }
+        self.show_colors();
+
+        self.pc += 2;
+    }
+
+    /// Has the display shown in the current CHIP-8X colors, by replacing the
+    /// render hook with one that knows them
+    #[cfg(feature = "chip8x")]
+    fn show_colors(&mut self) {
+        if let Some(colors) = &self.colors {
+            self.display.set_render_hook(colors.render_hook());
+        }
+    }
 
     /// 00E0 - CLS
     /// Clear the display.
+    ///
+    /// Only the selected XO-CHIP planes are cleared.
     fn cls(&mut self) {
-        println!("clear_display called");
         self.display.clear();
 
         self.pc += 2;
     }
 
-    /// 00EE - RET
-    /// Return from a subroutine.
+    /// 00EE - RET
+    /// Return from a subroutine.
+    ///
+    /// The interpreter sets the program counter to the address at the top of
+    /// the stack, then subtracts 1 from the stack pointer.
+    ///
+    /// With the wrap stack quirk the stack pointer wraps around instead of
+    /// underflowing.
+    fn ret(&mut self) -> Result<(), Chip8Error> {
+        if self.sp == 0 {
+            if !self.quirks.wrap_stack {
+                return Err(Chip8Error::StackUnderflow);
+            }
+
+            self.sp = self.stack.len() as u8;
+        }
+
+        if self.sp as usize > self.stack.len() {
+            return Err(Chip8Error::StackOverflow);
+        }
+
+        self.sp -= 1;
+        self.pc = self.stack[self.sp as usize];
+
+        Ok(())
+    }
+
+    /// 00FE - LOW
+    /// Switch to the 64x32 display. (SCHIP)
+    ///
+    /// The display is cleared when the resolution changes.
+    fn low(&mut self) {
+        self.display.set_hires(false);
+
+        self.pc += 2;
+    }
+
+    /// 00FF - HIGH
+    /// Switch to the 128x64 display. (SCHIP)
     ///
-    /// The interpreter sets the program counter to the address at the top of
-    /// the stack, then subtracts 1 from the stack pointer.
-    fn ret(&mut self) {
-        self.sp -= 1;
-        self.pc = self.stack[self.sp as usize];
+    /// The display is cleared when the resolution changes.
+    fn high(&mut self) {
+        self.display.set_hires(true);
+
+        self.pc += 2;
     }
 
     /// 1nnn - JP addr
@@ -163,7 +1716,15 @@ impl Chip8 {
     ///
     /// The interpreter sets the program counter to nnn.
     fn jp_addr(&mut self, command: u16) {
-        self.pc = command & 0x0FFF;
+        let addr = command & 0x0FFF;
+
+        // Jumping to the current instruction is the usual way for a ROM to
+        // signal that it has finished
+        if addr == self.pc {
+            self.halted = true;
+        }
+
+        self.pc = addr;
     }
 
     /// 2nnn - CALL addr
@@ -171,15 +1732,29 @@ impl Chip8 {
     ///
     /// The interpreter increments the stack pointer, then puts the current PC
     /// on the top of the stack. The PC is then set to nnn.
-    fn call_addr(&mut self, command: u16) {
-        // Put the current PC on the top of the stack
-        self.stack[self.sp as usize] = self.pc;
+    ///
+    /// With the wrap stack quirk the stack pointer wraps around instead of
+    /// overflowing, so the oldest return address is overwritten.
+    fn call_addr(&mut self, command: u16) -> Result<(), Chip8Error> {
+        if self.sp as usize >= self.stack.len() {
+            if !self.quirks.wrap_stack {
+                return Err(Chip8Error::StackOverflow);
+            }
+
+            self.sp = 0;
+        }
+
+        // Put the address of the next instruction on the top of the stack,
+        // so RET carries on after the CALL rather than making it again
+        self.stack[self.sp as usize] = self.pc + 2;
 
         // Increment stack pointer
         self.sp += 1;
 
         // Set PC to specified value
         self.pc = command & 0x0FFF;
+
+        Ok(())
     }
 
     /// 3xkk - SE Vx, byte
@@ -270,83 +1845,178 @@ impl Chip8 {
         self.pc += 2;
     }
 
-    // /// 8xy1 - OR Vx, Vy
-    // /// Set Vx = Vx OR Vy.
-    // ///
-    // /// Performs a bitwise OR on the values of Vx and Vy, then stores the
-    // /// result in Vx. A bitwise OR compares the corrseponding bits from two
-    // /// values, and if either bit is 1, then the same bit in the result is
-    // /// also 1. Otherwise, it is 0.
-    // fn or_vx_vy(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
-
-    // /// 8xy2 - AND Vx, Vy
-    // /// Set Vx = Vx AND Vy.
-    // ///
-    // /// Performs a bitwise AND on the values of Vx and Vy, then stores the
-    // /// result in Vx. A bitwise AND compares the corrseponding bits from two
-    // /// values, and if both bits are 1, then the same bit in the result is also
-    // /// 1. Otherwise, it is 0.
-    // fn and_vx_vy(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
-
-    // /// 8xy3 - XOR Vx, Vy
-    // /// Set Vx = Vx XOR Vy.
-    // ///
-    // /// Performs a bitwise exclusive OR on the values of Vx and Vy, then stores
-    // /// the result in Vx. An exclusive OR compares the corrseponding bits from
-    // /// two values, and if the bits are not both the same, then the
-    // /// corresponding bit in the result is set to 1. Otherwise, it is 0.
-    // fn xor_vx_vy(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
-
-    // /// 8xy4 - ADD Vx, Vy
-    // /// Set Vx = Vx + Vy, set VF = carry.
-    // ///
-    // /// The values of Vx and Vy are added together. If the result is greater
-    // /// than 8 bits (i.e., > 255,) VF is set to 1, otherwise 0. Only the lowest
-    // /// 8 bits of the result are kept, and stored in Vx.
-    // fn add_vx_vy(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
-    // /// 8xy5 - SUB Vx, Vy
-    // /// Set Vx = Vx - Vy, set VF = NOT borrow.
-    // ///
-    // /// If Vx > Vy, then VF is set to 1, otherwise 0. Then Vy is subtracted
-    // /// from Vx, and the results stored in Vx.
-    // fn sub_vx_vy(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
-
-    // /// 8xy6 - SHR Vx {, Vy}
-    // /// Set Vx = Vx SHR 1.
-    // ///
-    // /// If the least-significant bit of Vx is 1, then VF is set to 1, otherwise
-    // /// 0. Then Vx is divided by 2.
-    // fn shr_xv(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
-
-    // /// 8xy7 - SUBN Vx, Vy
-    // /// Set Vx = Vy - Vx, set VF = NOT borrow.
-    // ///
-    // /// If Vy > Vx, then VF is set to 1, otherwise 0. Then Vx is subtracted
-    // /// from Vy, and the results stored in Vx.
-    // fn subn_vx_vy(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
-
-    // /// 8xyE - SHL Vx {, Vy}
-    // /// Set Vx = Vx SHL 1.
-    // ///
-    // /// If the most-significant bit of Vx is 1, then VF is set to 1, otherwise
-    // /// to 0. Then Vx is multiplied by 2.
-    // fn shl_vx(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
+    /// 8xy1 - OR Vx, Vy
+    /// Set Vx = Vx OR Vy.
+    ///
+    /// Performs a bitwise OR on the values of Vx and Vy, then stores the
+    /// result in Vx. A bitwise OR compares the corrseponding bits from two
+    /// values, and if either bit is 1, then the same bit in the result is
+    /// also 1. Otherwise, it is 0.
+    fn or_vx_vy(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+        let y = ((command & 0x00F0) >> 4) as usize;
+
+        self.vx[x] |= self.vx[y];
+
+        if self.quirks.vf_reset {
+            self.vx[0xF] = 0x0;
+        }
+
+        self.pc += 2;
+    }
+
+    /// 8xy2 - AND Vx, Vy
+    /// Set Vx = Vx AND Vy.
+    ///
+    /// Performs a bitwise AND on the values of Vx and Vy, then stores the
+    /// result in Vx. A bitwise AND compares the corrseponding bits from two
+    /// values, and if both bits are 1, then the same bit in the result is also
+    /// 1. Otherwise, it is 0.
+    fn and_vx_vy(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+        let y = ((command & 0x00F0) >> 4) as usize;
+
+        self.vx[x] &= self.vx[y];
+
+        if self.quirks.vf_reset {
+            self.vx[0xF] = 0x0;
+        }
+
+        self.pc += 2;
+    }
+
+    /// 8xy3 - XOR Vx, Vy
+    /// Set Vx = Vx XOR Vy.
+    ///
+    /// Performs a bitwise exclusive OR on the values of Vx and Vy, then stores
+    /// the result in Vx. An exclusive OR compares the corrseponding bits from
+    /// two values, and if the bits are not both the same, then the
+    /// corresponding bit in the result is set to 1. Otherwise, it is 0.
+    fn xor_vx_vy(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+        let y = ((command & 0x00F0) >> 4) as usize;
+
+        self.vx[x] ^= self.vx[y];
+
+        if self.quirks.vf_reset {
+            self.vx[0xF] = 0x0;
+        }
+
+        self.pc += 2;
+    }
+
+    /// 8xy4 - ADD Vx, Vy
+    /// Set Vx = Vx + Vy, set VF = carry.
+    ///
+    /// The values of Vx and Vy are added together. If the result is greater
+    /// than 8 bits (i.e., > 255,) VF is set to 1, otherwise 0. Only the lowest
+    /// 8 bits of the result are kept, and stored in Vx.
+    fn add_vx_vy(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+        let y = ((command & 0x00F0) >> 4) as usize;
+
+        let (result, carry) = self.vx[x].overflowing_add(self.vx[y]);
+
+        // VF is written last so the flag wins if x is F
+        self.vx[x] = result;
+        self.vx[0xF] = carry as u8;
+
+        self.pc += 2;
+    }
+
+    /// 8xy5 - SUB Vx, Vy
+    /// Set Vx = Vx - Vy, set VF = NOT borrow.
+    ///
+    /// If Vx > Vy, then VF is set to 1, otherwise 0. Then Vy is subtracted
+    /// from Vx, and the results stored in Vx.
+    fn sub_vx_vy(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+        let y = ((command & 0x00F0) >> 4) as usize;
+
+        let (result, borrow) = self.vx[x].overflowing_sub(self.vx[y]);
+
+        self.vx[x] = result;
+        self.vx[0xF] = !borrow as u8;
+
+        self.pc += 2;
+    }
+
+    /// 8xy6 - SHR Vx {, Vy}
+    /// Set Vx = Vx SHR 1.
+    ///
+    /// If the least-significant bit of Vx is 1, then VF is set to 1, otherwise
+    /// 0. Then Vx is divided by 2.
+    ///
+    /// Without the shift quirk Vy is shifted instead and the result stored in
+    /// Vx, as on the COSMAC VIP. The shift VF first quirk decides whether the
+    /// flag or the result ends up in VF when x is F.
+    fn shr_vx(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+        let y = ((command & 0x00F0) >> 4) as usize;
+
+        let source = if self.quirks.shift {
+            self.vx[x]
+        } else {
+            self.vx[y]
+        };
+
+        self.store_shift(x, source >> 1, source & 0x1);
+
+        self.pc += 2;
+    }
+
+    /// 8xy7 - SUBN Vx, Vy
+    /// Set Vx = Vy - Vx, set VF = NOT borrow.
+    ///
+    /// If Vy > Vx, then VF is set to 1, otherwise 0. Then Vx is subtracted
+    /// from Vy, and the results stored in Vx.
+    fn subn_vx_vy(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+        let y = ((command & 0x00F0) >> 4) as usize;
+
+        let (result, borrow) = self.vx[y].overflowing_sub(self.vx[x]);
+
+        self.vx[x] = result;
+        self.vx[0xF] = !borrow as u8;
+
+        self.pc += 2;
+    }
+
+    /// 8xyE - SHL Vx {, Vy}
+    /// Set Vx = Vx SHL 1.
+    ///
+    /// If the most-significant bit of Vx is 1, then VF is set to 1, otherwise
+    /// to 0. Then Vx is multiplied by 2.
+    ///
+    /// Without the shift quirk Vy is shifted instead and the result stored in
+    /// Vx, as on the COSMAC VIP. The shift VF first quirk decides whether the
+    /// flag or the result ends up in VF when x is F.
+    fn shl_vx(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+        let y = ((command & 0x00F0) >> 4) as usize;
+
+        let source = if self.quirks.shift {
+            self.vx[x]
+        } else {
+            self.vx[y]
+        };
+
+        self.store_shift(x, source << 1, source >> 7);
+
+        self.pc += 2;
+    }
+
+    /// Stores the result of a shift in Vx and the shifted out bit in VF, in
+    /// the order the shift VF first quirk asks for
+    fn store_shift(&mut self, x: usize, result: u8, flag: u8) {
+        if self.quirks.shift_vf_first {
+            self.vx[0xF] = flag;
+            self.vx[x] = result;
+        } else {
+            self.vx[x] = result;
+            self.vx[0xF] = flag;
+        }
+    }
 
     /// 9xy0 - SNE Vx, Vy
     /// Skip next instruction if Vx != Vy.
@@ -376,23 +2046,44 @@ impl Chip8 {
         self.pc += 2;
     }
 
-    // /// Bnnn - JP V0, addr
-    // /// Jump to location nnn + V0.
-    // ///
-    // /// The program counter is set to nnn plus the value of V0.
-    // fn jp_v0_addr(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
+    /// Bnnn - JP V0, addr
+    /// Jump to location nnn + V0.
+    ///
+    /// The program counter is set to nnn plus the value of V0. With the jump
+    /// quirk the high nibble of nnn selects the register instead of V0.
+    fn jp_v0_addr(&mut self, command: u16) {
+        let nnn = command & 0x0FFF;
+
+        let register = if self.quirks.jump {
+            ((command & 0x0F00) >> 8) as usize
+        } else {
+            0x0
+        };
+
+        self.pc = nnn + self.vx[register] as u16;
+    }
+
+    /// Cxkk - RND Vx, byte
+    /// Set Vx = random byte AND kk.
+    ///
+    /// The interpreter generates a random number from 0 to 255, which is then
+    /// ANDed with the value kk. The results are stored in Vx. See instruction
+    /// 8xy2 for more information on AND.
+    fn rnd_vx_byte(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+        let kk = (command & 0x00FF) as u8;
+
+        let random = self.rng.next_byte();
+
+        #[cfg(feature = "std")]
+        if let Some(frame) = self.recording_frame() {
+            frame.random.push(random);
+        }
+
+        self.vx[x] = random & kk;
 
-    // /// Cxkk - RND Vx, byte
-    // /// Set Vx = random byte AND kk.
-    // ///
-    // /// The interpreter generates a random number from 0 to 255, which is then
-    // /// ANDed with the value kk. The results are stored in Vx. See instruction
-    // /// 8xy2 for more information on AND.
-    // fn rnd_vx_byte(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
+        self.pc += 2;
+    }
 
     /// Dxyn - DRW Vx, Vy, nibble
     /// Display n-byte sprite starting at memory location I at (Vx, Vy),
@@ -407,34 +2098,83 @@ impl Chip8 {
     /// screen. See instruction 8xy3 for more information on XOR, and
     /// section 2.4, Display, for more information on the Chip-8 screen and
     /// sprites.
-    fn drw_vx_vy_nibble(&mut self, command: u16) {
+    ///
+    /// Dxy0 draws a 16x16 sprite, two bytes per row, instead of an empty one.
+    /// When more than one XO-CHIP plane is selected the sprite for each plane
+    /// follows on from the one before in memory.
+    ///
+    /// A sprite that runs past the end of RAM is an error, and nothing is
+    /// drawn. With the clipped rows collide quirk, rows clipped off the
+    /// bottom of the display also set VF. The wrap start quirk wraps (Vx, Vy)
+    /// onto the display before anything is clipped.
+    fn drw_vx_vy_nibble(&mut self, command: u16) -> Result<(), Chip8Error> {
         let x = ((command & 0x0F00) >> 8) as usize;
         let y = ((command & 0x00F0) >> 4) as usize;
         let n = (command & 0x000F) as usize;
 
-        let sprite_data = self.ram.read_bytes(self.i as usize, n);
+        let plane_bytes = if n == 0 { 32 } else { n };
+        let size = plane_bytes * self.display.selected_planes();
 
-        let pixels_erased =
-            self.display
-                .draw_sprite(self.vx[x] as usize, self.vx[y] as usize, sprite_data);
+        self.ram
+            .record_access(self.i as usize, size, false, AccessKind::Sprite);
+        self.check_reserved_draw(size);
+        let sprite_data = self.ram.read_bytes(self.i as usize, size)?;
+
+        let (mut vx, mut vy) = (self.vx[x] as usize, self.vx[y] as usize);
+        if self.quirks.wrap_start {
+            vx %= self.display.width();
+            vy %= self.display.height();
+        }
+
+        let pixels_erased = if n == 0 {
+            self.display.draw_wide_sprite(vx, vy, sprite_data)
+        } else {
+            self.display.draw_sprite(vx, vy, sprite_data)
+        };
+
+        self.total_collisions += pixels_erased as u64;
+        self.erasures += pixels_erased;
+
+        // Clears the dirty flag too, so `render` doesn't show it again
+        if !self.coalesce_draws {
+            self.display.present();
+            self.display.update_if_dirty()?;
+        }
+
+        if self.quirks.display_wait {
+            self.waiting_for_vblank = true;
+        }
+
+        let rows = if n == 0 { 16 } else { n };
+        let clipped_collision =
+            self.quirks.clipped_rows_collide && self.display.clipped_rows(vy, rows) > 0;
 
-        if pixels_erased {
+        if pixels_erased > 0 || clipped_collision {
             self.vx[0xF] = 0x1;
         } else {
             self.vx[0xF] = 0x0;
         }
 
         self.pc += 2;
+
+        Ok(())
     }
 
-    // /// Ex9E - SKP Vx
-    // /// Skip next instruction if key with the value of Vx is pressed.
-    // ///
-    // /// Checks the keyboard, and if the key corresponding to the value of Vx is
-    // /// currently in the down position, PC is increased by 2.
-    // fn skp_vx(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
+    /// Ex9E - SKP Vx
+    /// Skip next instruction if key with the value of Vx is pressed.
+    ///
+    /// Checks the keyboard, and if the key corresponding to the value of Vx is
+    /// currently in the down position, PC is increased by 2.
+    fn skp_vx(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+
+        // There are only 16 keys, so only the low nibble of Vx is used
+        if self.key_is_down(self.vx[x]) {
+            self.pc += 2
+        }
+
+        self.pc += 2
+    }
 
     /// ExA1 - SKNP Vx
     /// Skip next instruction if key with the value of Vx is not pressed.
@@ -444,15 +2184,33 @@ impl Chip8 {
     fn sknp_vx(&mut self, command: u16) {
         let x = ((command & 0x0F00) >> 8) as usize;
 
-        let key_index = self.vx[x] as usize;
-
-        if self.display.window.is_key_down(self.keymap[key_index]) == false {
+        // There are only 16 keys, so only the low nibble of Vx is used
+        if !self.key_is_down(self.vx[x]) {
             self.pc += 2
         }
 
         self.pc += 2
     }
 
+    /// Whether SKP and SKNP see a key as down. Keys latched by the last poll
+    /// count as well as keys held now
+    fn key_is_down(&self, key: u8) -> bool {
+        self.keypad.union(&self.latched_keypad).is_down(key & 0x0F)
+    }
+
+    /// Fn01 - PLANE n
+    /// Select the display planes to draw to. (XO-CHIP)
+    ///
+    /// n is a bitmask of planes, so 1 selects the first plane, 2 the second
+    /// and 3 both. 00E0 and Dxyn only affect the selected planes.
+    fn plane_n(&mut self, command: u16) {
+        let n = ((command & 0x0F00) >> 8) as u8;
+
+        self.display.set_plane_mask(n);
+
+        self.pc += 2;
+    }
+
     /// Fx07 - LD Vx, DT
     /// Set Vx = delay timer value.
     ///
@@ -465,14 +2223,37 @@ impl Chip8 {
         self.pc += 2;
     }
 
-    // /// Fx0A - LD Vx, K
-    // /// Wait for a key press, store the value of the key in Vx.
-    // ///
-    // /// All execution stops until a key is pressed, then the value of that key
-    // /// is stored in Vx.
-    // fn ld_vx_k(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
+    /// Fx0A - LD Vx, K
+    /// Wait for a key press, store the value of the key in Vx.
+    ///
+    /// All execution stops until a key is pressed, then the value of that key
+    /// is stored in Vx.
+    ///
+    /// Only a key that goes down while waiting counts, and it has to stay
+    /// down for `key_debounce_ticks` full timer ticks before it is accepted.
+    /// Until then the instruction repeats without moving the PC. A key that
+    /// is let go too early is ignored and the wait starts over.
+    fn ld_vx_k(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+
+        let wait = self.key_wait.get_or_insert_with(KeyWait::default);
+        if wait.key.is_none() {
+            wait.key = self.keypad.newly_pressed(&self.previous_keypad);
+        }
+
+        // The first tick after the press only ends a partial tick, so a full
+        // tick has passed once the following one has been seen
+        let key = match wait.key {
+            Some(key) if self.key_debounce_ticks == 0 => key,
+            Some(key) if wait.ticks_seen > self.key_debounce_ticks => key,
+            _ => return,
+        };
+
+        self.vx[x] = key;
+        self.key_wait = None;
+
+        self.pc += 2;
+    }
 
     /// Fx15 - LD DT, Vx
     /// Set delay timer = Vx.
@@ -486,13 +2267,17 @@ impl Chip8 {
         self.pc += 2;
     }
 
-    // /// Fx18 - LD ST, Vx
-    // /// Set sound timer = Vx.
-    // ///
-    // /// ST is set equal to the value of Vx.
-    // fn ld_st_vx(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
+    /// Fx18 - LD ST, Vx
+    /// Set sound timer = Vx.
+    ///
+    /// ST is set equal to the value of Vx.
+    fn ld_st_vx(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
+
+        self.st = self.vx[x];
+
+        self.pc += 2;
+    }
 
     /// Fx1E - ADD I, Vx
     /// Set I = I + Vx.
@@ -501,7 +2286,18 @@ impl Chip8 {
     fn add_i_vx(&mut self, command: u16) {
         let x = ((command & 0x0F00) >> 8) as usize;
 
-        self.i += self.vx[x] as u16;
+        let ram_size = self.ram.size();
+        let result = self.i as usize + self.vx[x] as usize;
+
+        if self.quirks.i_overflow {
+            self.vx[0xF] = (result >= ram_size) as u8;
+        }
+
+        self.i = if self.quirks.i_wrap {
+            (result % ram_size) as u16
+        } else {
+            result as u16
+        };
 
         self.pc += 2;
     }
@@ -515,12 +2311,22 @@ impl Chip8 {
     fn ld_f_vx(&mut self, command: u16) {
         let x = ((command & 0x0F00) >> 8) as usize;
 
-        let digit = self.vx[x] as u16;
+        // Only the low nibble picks the digit, as on most interpreters
+        self.i = self.font_layout.small_address(self.vx[x]);
+
+        self.pc += 2;
+    }
+
+    /// Fx30 - LD HF, Vx
+    /// Set I = location of big sprite for digit Vx. (SCHIP)
+    ///
+    /// The value of I is set to the location for the 8x10 hexadecimal sprite
+    /// corresponding to the value of Vx. The big font is only present in RAM
+    /// when enabled in the config.
+    fn ld_hf_vx(&mut self, command: u16) {
+        let x = ((command & 0x0F00) >> 8) as usize;
 
-        // Each sprite is 5 bytes long, so multiply the digit by 5 to get the
-        // memory address. For example, the sprite for 0 begins at 0x0, and the
-        // sprite for 1 begins at 0x5
-        self.i = digit * 5;
+        self.i = self.font_layout.big_address(self.vx[x]);
 
         self.pc += 2;
     }
@@ -531,69 +2337,375 @@ impl Chip8 {
     /// The interpreter takes the decimal value of Vx, and places the hundreds
     /// digit in memory at location in I, the tens digit at location I+1, and
     /// the ones digit at location I+2.
-    fn ld_b_vx(&mut self, command: u16) {
+    ///
+    /// Writing past the end of RAM is an error, and nothing is written.
+    fn ld_b_vx(&mut self, command: u16) -> Result<(), Chip8Error> {
         let x = ((command & 0x0F00) >> 8) as usize;
 
-        let reg_val = self.vx[x] as u8;
+        let reg_val = self.vx[x];
 
         let hundreds: u8 = reg_val / 100;
         let tens: u8 = (reg_val - hundreds * 100) / 10;
         let ones: u8 = reg_val - (hundreds * 100 + tens * 10);
 
-        self.ram
-            .write_data(self.i as usize, &[hundreds, tens, ones]);
+        self.store(self.i as usize, &[hundreds, tens, ones])?;
 
         self.pc += 2;
+
+        Ok(())
     }
 
-    // /// Fx55 - LD [I], Vx
-    // /// Store registers V0 through Vx in memory starting at location I.
-    // ///
-    // /// The interpreter copies the values of registers V0 through Vx into
-    // /// memory, starting at the address in I.
-    // fn ld_i_vx(&mut self, command: u16) {
-    //     panic!("Not Implemented");
-    // }
+    /// Fx55 - LD [I], Vx
+    /// Store registers V0 through Vx in memory starting at location I.
+    ///
+    /// The interpreter copies the values of registers V0 through Vx into
+    /// memory, starting at the address in I.
+    ///
+    /// Writing past the end of RAM is an error, and nothing is written.
+    fn ld_i_vx(&mut self, command: u16) -> Result<(), Chip8Error> {
+        let x = ((command & 0x0F00) >> 8) as usize;
+
+        let registers = self.vx;
+        self.store(self.i as usize, &registers[..x + 1])?;
+
+        self.i = self
+            .i
+            .wrapping_add(self.quirks.memory_increment.amount(x as u16));
+
+        self.pc += 2;
+
+        Ok(())
+    }
 
     /// Fx65 - LD Vx, [I]
     /// Read registers V0 through Vx from memory starting at location I.
     ///
     /// The interpreter reads values from memory starting at location I into
     /// registers V0 through Vx.
-    fn ld_vx_i(&mut self, command: u16) {
+    fn ld_vx_i(&mut self, command: u16) -> Result<(), Chip8Error> {
         let x = ((command & 0x0F00) >> 8) as usize;
 
-        for i in 0..x + 1 {
-            let memory_index = self.i as usize + i;
-            // println!("memory_index: {:?}", memory_index);
-            // println!("vx[{:#04x?}] before is {:#06x?}", i, self.vx[i]);
-            self.vx[i] = *self.ram.read_byte(memory_index);
-            // println!("vx[{:#04x?}] after is {:#06x?}", i, self.vx[i]);
-        }
+        let values = self.ram.read_bytes(self.i as usize, x + 1)?;
+        self.vx[..x + 1].copy_from_slice(values);
+        self.ram
+            .record_access(self.i as usize, x + 1, false, AccessKind::Data);
+
+        self.i = self
+            .i
+            .wrapping_add(self.quirks.memory_increment.amount(x as u16));
 
         self.pc += 2;
+        Ok(())
     }
 
+    /// Total number of pixels erased by sprite draws since the machine started
+    pub fn total_collisions(&self) -> u64 {
+        self.total_collisions
+    }
+
+    /// Number of pixels erased by sprite draws in the frame shown by the
+    /// last `render`. A game that erases and redraws its sprites every
+    /// frame scores high here, so it measures how much the display flickers
+    pub fn erasures_last_frame(&self) -> u32 {
+        self.erasures_last_frame
+    }
+
+    /// Number of instructions executed since the machine started
+    pub fn cycle_count(&self) -> u64 {
+        self.cycle_count
+    }
+
+    /// Sets the window title. Does nothing when headless
+    pub fn set_title(&mut self, title: &str) {
+        self.display.set_title(title);
+    }
+
+    /// Shows the display on a different backend from now on, e.g. a screen
+    /// driver on an embedded board
+    pub fn set_backend(&mut self, backend: Box<dyn Backend>) {
+        self.display.set_backend(backend);
+    }
+
+    #[cfg(feature = "std")]
+    fn recording_frame(&mut self) -> Option<&mut ReplayFrame> {
+        self.recording
+            .as_mut()
+            .and_then(|(_, replay)| replay.frames.last_mut())
+    }
+
+    /// Number of times each instruction has been executed, keyed by mnemonic
+    pub fn opcode_counts(&self) -> &BTreeMap<&'static str, u64> {
+        &self.opcode_counts
+    }
+
+    /// Clears `opcode_counts`, e.g. so a test only sees the instructions a
+    /// ROM runs from a given point on
+    pub fn reset_opcode_counts(&mut self) {
+        self.opcode_counts.clear();
+    }
+
+    /// Turns profiling on, timed with the given clock. Replaces any clock
+    /// chosen before and starts the totals again
+    pub fn set_profile_clock(&mut self, clock: Box<dyn Clock>) {
+        self.profiler = Some(Profiler {
+            clock,
+            totals: BTreeMap::new(),
+        });
+    }
+
+    /// Total time spent running each kind of instruction and how many ran,
+    /// slowest first. Empty unless profiling is enabled. Only kinds that
+    /// have run are listed
+    pub fn profile_report(&self) -> Vec<(&'static str, Duration, u64)> {
+        let mut report: Vec<_> = match &self.profiler {
+            Some(profiler) => profiler
+                .totals
+                .iter()
+                .map(|(mnemonic, (time, count))| (*mnemonic, *time, *count))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        report.sort_by_key(|(_, time, _)| core::cmp::Reverse(*time));
+        report
+    }
+
+    /// Writes a block of data into RAM after the ROM. It is written again
+    /// by `load_rom`. See `Ram::load_data`
+    pub fn load_data(&mut self, data: &[u8], offset: usize) -> Result<(), Chip8Error> {
+        self.ram.load_data(data, offset)
+    }
+
+    /// Reads a byte of RAM, e.g. for a memory viewer
+    pub fn read_byte_checked(&self, addr: usize) -> Result<u8, Chip8Error> {
+        self.ram.read_byte_checked(addr)
+    }
+
+    /// Writes a byte of RAM while the program runs, e.g. for live patching
+    /// or cheats. Patched instructions take effect the next time they are
+    /// fetched.
+    pub fn write_byte(&mut self, addr: usize, value: u8) -> Result<(), Chip8Error> {
+        self.ram.write_byte(addr, value)
+    }
+
+    /// Number of subroutine calls that haven't returned yet
+    pub fn call_depth(&self) -> u8 {
+        self.sp
+    }
+
+    /// Return addresses of the active subroutine calls, outermost first
+    pub fn call_stack(&self) -> &[u16] {
+        &self.stack[..(self.sp as usize).min(self.stack.len())]
+    }
+
+    /// True once the ROM has entered an infinite jump to itself
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// The RAM accesses made so far, oldest first, or None if memory tracing
+    /// is disabled. Only the most recent ACCESS_LOG_LIMIT or so are kept
+    pub fn memory_access_log(&self) -> Option<&[MemoryAccess]> {
+        self.ram.access_log()
+    }
+
+    pub fn clear_memory_access_log(&mut self) {
+        self.ram.clear_access_log();
+    }
+
+    #[cfg(feature = "std")]
     pub fn debug_print_ram(&self) {
         self.ram.debug_print_ram();
     }
 
-    pub fn debug_print_registers(&self) {
-        println!("vX: {:02X?}", self.vx);
-        println!("stack: {:04X?}", self.stack);
-        println!("I: {:#06X?}", self.i);
-        println!("PC: {:#06X?}", self.pc);
-        println!("DT: {:#04X?}", self.dt);
-        println!("SP: {:#04X?}", self.sp);
+    /// Every byte of RAM along with its address, for disassemblers and other
+    /// tools that walk the whole of memory
+    pub fn iter_memory(&self) -> impl Iterator<Item = (usize, u8)> + '_ {
+        self.ram.iter_memory()
+    }
+
+    /// The bytes of RAM in `range`. See `Ram::memory_slice`
+    pub fn memory_slice(&self, range: Range<usize>) -> Result<&[u8], Chip8Error> {
+        self.ram.memory_slice(range)
+    }
+
+    /// Formats the registers, timers, stack and the next instruction into a
+    /// human readable block of text
+    pub fn state_report(&self) -> String {
+        let next = if (self.pc as usize) + 1 < self.ram.size() {
+            let opcode = self.ram.read_word(self.pc as usize);
+            format!("{:04X}  {}", opcode, disasm::disassemble(opcode))
+        } else {
+            String::from("out of bounds")
+        };
+
+        let mut report = String::new();
+        report += &format!("vX: {:02X?}\n", self.vx);
+        report += &format!("stack: {:04X?}\n", self.stack);
+        report += &format!("I: {:#06X?}\n", self.i);
+        report += &format!("PC: {:#06X?}\n", self.pc);
+        report += &format!("SP: {:#04X?}\n", self.sp);
+        report += &format!("DT: {:#04X?}\n", self.dt);
+        report += &format!("ST: {:#04X?}\n", self.st);
+        report += &format!("Next: {}\n", next);
+
+        report
+    }
+
+    /// Disassembles the instructions from `radius` instructions before the
+    /// PC to `radius` instructions after it, for showing where the program
+    /// was when something went wrong. The window is cut short at either end
+    /// of memory. Each entry is the address and the disassembly.
+    ///
+    /// ```
+    /// use chip8rs::{Chip8, Chip8State, Config, RAM_SIZE};
+    ///
+    /// let config = Config::builder()
+    ///     .rom_path("unused.ch8")
+    ///     .headless(true)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut memory = vec![0; RAM_SIZE];
+    /// memory[0x202..0x204].copy_from_slice(&[0x60, 0x2A]);
+    /// let state = Chip8State {
+    ///     vx: [0; 16],
+    ///     i: 0,
+    ///     pc: 0x202,
+    ///     sp: 0,
+    ///     dt: 0,
+    ///     st: 0,
+    ///     stack: vec![0; 16],
+    ///     memory,
+    /// };
+    /// let chip8 = Chip8::from_state(&config, state).unwrap();
+    ///
+    /// let window = chip8.disasm_window(2);
+    /// assert_eq!(window.len(), 5);
+    /// assert_eq!(window[0].0, 0x1FE);
+    /// assert_eq!(window[2], (0x202, String::from("LD V0, 0x2A")));
+    /// assert_eq!(window[4].0, 0x206);
+    /// ```
+    pub fn disasm_window(&self, radius: usize) -> Vec<(u16, String)> {
+        let pc = self.pc as usize;
+        let start = pc.saturating_sub(radius * 2);
+        let end = (pc + radius * 2).min(self.ram.size() - 2);
+
+        (start..=end)
+            .step_by(2)
+            .map(|address| {
+                let opcode = self.ram.read_word(address);
+                (address as u16, disasm::disassemble(opcode))
+            })
+            .collect()
+    }
+
+    /// Targets of every CALL (2nnn) in the loaded ROM, as a rough list of
+    /// subroutine entry points. Words are read two bytes apart from the load
+    /// address, so data that happens to look like a CALL is included and
+    /// CALLs at odd offsets are missed. Without a ROM, e.g. when built from
+    /// a state, everything from the load address up is scanned
+    pub fn find_call_targets(&self) -> BTreeSet<u16> {
+        let start = self.ram.load_address();
+        let end = match self.ram.rom_size() {
+            Some(rom_size) => (start + rom_size).min(self.ram.size()),
+            None => self.ram.size(),
+        };
+
+        (start..end.saturating_sub(1))
+            .step_by(2)
+            .map(|address| self.ram.read_word(address))
+            .filter(|opcode| opcode & 0xF000 == 0x2000)
+            .map(|opcode| opcode & 0x0FFF)
+            .collect()
     }
 
+    /// Moves execution to `address`, e.g. one of `find_call_targets`. The
+    /// registers and stack are left as they are
+    pub fn jump_to(&mut self, address: u16) {
+        self.pc = address;
+        self.halted = false;
+    }
+
+    /// Writes a disassembly of RAM from `start` up to and including the
+    /// instruction at `end` to a text file, one instruction per line with
+    /// its address and raw opcode, e.g. `0x0200  6005  LD V0, 0x05`. `end`
+    /// is clamped to the last instruction that fits in RAM
+    #[cfg(feature = "std")]
+    pub fn export_listing(&self, path: &Path, start: u16, end: u16) -> io::Result<()> {
+        use std::io::Write;
+
+        let end = (end as usize).min(self.ram.size() - 2);
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+
+        for address in (start as usize..=end).step_by(2) {
+            let opcode = self.ram.read_word(address);
+            writeln!(
+                file,
+                "{:#06X}  {:04X}  {}",
+                address,
+                opcode,
+                disasm::disassemble(opcode)
+            )?;
+        }
+
+        file.flush()
+    }
+
+    #[cfg(feature = "std")]
     pub fn debug_print_keymap(&self) {
         let mut key_states: Vec<bool> = Vec::new();
 
         for key in self.keymap.iter() {
-            key_states.push(self.display.window.is_key_down(*key));
+            key_states.push(self.display.is_key_down(*key));
         }
 
         println!("Keymap: {:?}", key_states);
     }
 }
+
+/// Memory holding the fonts the config loads
+fn font_ranges(config: &Config) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    if config.init_font {
+        ranges.push(config.font_layout.small_range());
+        if config.big_font {
+            ranges.push(config.font_layout.big_range());
+        }
+    }
+
+    ranges
+}
+
+/// The registers an instruction reads and writes, as bitmasks where bit n
+/// stands for Vn
+fn register_usage(opcode: u16, quirks: &Quirks) -> (u16, u16) {
+    let x = 1 << ((opcode & 0x0F00) >> 8);
+    let y = 1 << ((opcode & 0x00F0) >> 4);
+    let vf = 1 << 0xF;
+    // V0 through Vx, for Fx55 and Fx65. Shifting down from all ones
+    // rather than up from x keeps VF from overflowing
+    let up_to_x = u16::MAX >> (15 - ((opcode & 0x0F00) >> 8));
+
+    match (opcode >> 12, opcode & 0x000F, opcode & 0x00FF) {
+        (0x3, _, _) | (0x4, _, _) | (0x7, _, _) => (x, x),
+        (0x5, 0x0, _) | (0x9, 0x0, _) => (x | y, 0),
+        (0x6, _, _) | (0xC, _, _) => (0, x),
+        (0x8, 0x0, _) => (y, x),
+        (0x8, 0x1, _) | (0x8, 0x2, _) | (0x8, 0x3, _) => {
+            (x | y, if quirks.vf_reset { x | vf } else { x })
+        }
+        (0x8, 0x4, _) | (0x8, 0x5, _) | (0x8, 0x7, _) => (x | y, x | vf),
+        (0x8, 0x6, _) | (0x8, 0xE, _) => (if quirks.shift { x } else { y }, x | vf),
+        (0xB, _, _) => (if quirks.jump { x } else { 1 }, 0),
+        (0xD, _, _) => (x | y, vf),
+        (0xE, _, 0x9E) | (0xE, _, 0xA1) => (x, 0),
+        (0xF, _, 0x07) | (0xF, _, 0x0A) => (0, x),
+        (0xF, _, 0x1E) => (x, if quirks.i_overflow { vf } else { 0 }),
+        (0xF, _, 0x15) | (0xF, _, 0x18) | (0xF, _, 0x29) | (0xF, _, 0x30) | (0xF, _, 0x33) => {
+            (x, 0)
+        }
+        (0xF, _, 0x55) => (up_to_x, 0),
+        (0xF, _, 0x65) => (0, up_to_x),
+        _ => (0, 0),
+    }
+}
@@ -0,0 +1,153 @@
+//! Partial support for CHIP-8X, the CHIP-8 variant for the COSMAC VIP with
+//! the VP-590 color board. A `ColorLayer` sits over the monochrome display
+//! and gives every pixel a color, without changing which pixels are lit,
+//! so drawing and collisions work exactly as they do without it.
+//!
+//! With `Config::chip8x` set these opcodes change meaning:
+//!
+//! - `02A0` steps the background through blue, black, green and red
+//! - `Bxy0` colors a block of 8x4 pixel zones with the color in Vy. The low
+//!   nibble of Vx is the leftmost zone column and the high nibble is how
+//!   many more columns to the right to color. Vx+1 gives the zone rows the
+//!   same way
+//! - `Bxyn` colors `n` rows of the 8 pixel wide column that holds Vx,
+//!   starting at row Vy, with the color in Vx+1
+//!
+//! Colors are indexes into `FOREGROUND_COLORS`. Known limitations:
+//!
+//! - No other CHIP-8X opcodes are supported, e.g. 5xy1, the second keypad
+//!   (ExF2, ExF5) or the sound and I/O port instructions
+//! - `Bnnn` can't be used as a jump while CHIP-8X is on. CHIP-8X programs
+//!   usually expect to be loaded at 0x300, which `Config::load_address` sets
+//! - Colors are kept at 64x32. In the high resolution mode each color cell
+//!   covers four times as many pixels
+//! - Colors aren't part of save states, replays or the rewind history, and
+//!   the disassembler and profiler still see SYS and JP V0
+//! - Colors are shown through the render hook, so they replace any hook set
+//!   with `Chip8::set_render_hook`
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::{RenderHook, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+/// The VP-590 foreground colors: black, red, blue, violet, green, yellow,
+/// aqua and white
+pub const FOREGROUND_COLORS: [u32; 8] = [
+    0x000000, 0xFF0000, 0x0000FF, 0xFF00FF, 0x00FF00, 0xFFFF00, 0x00FFFF, 0xFFFFFF,
+];
+
+/// Background colors in the order `02A0` steps through them: blue, black,
+/// green and red
+pub const BACKGROUND_COLORS: [u32; 4] = [0x000080, 0x000000, 0x008000, 0x800000];
+
+/// Width of a color cell and of a `Bxy0` zone
+const CELL_WIDTH: usize = 8;
+/// Height of a `Bxy0` zone. `Bxyn` colors single rows
+const ZONE_HEIGHT: usize = 4;
+const COLUMNS: usize = DISPLAY_WIDTH / CELL_WIDTH;
+
+/// True for the opcodes CHIP-8X gives a color meaning to
+pub fn is_color_opcode(opcode: u16) -> bool {
+    opcode == 0x02A0 || opcode >> 12 == 0xB
+}
+
+/// The CHIP-8X colors: a background color for unlit pixels, and a
+/// foreground color for each 8x1 cell of lit pixels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorLayer {
+    /// Index into BACKGROUND_COLORS
+    background: usize,
+    /// Index into FOREGROUND_COLORS for each cell, row by row
+    foreground: [u8; COLUMNS * DISPLAY_HEIGHT],
+}
+
+impl ColorLayer {
+    /// A blue background with every cell white, so a program that never
+    /// sets a color still shows up
+    pub fn new() -> ColorLayer {
+        ColorLayer {
+            background: 0,
+            foreground: [7; COLUMNS * DISPLAY_HEIGHT],
+        }
+    }
+
+    /// The current background color
+    pub fn background(&self) -> u32 {
+        BACKGROUND_COLORS[self.background]
+    }
+
+    /// Moves to the next background color, wrapping from red back to blue
+    pub fn step_background(&mut self) {
+        self.background = (self.background + 1) % BACKGROUND_COLORS.len();
+    }
+
+    /// The color a lit pixel at (x, y) is shown in, in 64x32 coordinates
+    pub fn foreground(&self, x: usize, y: usize) -> u32 {
+        let cell =
+            self.foreground[(y % DISPLAY_HEIGHT) * COLUMNS + (x % DISPLAY_WIDTH) / CELL_WIDTH];
+        FOREGROUND_COLORS[cell as usize]
+    }
+
+    /// Colors zones as `Bxy0` does. `columns` and `rows` hold the first zone
+    /// in the low nibble and how many more follow in the high nibble. Zones
+    /// past the edge of the display are ignored
+    pub fn set_zones(&mut self, columns: u8, rows: u8, color: u8) {
+        let (left, width) = ((columns & 0xF) as usize, (columns >> 4) as usize);
+        let (top, height) = ((rows & 0xF) as usize, (rows >> 4) as usize);
+
+        for zone_row in top..=top + height {
+            for row in zone_row * ZONE_HEIGHT..(zone_row + 1) * ZONE_HEIGHT {
+                for column in left..=left + width {
+                    self.set_cell(column, row, color);
+                }
+            }
+        }
+    }
+
+    /// Colors `rows` rows of the column holding `x`, starting at row `y`, as
+    /// `Bxyn` does. Rows past the bottom of the display are ignored
+    pub fn set_rows(&mut self, x: u8, y: u8, rows: usize, color: u8) {
+        let column = x as usize % DISPLAY_WIDTH / CELL_WIDTH;
+        let top = y as usize % DISPLAY_HEIGHT;
+
+        for row in top..top + rows {
+            self.set_cell(column, row, color);
+        }
+    }
+
+    /// Turns the lit state of every pixel into colors. High resolution
+    /// displays are mapped onto the 64x32 cells
+    pub fn compose(&self, lit: &[bool], width: usize, height: usize) -> Vec<u32> {
+        lit.iter()
+            .enumerate()
+            .map(|(index, lit)| {
+                if !*lit {
+                    return self.background();
+                }
+
+                let x = index % width * DISPLAY_WIDTH / width;
+                let y = index / width * DISPLAY_HEIGHT / height;
+                self.foreground(x, y)
+            })
+            .collect()
+    }
+
+    /// A render hook that shows the display in these colors
+    pub fn render_hook(&self) -> RenderHook {
+        let colors = self.clone();
+        Box::new(move |lit, width, height| colors.compose(lit, width, height))
+    }
+
+    fn set_cell(&mut self, column: usize, row: usize, color: u8) {
+        if column < COLUMNS && row < DISPLAY_HEIGHT {
+            self.foreground[row * COLUMNS + column] = color & 0x7;
+        }
+    }
+}
+
+impl Default for ColorLayer {
+    fn default() -> ColorLayer {
+        ColorLayer::new()
+    }
+}
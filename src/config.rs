@@ -1,18 +1,725 @@
-use std::env;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::fs;
+
+#[cfg(feature = "std")]
+use crate::disasm;
+use crate::display::DEFAULT_PALETTE;
+#[cfg(feature = "std")]
+use crate::parse_cheats;
+use crate::ram::{FontLayout, FONT_SIZE, PROGRAM_START, RAM_SIZE};
+use crate::timing::{CostModel, SyncMode};
+use crate::{Cheat, Quirks};
+
+/// Where the display is shown when not running headless
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    /// A desktop window
+    Window,
+    /// The terminal, drawn with text. Needs the `tui` feature
+    Terminal,
+}
 
 pub struct Config {
     pub rom_path: String,
+    /// Run the built in SPLASH_ROM instead of reading `rom_path`. Chosen by
+    /// `build` when there is no ROM path, ROM directory or self-test
+    pub embedded_rom: bool,
+    /// Directory of ROMs the front end can switch between while running
+    pub rom_dir: Option<String>,
+    /// Load the SCHIP 8x10 font alongside the small font
+    pub big_font: bool,
+    /// Write the fonts into RAM. When false the reserved area below
+    /// PROGRAM_START is left zeroed, and so is the big font
+    pub init_font: bool,
+    /// Replacement for the built in small font. Must be FONT_SIZE bytes long
+    pub font_bytes: Option<Vec<u8>>,
+    /// Where the fonts are written and where Fx29 and Fx30 point
+    pub font_layout: FontLayout,
+    /// Size of each CHIP-8 pixel in the window. One of 1, 2, 4, 8, 16 or 32
+    pub scale: usize,
+    /// Number of instructions executed per second
+    pub cpu_hz: u32,
+    /// Speed multiplier applied while the turbo key is held
+    pub turbo_multiplier: u32,
+    /// Run without opening a window
+    pub headless: bool,
+    pub backend: BackendKind,
+    /// Start paused, with keys to single step through the program. See
+    /// `Chip8::handle_debug_keys`
+    pub debug: bool,
+    /// Show the measured frame and instruction rates in the title bar
+    pub show_stats: bool,
+    /// Draw a faint grid between CHIP-8 pixels in the window, for lining
+    /// up sprites. Only worth it at larger scales
+    pub show_grid: bool,
+    /// 0RGB colors for pixels lit on no plane, the first plane, the second
+    /// plane and both planes
+    pub palette: [u32; 4],
+    /// Frames per second the main loop is limited to. 0 means unlimited
+    pub target_fps: u32,
+    /// Show the display once per frame, after all of the frame's draws. When
+    /// false every Dxyn is shown as soon as it runs, as on hardware without
+    /// double buffering. Games that erase and redraw a sprite each frame then
+    /// show it missing in every other update, which is seen as flicker
+    pub coalesce_draws: bool,
+    /// How the main loop holds target_fps. Vsync falls back to sleeping
+    /// without a window
+    pub sync_mode: SyncMode,
+    pub quirks: Quirks,
+    /// The quirks were chosen by the user rather than left at the default,
+    /// so they aren't replaced by the ROM database
+    pub quirks_explicit: bool,
+    /// Seed for the random number generator. A random seed is used if unset
+    pub seed: Option<u64>,
+    /// Record the session to this .c8r replay file
+    pub record_path: Option<String>,
+    /// Play back a session from this .c8r replay file
+    pub replay_path: Option<String>,
+    /// Full timer ticks a key must be held before Fx0A accepts it
+    pub key_debounce_ticks: u32,
+    /// Bind CHIP-8 keys 0-F to the number keys and A-F in numeric order,
+    /// instead of the block of keys that mirrors the COSMAC VIP keypad
+    pub numeric_keypad: bool,
+    /// Keep a log of every RAM access made by the running program
+    pub trace_memory: bool,
+    /// Record every read of a register that nothing has written to yet,
+    /// which usually points to a bug in the ROM. See `Chip8::uninit_reads`.
+    /// The chip8rs binary prints each one as a warning
+    pub strict_uninit: bool,
+    /// Record every sprite drawn from memory below PROGRAM_START that isn't
+    /// part of a font, which is almost always a bad I. A sprite that only
+    /// runs into the program from there is recorded too. See
+    /// `Chip8::reserved_draws`. The chip8rs binary prints each one as a
+    /// warning
+    pub warn_draw_reserved: bool,
+    /// Halt and pause on an unknown opcode, leaving the PC on it, instead of
+    /// failing with an error. See `StepOutcome::UnknownOpcode`
+    pub break_on_unknown: bool,
+    /// Run CHIP-8X programs, where 02A0, Bxy0 and Bxyn set colors instead
+    /// of being SYS and JP V0. Needs the `chip8x` feature. See `chip8x`
+    pub chip8x: bool,
+    /// Time how long each kind of instruction takes to run. See
+    /// `Chip8::profile_report`
+    pub profile: bool,
+    /// Bytes of RAM. RAM_SIZE on the classic interpreters. At least 0x300,
+    /// so there is room for a program, and at most 0x10000, as far as I
+    /// can address. Save states only load into RAM_SIZE bytes
+    pub ram_size: usize,
+    /// Address the ROM is loaded at. Must not be below PROGRAM_START, where
+    /// the fonts are
+    pub load_address: u16,
+    /// Address execution starts from instead of the load address, for ROMs
+    /// with a header in front of their code
+    pub entry_point: Option<u16>,
+    /// A file of data written into RAM at data_offset once the ROM is
+    /// loaded. It must not overlap the ROM or the fonts
+    pub data_file: Option<String>,
+    pub data_offset: u16,
+    /// Number of past states kept for `Chip8::step_back`. 0 turns the
+    /// history off
+    pub history_size: usize,
+    /// Number of return addresses the stack holds. 16 on the classic
+    /// interpreters, more on some extended ones. At most 255
+    pub stack_size: usize,
+    /// Run the quirk self-check ROM and report the results instead of
+    /// running a ROM. See `selftest::run`
+    pub selftest: bool,
+    /// Only opcodes this returns true for may run. Any other is an error,
+    /// even if it is a valid instruction. See `disasm::is_classic`
+    pub allowed_opcodes: Option<fn(u16) -> bool>,
+    /// Machine cycles each instruction takes out of the frame's budget.
+    /// Flat by default, so the budget counts instructions
+    pub cost_model: CostModel,
+    /// Address to serve the HTTP debug server on, e.g. 127.0.0.1:8080.
+    /// Needs the `debug-server` feature
+    pub debug_server: Option<String>,
+    /// Directory to write every rendered frame to as a numbered PPM, e.g.
+    /// to encode a video with ffmpeg afterwards. At most one frame is
+    /// written per timer tick, so the frames are at 60 Hz
+    pub capture_dir: Option<String>,
+    /// Cheats poked into RAM while the ROM runs. See `parse_cheats`
+    pub cheats: Vec<Cheat>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            rom_path: String::new(),
+            embedded_rom: false,
+            rom_dir: None,
+            big_font: false,
+            init_font: true,
+            font_bytes: None,
+            font_layout: FontLayout::STANDARD,
+            scale: 16,
+            cpu_hz: 500,
+            turbo_multiplier: 4,
+            headless: false,
+            backend: BackendKind::Window,
+            debug: false,
+            show_stats: false,
+            show_grid: false,
+            palette: DEFAULT_PALETTE,
+            target_fps: 60,
+            coalesce_draws: true,
+            sync_mode: SyncMode::Sleep,
+            quirks: Quirks::default(),
+            quirks_explicit: false,
+            seed: None,
+            record_path: None,
+            replay_path: None,
+            key_debounce_ticks: 1,
+            numeric_keypad: false,
+            trace_memory: false,
+            strict_uninit: false,
+            warn_draw_reserved: false,
+            break_on_unknown: false,
+            chip8x: false,
+            profile: false,
+            ram_size: RAM_SIZE,
+            load_address: PROGRAM_START as u16,
+            entry_point: None,
+            data_file: None,
+            data_offset: 0,
+            history_size: 0,
+            stack_size: 16,
+            selftest: false,
+            cheats: Vec::new(),
+            debug_server: None,
+            capture_dir: None,
+            allowed_opcodes: None,
+            cost_model: CostModel::flat(),
+        }
+    }
 }
 
 impl Config {
-    pub fn new(mut args: env::Args) -> Result<Config, &'static str> {
+    /// Parses the command line arguments into a config. The first argument
+    /// is the program name, as with `env::args`
+    #[cfg(feature = "std")]
+    pub fn new<I: IntoIterator<Item = String>>(args: I) -> Result<Config, &'static str> {
+        let mut args = args.into_iter();
         args.next();
 
-        let rom_path = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Rom path not specified"),
-        };
+        let mut builder = Config::builder();
+        let mut quirks = Quirks::default();
+        let mut preset_chosen = false;
+        let mut clip_x = false;
+        let mut clip_y = false;
+
+        while let Some(arg) = args.next() {
+            builder = match arg.as_str() {
+                "--big-font" => builder.big_font(true),
+                "--no-font" => builder.init_font(false),
+                "--numeric-keypad" => builder.numeric_keypad(true),
+                "--headless" => builder.headless(true),
+                "--debug" => builder.debug(true),
+                "--stats" => builder.show_stats(true),
+                "--grid" => builder.show_grid(true),
+                "--profile" => builder.profile(true),
+                "--selftest" => builder.selftest(true),
+                "--classic-only" => builder.allowed_opcodes(disasm::is_classic),
+                "--no-coalesce-draws" => builder.coalesce_draws(false),
+                "--strict-uninit" => builder.strict_uninit(true),
+                "--warn-draw-reserved" => builder.warn_draw_reserved(true),
+                "--break-on-unknown" => builder.break_on_unknown(true),
+                "--chip8x" => builder.chip8x(true),
+                "--clip-x" => {
+                    clip_x = true;
+                    builder
+                }
+                "--clip-y" => {
+                    clip_y = true;
+                    builder
+                }
+                "--preset" => {
+                    quirks = match args.next().and_then(|arg| Quirks::preset(&arg)) {
+                        Some(preset) => preset,
+                        None => {
+                            return Err("Preset must be one of cosmac, chip48, schip or xochip")
+                        }
+                    };
+                    preset_chosen = true;
+                    builder
+                }
+                "--backend" => match args.next().as_deref() {
+                    Some("window") => builder.backend(BackendKind::Window),
+                    Some("tui") => builder.backend(BackendKind::Terminal),
+                    _ => return Err("Backend must be one of window or tui"),
+                },
+                "--font" => {
+                    let font_path = match args.next() {
+                        Some(arg) => arg,
+                        None => return Err("Font path not specified"),
+                    };
+
+                    match fs::read(font_path) {
+                        Ok(bytes) => builder.font_bytes(bytes),
+                        Err(_) => return Err("Could not read font file"),
+                    }
+                }
+                "--cheats" => {
+                    let cheats_path = match args.next() {
+                        Some(arg) => arg,
+                        None => return Err("Cheat file path not specified"),
+                    };
+
+                    let text = match fs::read_to_string(cheats_path) {
+                        Ok(text) => text,
+                        Err(_) => return Err("Could not read cheat file"),
+                    };
+
+                    match parse_cheats(&text) {
+                        Ok(cheats) => builder.cheats(cheats),
+                        Err(_) => return Err("Could not parse cheat file"),
+                    }
+                }
+                "--scale" => match args.next().map(|arg| arg.parse()) {
+                    Some(Ok(scale)) => builder.scale(scale),
+                    _ => return Err("Scale must be a number"),
+                },
+                "--cpu-hz" => match args.next().map(|arg| arg.parse()) {
+                    Some(Ok(hz)) => builder.cpu_hz(hz),
+                    _ => return Err("CPU frequency must be a positive number"),
+                },
+                "--turbo" => match args.next().map(|arg| arg.parse()) {
+                    Some(Ok(multiplier)) => builder.turbo_multiplier(multiplier),
+                    _ => return Err("Turbo multiplier must be a positive number"),
+                },
+                "--fps" => match args.next().map(|arg| arg.parse()) {
+                    Some(Ok(fps)) => builder.target_fps(fps),
+                    _ => return Err("Target FPS must be a number"),
+                },
+                "--palette" => match args.next().and_then(|arg| parse_palette(&arg)) {
+                    Some(palette) => builder.palette(palette),
+                    None => {
+                        return Err(
+                            "Palette must be four hex colors, e.g. 000000,FFFFFF,AAAAAA,555555",
+                        )
+                    }
+                },
+                "--sync" => match args.next().as_deref() {
+                    Some("sleep") => builder.sync_mode(SyncMode::Sleep),
+                    Some("spin") => builder.sync_mode(SyncMode::Spin),
+                    Some("vsync") => builder.sync_mode(SyncMode::Vsync),
+                    _ => return Err("Sync mode must be one of sleep, spin or vsync"),
+                },
+                "--seed" => match args.next().map(|arg| arg.parse()) {
+                    Some(Ok(seed)) => builder.seed(seed),
+                    _ => return Err("Seed must be a number"),
+                },
+                "--key-debounce" => match args.next().map(|arg| arg.parse()) {
+                    Some(Ok(ticks)) => builder.key_debounce_ticks(ticks),
+                    _ => return Err("Key debounce must be a number of ticks"),
+                },
+                "--load-address" => match args.next().and_then(|arg| parse_address(&arg)) {
+                    Some(address) => builder.load_address(address),
+                    None => return Err("Load address must be an address, e.g. 0x200"),
+                },
+                "--entry" => match args.next().and_then(|arg| parse_address(&arg)) {
+                    Some(address) => builder.entry_point(address),
+                    None => return Err("Entry point must be an address, e.g. 0x210"),
+                },
+                "--debug-server" => match args.next() {
+                    Some(arg) => builder.debug_server(&arg),
+                    None => return Err("Debug server address not specified"),
+                },
+                "--capture" => match args.next() {
+                    Some(arg) => builder.capture_dir(&arg),
+                    None => return Err("Capture directory not specified"),
+                },
+                "--data" => match args.next() {
+                    Some(arg) => builder.data_file(&arg),
+                    None => return Err("Data file path not specified"),
+                },
+                "--data-offset" => match args.next().and_then(|arg| parse_address(&arg)) {
+                    Some(address) => builder.data_offset(address),
+                    None => return Err("Data offset must be an address, e.g. 0x400"),
+                },
+                "--history" => match args.next().map(|arg| arg.parse()) {
+                    Some(Ok(size)) => builder.history_size(size),
+                    _ => return Err("History size must be a number of instructions"),
+                },
+                "--stack-size" => match args.next().map(|arg| arg.parse()) {
+                    Some(Ok(size)) => builder.stack_size(size),
+                    _ => return Err("Stack size must be a number of return addresses"),
+                },
+                "--rom-dir" => match args.next() {
+                    Some(arg) => builder.rom_dir(&arg),
+                    None => return Err("ROM directory not specified"),
+                },
+                "--record" => match args.next() {
+                    Some(arg) => builder.record_path(&arg),
+                    None => return Err("Recording path not specified"),
+                },
+                "--replay" => match args.next() {
+                    Some(arg) => builder.replay_path(&arg),
+                    None => return Err("Replay path not specified"),
+                },
+                _ => builder.rom_path(&arg),
+            };
+        }
+
+        // Individual quirk flags take priority over the preset, wherever they
+        // appear on the command line
+        if clip_x {
+            quirks.wrap_x = false;
+        }
+        if clip_y {
+            quirks.wrap_y = false;
+        }
+
+        // Only pass the quirks on if something was chosen, so the ROM database
+        // can still pick them otherwise
+        if preset_chosen || clip_x || clip_y {
+            builder = builder.quirks(quirks);
+        }
+
+        builder.build()
+    }
+
+    /// Starts building a config programmatically. Anything that isn't set
+    /// keeps its default value
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder {
+            config: Config::default(),
+        }
+    }
+}
+
+/// Builds a `Config` without going through command line parsing. Create one
+/// with `Config::builder()`.
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    pub fn rom_path(mut self, rom_path: &str) -> ConfigBuilder {
+        self.config.rom_path = rom_path.to_string();
+        self
+    }
+
+    pub fn embedded_rom(mut self, embedded_rom: bool) -> ConfigBuilder {
+        self.config.embedded_rom = embedded_rom;
+        self
+    }
+
+    pub fn rom_dir(mut self, rom_dir: &str) -> ConfigBuilder {
+        self.config.rom_dir = Some(rom_dir.to_string());
+        self
+    }
+
+    pub fn big_font(mut self, big_font: bool) -> ConfigBuilder {
+        self.config.big_font = big_font;
+        self
+    }
+
+    pub fn init_font(mut self, init_font: bool) -> ConfigBuilder {
+        self.config.init_font = init_font;
+        self
+    }
+
+    pub fn font_bytes(mut self, font_bytes: Vec<u8>) -> ConfigBuilder {
+        self.config.font_bytes = Some(font_bytes);
+        self
+    }
+
+    pub fn font_layout(mut self, font_layout: FontLayout) -> ConfigBuilder {
+        self.config.font_layout = font_layout;
+        self
+    }
+
+    pub fn scale(mut self, scale: usize) -> ConfigBuilder {
+        self.config.scale = scale;
+        self
+    }
+
+    pub fn cpu_hz(mut self, cpu_hz: u32) -> ConfigBuilder {
+        self.config.cpu_hz = cpu_hz;
+        self
+    }
+
+    pub fn turbo_multiplier(mut self, turbo_multiplier: u32) -> ConfigBuilder {
+        self.config.turbo_multiplier = turbo_multiplier;
+        self
+    }
+
+    pub fn headless(mut self, headless: bool) -> ConfigBuilder {
+        self.config.headless = headless;
+        self
+    }
+
+    pub fn backend(mut self, backend: BackendKind) -> ConfigBuilder {
+        self.config.backend = backend;
+        self
+    }
+
+    pub fn debug(mut self, debug: bool) -> ConfigBuilder {
+        self.config.debug = debug;
+        self
+    }
+
+    pub fn show_stats(mut self, show_stats: bool) -> ConfigBuilder {
+        self.config.show_stats = show_stats;
+        self
+    }
+
+    pub fn show_grid(mut self, show_grid: bool) -> ConfigBuilder {
+        self.config.show_grid = show_grid;
+        self
+    }
+
+    pub fn palette(mut self, palette: [u32; 4]) -> ConfigBuilder {
+        self.config.palette = palette;
+        self
+    }
+
+    pub fn target_fps(mut self, target_fps: u32) -> ConfigBuilder {
+        self.config.target_fps = target_fps;
+        self
+    }
+
+    pub fn coalesce_draws(mut self, coalesce_draws: bool) -> ConfigBuilder {
+        self.config.coalesce_draws = coalesce_draws;
+        self
+    }
+
+    pub fn sync_mode(mut self, sync_mode: SyncMode) -> ConfigBuilder {
+        self.config.sync_mode = sync_mode;
+        self
+    }
+
+    /// Sets the quirks explicitly, so they are used even for ROMs the ROM
+    /// database knows about
+    pub fn quirks(mut self, quirks: Quirks) -> ConfigBuilder {
+        self.config.quirks = quirks;
+        self.config.quirks_explicit = true;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> ConfigBuilder {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn record_path(mut self, record_path: &str) -> ConfigBuilder {
+        self.config.record_path = Some(record_path.to_string());
+        self
+    }
+
+    pub fn replay_path(mut self, replay_path: &str) -> ConfigBuilder {
+        self.config.replay_path = Some(replay_path.to_string());
+        self
+    }
+
+    pub fn key_debounce_ticks(mut self, key_debounce_ticks: u32) -> ConfigBuilder {
+        self.config.key_debounce_ticks = key_debounce_ticks;
+        self
+    }
+
+    pub fn numeric_keypad(mut self, numeric_keypad: bool) -> ConfigBuilder {
+        self.config.numeric_keypad = numeric_keypad;
+        self
+    }
+
+    pub fn trace_memory(mut self, trace_memory: bool) -> ConfigBuilder {
+        self.config.trace_memory = trace_memory;
+        self
+    }
+
+    pub fn strict_uninit(mut self, strict_uninit: bool) -> ConfigBuilder {
+        self.config.strict_uninit = strict_uninit;
+        self
+    }
+
+    pub fn warn_draw_reserved(mut self, warn_draw_reserved: bool) -> ConfigBuilder {
+        self.config.warn_draw_reserved = warn_draw_reserved;
+        self
+    }
+
+    pub fn break_on_unknown(mut self, break_on_unknown: bool) -> ConfigBuilder {
+        self.config.break_on_unknown = break_on_unknown;
+        self
+    }
+
+    pub fn chip8x(mut self, chip8x: bool) -> ConfigBuilder {
+        self.config.chip8x = chip8x;
+        self
+    }
+
+    pub fn profile(mut self, profile: bool) -> ConfigBuilder {
+        self.config.profile = profile;
+        self
+    }
+
+    pub fn allowed_opcodes(mut self, allowed_opcodes: fn(u16) -> bool) -> ConfigBuilder {
+        self.config.allowed_opcodes = Some(allowed_opcodes);
+        self
+    }
+
+    pub fn cost_model(mut self, cost_model: CostModel) -> ConfigBuilder {
+        self.config.cost_model = cost_model;
+        self
+    }
+
+    pub fn debug_server(mut self, address: &str) -> ConfigBuilder {
+        self.config.debug_server = Some(address.to_string());
+        self
+    }
+
+    pub fn capture_dir(mut self, path: &str) -> ConfigBuilder {
+        self.config.capture_dir = Some(path.to_string());
+        self
+    }
+
+    pub fn cheats(mut self, cheats: Vec<Cheat>) -> ConfigBuilder {
+        self.config.cheats = cheats;
+        self
+    }
+
+    pub fn selftest(mut self, selftest: bool) -> ConfigBuilder {
+        self.config.selftest = selftest;
+        self
+    }
+
+    pub fn history_size(mut self, history_size: usize) -> ConfigBuilder {
+        self.config.history_size = history_size;
+        self
+    }
+
+    pub fn stack_size(mut self, stack_size: usize) -> ConfigBuilder {
+        self.config.stack_size = stack_size;
+        self
+    }
+
+    pub fn ram_size(mut self, ram_size: usize) -> ConfigBuilder {
+        self.config.ram_size = ram_size;
+        self
+    }
+
+    pub fn load_address(mut self, load_address: u16) -> ConfigBuilder {
+        self.config.load_address = load_address;
+        self
+    }
+
+    pub fn entry_point(mut self, entry_point: u16) -> ConfigBuilder {
+        self.config.entry_point = Some(entry_point);
+        self
+    }
+
+    pub fn data_file(mut self, data_file: &str) -> ConfigBuilder {
+        self.config.data_file = Some(data_file.to_string());
+        self
+    }
+
+    pub fn data_offset(mut self, data_offset: u16) -> ConfigBuilder {
+        self.config.data_offset = data_offset;
+        self
+    }
+
+    /// Validates the settings and returns the finished config
+    pub fn build(self) -> Result<Config, &'static str> {
+        let mut config = self.config;
+
+        // The front end picks the first ROM from the directory if no ROM
+        // was given. The self-test brings its own ROM. Otherwise the splash
+        // ROM is run
+        if config.rom_path.is_empty() && config.rom_dir.is_none() && !config.selftest {
+            config.embedded_rom = true;
+        }
+
+        if let Some(font_bytes) = &config.font_bytes {
+            if font_bytes.len() != FONT_SIZE {
+                return Err("Font file must be exactly 80 bytes");
+            }
+        }
+
+        config.font_layout.validate(config.big_font)?;
+
+        if ![1, 2, 4, 8, 16, 32].contains(&config.scale) {
+            return Err("Scale must be one of 1, 2, 4, 8, 16 or 32");
+        }
+
+        if config.cpu_hz == 0 {
+            return Err("CPU frequency must be a positive number");
+        }
+
+        // The stack pointer is a byte
+        if config.stack_size == 0 || config.stack_size > u8::MAX as usize {
+            return Err("Stack size must be between 1 and 255");
+        }
+
+        if config.turbo_multiplier == 0 {
+            return Err("Turbo multiplier must be a positive number");
+        }
+
+        if config.ram_size < PROGRAM_START + 0x100 || config.ram_size > 0x10000 {
+            return Err("RAM size must be between 0x300 and 0x10000 bytes");
+        }
+
+        if config.load_address as usize >= config.ram_size {
+            return Err("Load address must be inside RAM");
+        }
+
+        // Both bytes of the first instruction have to be in RAM
+        if let Some(entry_point) = config.entry_point {
+            if entry_point as usize + 1 >= config.ram_size {
+                return Err("Entry point must be inside RAM");
+            }
+        }
+
+        if config.data_file.is_some() && config.data_offset as usize >= config.ram_size {
+            return Err("Data offset must be inside RAM");
+        }
+
+        if cfg!(not(feature = "tui")) && config.backend == BackendKind::Terminal {
+            return Err("The tui backend needs chip8rs to be built with the tui feature");
+        }
+
+        if cfg!(not(feature = "debug-server")) && config.debug_server.is_some() {
+            return Err("The debug server needs chip8rs to be built with the debug-server feature");
+        }
+
+        if cfg!(not(feature = "chip8x")) && config.chip8x {
+            return Err("CHIP-8X needs chip8rs to be built with the chip8x feature");
+        }
+
+        if cfg!(not(feature = "std")) && config.capture_dir.is_some() {
+            return Err("Capturing frames needs chip8rs to be built with the std feature");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Parses an address given in hex with a 0x prefix, or in decimal
+#[cfg(feature = "std")]
+fn parse_address(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Parses four comma separated RRGGBB hex colors
+#[cfg(feature = "std")]
+fn parse_palette(text: &str) -> Option<[u32; 4]> {
+    let mut palette = [0; 4];
+    let mut colors = text.split(',');
+
+    for entry in palette.iter_mut() {
+        let color = colors.next()?.trim();
+        if color.len() != 6 {
+            return None;
+        }
+
+        *entry = u32::from_str_radix(color, 16).ok()?;
+    }
 
-        Ok(Config { rom_path })
+    match colors.next() {
+        Some(_) => None,
+        None => Some(palette),
     }
 }
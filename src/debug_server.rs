@@ -0,0 +1,166 @@
+//! A tiny HTTP server for inspecting a running machine from a browser or
+//! curl. It is polled from the main loop, so requests are handled between
+//! frames on the emulator's own thread and never race with execution.
+//!
+//! - `GET /state` returns the registers, timers and stack as JSON
+//! - `POST /step` runs one instruction and returns the new state
+//! - `GET /ram?start=0x200&end=0x210` returns the bytes in a range of RAM.
+//!   `start` defaults to 0 and `end` to RAM_SIZE
+//!
+//! Only the request line is looked at; headers and bodies are ignored.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::{Chip8, Chip8State, RAM_SIZE};
+
+/// How long a client gets to send its request before it is dropped. The
+/// read happens on the emulator's thread, so this is kept well under a
+/// frame. Local clients send the request as soon as they connect
+const READ_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// How long a client gets to take the response, for the same reason
+const WRITE_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Connections answered per `poll`. Any more wait for the next frame, so a
+/// burst of clients can't stall the emulator
+const MAX_CONNECTIONS_PER_POLL: usize = 4;
+
+pub struct DebugServer {
+    listener: TcpListener,
+}
+
+impl DebugServer {
+    /// Starts listening on `address`, e.g. `127.0.0.1:8080`. Port 0 picks a
+    /// free port, which `local_addr` reports
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<DebugServer> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(DebugServer { listener })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Answers the requests that are waiting, up to MAX_CONNECTIONS_PER_POLL
+    /// of them, without blocking when there are none. Call this once per
+    /// frame
+    pub fn poll(&self, chip8: &mut Chip8) -> io::Result<()> {
+        for _ in 0..MAX_CONNECTIONS_PER_POLL {
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err),
+            };
+
+            // A client that goes away part way through only affects itself
+            let _ = handle(stream, chip8);
+        }
+
+        Ok(())
+    }
+}
+
+fn handle(mut stream: TcpStream, chip8: &mut Chip8) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("");
+    let (path, query) = match target.find('?') {
+        Some(split) => (&target[..split], &target[split + 1..]),
+        None => (target, ""),
+    };
+
+    let (status, body) = match (method, path) {
+        ("GET", "/state") => ("200 OK", state_json(&chip8.state())),
+        ("POST", "/step") => match chip8.step() {
+            Ok(_) => ("200 OK", state_json(&chip8.state())),
+            Err(err) => ("500 Internal Server Error", error_json(&err.to_string())),
+        },
+        ("GET", "/ram") => ram_response(chip8, query),
+        (_, "/state") | (_, "/step") | (_, "/ram") => {
+            ("405 Method Not Allowed", error_json("Method not allowed"))
+        }
+        _ => ("404 Not Found", error_json("Not found")),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn ram_response(chip8: &Chip8, query: &str) -> (&'static str, String) {
+    let mut start = Some(0);
+    let mut end = Some(RAM_SIZE);
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        match pair.split_once('=') {
+            Some(("start", value)) => start = parse_number(value),
+            Some(("end", value)) => end = parse_number(value),
+            _ => {}
+        }
+    }
+
+    let (start, end) = match (start, end) {
+        (Some(start), Some(end)) => (start, end),
+        _ => {
+            return (
+                "400 Bad Request",
+                error_json("start and end must be numbers"),
+            )
+        }
+    };
+
+    match chip8.memory_slice(start..end) {
+        Ok(bytes) => (
+            "200 OK",
+            format!(r#"{{"start":{},"bytes":{}}}"#, start, json_list(bytes)),
+        ),
+        Err(err) => ("400 Bad Request", error_json(&err.to_string())),
+    }
+}
+
+fn parse_number(text: &str) -> Option<usize> {
+    match text.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// The registers, timers and stack. RAM is left out, as `/ram` serves it
+fn state_json(state: &Chip8State) -> String {
+    format!(
+        r#"{{"pc":{},"i":{},"sp":{},"dt":{},"st":{},"v":{},"stack":{}}}"#,
+        state.pc,
+        state.i,
+        state.sp,
+        state.dt,
+        state.st,
+        json_list(&state.vx),
+        json_list(&state.stack)
+    )
+}
+
+fn json_list<T: ToString>(values: &[T]) -> String {
+    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    format!("[{}]", values.join(","))
+}
+
+/// Error messages are our own, so only quotes and backslashes need escaping
+fn error_json(message: &str) -> String {
+    let escaped = message.replace('\\', "\\\\").replace('"', "\\\"");
+    format!(r#"{{"error":"{}"}}"#, escaped)
+}
@@ -0,0 +1,114 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+
+/// Returns the mnemonic for an opcode, or None if the word doesn't decode to
+/// a known instruction. Instructions that share a mnemonic (e.g. all the LD
+/// variants) are grouped together.
+pub fn mnemonic(opcode: u16) -> Option<&'static str> {
+    let mnemonic = match opcode >> 12 {
+        0x0 => match opcode {
+            0x00E0 => "CLS",
+            0x00EE => "RET",
+            0x00FE => "LOW",
+            0x00FF => "HIGH",
+            _ => "SYS",
+        },
+        0x1 => "JP",
+        0x2 => "CALL",
+        0x3 => "SE",
+        0x4 => "SNE",
+        0x5 if opcode & 0x000F == 0x0 => "SE",
+        0x6 => "LD",
+        0x7 => "ADD",
+        0x8 => match opcode & 0x000F {
+            0x0 => "LD",
+            0x1 => "OR",
+            0x2 => "AND",
+            0x3 => "XOR",
+            0x4 => "ADD",
+            0x5 => "SUB",
+            0x6 => "SHR",
+            0x7 => "SUBN",
+            0xE => "SHL",
+            _ => return None,
+        },
+        0x9 if opcode & 0x000F == 0x0 => "SNE",
+        0xA => "LD",
+        0xB => "JP",
+        0xC => "RND",
+        0xD => "DRW",
+        0xE => match opcode & 0x00FF {
+            0x9E => "SKP",
+            0xA1 => "SKNP",
+            _ => return None,
+        },
+        0xF => match opcode & 0x00FF {
+            0x07 | 0x0A | 0x15 | 0x18 | 0x29 | 0x30 | 0x33 | 0x55 | 0x65 => "LD",
+            0x1E => "ADD",
+            0x01 => "PLANE",
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(mnemonic)
+}
+
+/// True if the opcode is part of the original COSMAC VIP instruction set,
+/// rather than a SCHIP or XO-CHIP extension. Useful as
+/// `Config::allowed_opcodes` to hold a ROM to classic CHIP-8.
+pub fn is_classic(opcode: u16) -> bool {
+    match opcode {
+        0x00FE | 0x00FF => false,
+        _ if opcode & 0xF00F == 0xD000 => false,
+        _ if opcode & 0xF0FF == 0xF030 || opcode & 0xF0FF == 0xF001 => false,
+        _ => true,
+    }
+}
+
+/// Disassembles a single opcode into assembly text, e.g. `LD V1, 0x2A`.
+/// Words that aren't valid instructions are shown as a data word `DW`.
+pub fn disassemble(opcode: u16) -> String {
+    let nnn = opcode & 0x0FFF;
+    let x = (opcode & 0x0F00) >> 8;
+    let y = (opcode & 0x00F0) >> 4;
+    let kk = opcode & 0x00FF;
+    let n = opcode & 0x000F;
+
+    let mnemonic = match mnemonic(opcode) {
+        Some(mnemonic) => mnemonic,
+        None => return format!("DW {:#06X}", opcode),
+    };
+
+    match opcode >> 12 {
+        0x0 if mnemonic == "SYS" => format!("SYS {:#05X}", nnn),
+        0x0 => mnemonic.to_string(),
+        0x1 | 0x2 => format!("{} {:#05X}", mnemonic, nnn),
+        0x3 | 0x4 | 0x6 | 0x7 => format!("{} V{:X}, {:#04X}", mnemonic, x, kk),
+        0x5 | 0x9 => format!("{} V{:X}, V{:X}", mnemonic, x, y),
+        0x8 => match n {
+            // Vy is only used by the COSMAC VIP shift, but it is kept
+            // whenever it is set so the listing reassembles to the same bytes
+            0x6 | 0xE if y == 0 => format!("{} V{:X}", mnemonic, x),
+            _ => format!("{} V{:X}, V{:X}", mnemonic, x, y),
+        },
+        0xA => format!("LD I, {:#05X}", nnn),
+        0xB => format!("JP V0, {:#05X}", nnn),
+        0xC => format!("RND V{:X}, {:#04X}", x, kk),
+        0xD => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        0xE => format!("{} V{:X}", mnemonic, x),
+        _ => match kk {
+            0x01 => format!("PLANE {}", x),
+            0x07 => format!("LD V{:X}, DT", x),
+            0x0A => format!("LD V{:X}, K", x),
+            0x15 => format!("LD DT, V{:X}", x),
+            0x18 => format!("LD ST, V{:X}", x),
+            0x1E => format!("ADD I, V{:X}", x),
+            0x29 => format!("LD F, V{:X}", x),
+            0x30 => format!("LD HF, V{:X}", x),
+            0x33 => format!("LD B, V{:X}", x),
+            0x55 => format!("LD [I], V{:X}", x),
+            _ => format!("LD V{:X}, [I]", x),
+        },
+    }
+}
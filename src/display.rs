@@ -1,57 +1,595 @@
-use minifb::{Scale, Window, WindowOptions};
-use std::time::Duration;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use minifb::{Key, KeyRepeat, Scale, Window, WindowOptions};
 
-const DISPLAY_WIDTH: usize = 64;
-const DISPLAY_HEIGHT: usize = 32;
-const FRAME_TIME: Duration = Duration::from_micros(16600);
-const COLOR_EMPTY: u32 = 0x000000;
-const COLOR_FILLED: u32 = 0xFFFFFF;
+use crate::Chip8Error;
 
-pub struct Display {
-    pixels: [u32; DISPLAY_WIDTH * DISPLAY_HEIGHT],
-    pub window: Window,
+pub const DISPLAY_WIDTH: usize = 64;
+pub const DISPLAY_HEIGHT: usize = 32;
+/// Size of the display in the SCHIP and XO-CHIP high resolution mode
+pub const HIRES_WIDTH: usize = 128;
+pub const HIRES_HEIGHT: usize = 64;
+/// Number of XO-CHIP bit planes
+pub const PLANE_COUNT: usize = 2;
+
+/// Colors for each combination of planes, indexed by the plane bits of a
+/// pixel. With only the first plane in use this is plain black and white
+pub const DEFAULT_PALETTE: [u32; 4] = [0x000000, 0xFFFFFF, 0xAAAAAA, 0x555555];
+
+/// Fills `buffer` with one 0RGB color per pixel, looking each pixel's plane
+/// bits up in `palette`. The buffer is resized to match `pixels`
+pub fn fill_colors(buffer: &mut Vec<u32>, pixels: &[u8], palette: &[u32; 4]) {
+    buffer.resize(pixels.len(), palette[0]);
+    for (color, planes) in buffer.iter_mut().zip(pixels) {
+        *color = palette[*planes as usize & 0x3];
+    }
 }
 
-impl Display {
-    pub fn new() -> Display {
+/// Color that `draw_grid` blends grid lines towards
+pub const GRID_COLOR: u32 = 0x808080;
+
+/// Scales a buffer of colors `width` pixels wide up so each pixel becomes a
+/// `scale` by `scale` square
+pub fn scale_colors(colors: &[u32], width: usize, scale: usize) -> Vec<u32> {
+    let mut scaled = Vec::with_capacity(colors.len() * scale * scale);
+
+    for row in colors.chunks(width) {
+        for _ in 0..scale {
+            for color in row {
+                for _ in 0..scale {
+                    scaled.push(*color);
+                }
+            }
+        }
+    }
+
+    scaled
+}
+
+/// Overlays a faint grid on a buffer that `scale_colors` scaled up, so
+/// each CHIP-8 pixel can be picked out. The top row and left column of
+/// every `scale` by `scale` square are blended halfway to GRID_COLOR.
+/// Nothing is drawn at a scale of 1, where the grid would cover everything
+pub fn draw_grid(buffer: &mut [u32], width: usize, scale: usize) {
+    if scale < 2 {
+        return;
+    }
+
+    for (y, row) in buffer.chunks_mut(width).enumerate() {
+        for (x, color) in row.iter_mut().enumerate() {
+            if x % scale == 0 || y % scale == 0 {
+                *color = ((*color >> 1) & 0x7F7F7F) + ((GRID_COLOR >> 1) & 0x7F7F7F);
+            }
+        }
+    }
+}
+
+/// Encodes a buffer of 0RGB colors `width` by `height` pixels as a binary
+/// PPM image, which ffmpeg and most image tools read without any help
+///
+/// ```
+/// let image = chip8rs::ppm(&[0xFF0000, 0x00FF00], 2, 1);
+/// assert_eq!(image, b"P6\n2 1\n255\n\xFF\x00\x00\x00\xFF\x00");
+/// ```
+pub fn ppm(colors: &[u32], width: usize, height: usize) -> Vec<u8> {
+    let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    image.reserve(colors.len() * 3);
+
+    for color in colors {
+        let [_, r, g, b] = color.to_be_bytes();
+        image.extend_from_slice(&[r, g, b]);
+    }
+
+    image
+}
+
+/// Turns the display into 0RGB colors, e.g. to apply a shader. It is given
+/// whether each pixel is lit on any plane, row by row, along with the width
+/// and height, and must return one color per pixel in the same order
+pub type RenderHook = Box<dyn Fn(&[bool], usize, usize) -> Vec<u32>>;
+
+/// Somewhere the display can be shown, which also supplies the keyboard.
+/// Keys are always reported as minifb keys, whatever the backend. Without
+/// the `std` feature there are no keys; CHIP-8 keys are set with
+/// `Chip8::set_key` instead
+pub trait Backend {
+    fn is_open(&self) -> bool;
+    #[cfg(feature = "std")]
+    fn is_key_down(&self, key: Key) -> bool;
+    /// True only on the frame the key goes down, ignoring key repeat
+    #[cfg(feature = "std")]
+    fn is_key_pressed(&self, key: Key) -> bool;
+    /// Shows a frame. `pixels` holds one entry per CHIP-8 pixel, row by row.
+    /// Bit n of an entry is set if the pixel is lit on plane n, so anything
+    /// non-zero is lit
+    fn present(&mut self, pixels: &[u8], width: usize, height: usize) -> Result<(), Chip8Error>;
+    /// Shows a frame that a render hook has already turned into colors, one
+    /// per pixel. Backends that can't show colors can leave this as it is,
+    /// which shows every pixel that isn't black as lit
+    fn present_colors(
+        &mut self,
+        colors: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Chip8Error> {
+        let pixels: Vec<u8> = colors.iter().map(|color| (*color != 0) as u8).collect();
+        self.present(&pixels, width, height)
+    }
+    /// Shows a short status line, e.g. in the title bar. Backends with
+    /// nowhere to show it can ignore it
+    fn set_title(&mut self, _title: &str) {}
+    /// Handles window events without showing a frame, so keys and closing
+    /// still work on frames where nothing changed. Backends that read
+    /// events some other way can ignore it
+    fn poll_events(&mut self) {}
+}
+
+/// Shows the display in a minifb window
+#[cfg(feature = "std")]
+pub struct WindowBackend {
+    window: Window,
+    buffer: Vec<u32>,
+    palette: [u32; 4],
+    /// Size of a low resolution CHIP-8 pixel when the backend scales frames
+    /// itself to draw a grid over them. None when minifb does the scaling
+    grid_scale: Option<usize>,
+}
+
+#[cfg(feature = "std")]
+impl WindowBackend {
+    /// Opens a window where each CHIP-8 pixel is `scale` pixels wide. Scales
+    /// other than 1, 2, 4, 8, 16 and 32 fall back to 16
+    pub fn new(scale: usize) -> Result<WindowBackend, Chip8Error> {
+        let scale = match scale {
+            1 => Scale::X1,
+            2 => Scale::X2,
+            4 => Scale::X4,
+            8 => Scale::X8,
+            32 => Scale::X32,
+            _ => Scale::X16,
+        };
+
+        WindowBackend::open(DISPLAY_WIDTH, DISPLAY_HEIGHT, scale, None)
+    }
+
+    /// Opens a window the same size as `new`, with a faint grid drawn
+    /// between CHIP-8 pixels. See `draw_grid`
+    pub fn with_grid(scale: usize) -> Result<WindowBackend, Chip8Error> {
+        WindowBackend::open(
+            DISPLAY_WIDTH * scale,
+            DISPLAY_HEIGHT * scale,
+            Scale::X1,
+            Some(scale),
+        )
+    }
+
+    fn open(
+        width: usize,
+        height: usize,
+        scale: Scale,
+        grid_scale: Option<usize>,
+    ) -> Result<WindowBackend, Chip8Error> {
         let window_options = WindowOptions {
-            scale: Scale::X16,
+            scale,
             ..WindowOptions::default()
         };
 
-        let mut window = Window::new(
-            "Chip8-rs - ESC to exit",
-            DISPLAY_WIDTH,
-            DISPLAY_HEIGHT,
-            window_options,
+        let mut window = Window::new("Chip8-rs - ESC to exit", width, height, window_options)
+            .map_err(|_| Chip8Error::DisplayInitFailed)?;
+
+        // Frame pacing is handled by the main loop so it behaves the same
+        // with or without a window, unless limit_fps asks otherwise
+        window.limit_update_rate(None);
+
+        Ok(WindowBackend {
+            window,
+            buffer: vec![DEFAULT_PALETTE[0]; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            palette: DEFAULT_PALETTE,
+            grid_scale,
+        })
+    }
+
+    /// Shows a frame of colors, scaling it up and drawing the grid first
+    /// when there is one
+    fn show(
+        window: &mut Window,
+        grid_scale: Option<usize>,
+        colors: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Chip8Error> {
+        let result = match grid_scale {
+            // High resolution pixels are half the size, so the window stays
+            // the same size
+            Some(grid_scale) => {
+                let scale = (grid_scale * DISPLAY_WIDTH / width).max(1);
+                let mut scaled = scale_colors(colors, width, scale);
+                draw_grid(&mut scaled, width * scale, scale);
+
+                window.update_with_buffer(&scaled, width * scale, height * scale)
+            }
+            None => window.update_with_buffer(colors, width, height),
+        };
+
+        result.map_err(|_| Chip8Error::DisplayUpdateFailed)
+    }
+
+    /// Sets the colors used for each combination of planes. See
+    /// `DEFAULT_PALETTE`
+    pub fn set_palette(&mut self, palette: [u32; 4]) {
+        self.palette = palette;
+    }
+
+    /// Has the window hold presenting to `fps` frames per second, waiting
+    /// in `present` if a frame comes early. 0 removes the limit
+    pub fn limit_fps(&mut self, fps: u32) {
+        let frame_time = match fps {
+            0 => None,
+            fps => Some(std::time::Duration::from_secs(1) / fps),
+        };
+
+        self.window.limit_update_rate(frame_time);
+    }
+}
+
+#[cfg(feature = "std")]
+impl Backend for WindowBackend {
+    fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    fn is_key_down(&self, key: Key) -> bool {
+        self.window.is_key_down(key)
+    }
+
+    fn is_key_pressed(&self, key: Key) -> bool {
+        self.window.is_key_pressed(key, KeyRepeat::No)
+    }
+
+    fn present(&mut self, pixels: &[u8], width: usize, height: usize) -> Result<(), Chip8Error> {
+        // The window stretches whatever size buffer it is given, so switching
+        // resolution doesn't need a new window
+        fill_colors(&mut self.buffer, pixels, &self.palette);
+
+        WindowBackend::show(
+            &mut self.window,
+            self.grid_scale,
+            &self.buffer,
+            width,
+            height,
         )
-        .unwrap_or_else(|err| {
-            panic!("Could not create window: {}", err);
-        });
+    }
 
-        window.limit_update_rate(Some(FRAME_TIME));
+    fn present_colors(
+        &mut self,
+        colors: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Chip8Error> {
+        WindowBackend::show(&mut self.window, self.grid_scale, colors, width, height)
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.window.set_title(title);
+    }
 
+    fn poll_events(&mut self) {
+        self.window.update();
+    }
+}
+
+pub struct Display {
+    /// The back buffer, which is drawn to and checked for collisions. One
+    /// buffer per XO-CHIP plane, each holding the active resolution
+    planes: [Vec<bool>; PLANE_COUNT],
+    /// The front buffer shown by `update`, in the same form as `pixels`.
+    /// Only changes when `present` copies the back buffer over
+    front: Vec<u8>,
+    /// Resolution of the front buffer, which can lag behind the back
+    /// buffer's after a resolution switch
+    front_width: usize,
+    front_height: usize,
+    /// The SCHIP 128x64 mode is active
+    hires: bool,
+    /// Bit n is set if plane n is drawn to and cleared. Only the first plane
+    /// is selected unless a program changes it
+    plane_mask: u8,
+    /// The backend is None when running headless
+    backend: Option<Box<dyn Backend>>,
+    /// Sprites wrap around the edges of the display when true, and are
+    /// clipped when false. Each axis can be set independently
+    wrap_x: bool,
+    wrap_y: bool,
+    /// Colors used by `render_buffer` when there is no render hook
+    palette: [u32; 4],
+    render_hook: Option<RenderHook>,
+    /// Number of times the display has been composed for showing
+    updates: u64,
+    /// Something has changed since the last `update_if_dirty`, so the next
+    /// one needs to show a frame
+    dirty: bool,
+}
+
+impl Display {
+    /// Opens a window where each CHIP-8 pixel is `scale` pixels wide. Scales
+    /// other than 1, 2, 4, 8, 16 and 32 fall back to 16
+    #[cfg(feature = "std")]
+    pub fn new(scale: usize) -> Result<Display, Chip8Error> {
+        Ok(Display::with_backend(Box::new(WindowBackend::new(scale)?)))
+    }
+
+    /// Creates a display that is shown on the given backend
+    pub fn with_backend(backend: Box<dyn Backend>) -> Display {
+        let mut display = Display::headless();
+        display.backend = Some(backend);
+
+        display
+    }
+
+    /// Creates a display without a window. Drawing works as normal, but
+    /// nothing is ever shown and no keys are ever pressed
+    pub fn headless() -> Display {
         Display {
-            pixels: [COLOR_EMPTY; DISPLAY_WIDTH * DISPLAY_HEIGHT],
-            window: window,
+            planes: [
+                vec![false; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+                vec![false; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            ],
+            front: vec![0; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            front_width: DISPLAY_WIDTH,
+            front_height: DISPLAY_HEIGHT,
+            hires: false,
+            plane_mask: 0x1,
+            backend: None,
+            wrap_x: true,
+            wrap_y: true,
+            palette: DEFAULT_PALETTE,
+            render_hook: None,
+            updates: 0,
+            dirty: true,
+        }
+    }
+
+    /// Replaces the backend the display is shown on
+    pub fn set_backend(&mut self, backend: Box<dyn Backend>) {
+        self.backend = Some(backend);
+        self.dirty = true;
+    }
+
+    /// Closes the window or terminal, leaving the display headless
+    pub fn close(&mut self) {
+        self.backend = None;
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.backend.is_none()
+    }
+
+    /// A headless display is always considered open
+    pub fn is_open(&self) -> bool {
+        match &self.backend {
+            Some(backend) => backend.is_open(),
+            None => true,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn is_key_down(&self, key: Key) -> bool {
+        match &self.backend {
+            Some(backend) => backend.is_key_down(key),
+            None => false,
         }
     }
 
-    /// Clears the display
+    /// True only on the frame the key goes down, ignoring key repeat
+    #[cfg(feature = "std")]
+    pub fn is_key_pressed(&self, key: Key) -> bool {
+        match &self.backend {
+            Some(backend) => backend.is_key_pressed(key),
+            None => false,
+        }
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        if let Some(backend) = &mut self.backend {
+            backend.set_title(title);
+        }
+    }
+
+    /// Width of the display in the active resolution
+    pub fn width(&self) -> usize {
+        if self.hires {
+            HIRES_WIDTH
+        } else {
+            DISPLAY_WIDTH
+        }
+    }
+
+    /// Height of the display in the active resolution
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_HEIGHT
+        } else {
+            DISPLAY_HEIGHT
+        }
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    /// Switches between the 64x32 and 128x64 resolutions. Every plane is
+    /// cleared, whatever the plane mask, as the old image can't be kept
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.dirty = true;
+
+        let size = self.width() * self.height();
+        for plane in self.planes.iter_mut() {
+            *plane = vec![false; size];
+        }
+    }
+
+    pub fn plane_mask(&self) -> u8 {
+        self.plane_mask
+    }
+
+    /// Selects which planes are drawn to and cleared. Bit n selects plane n,
+    /// and bits past the last plane are ignored
+    pub fn set_plane_mask(&mut self, plane_mask: u8) {
+        self.plane_mask = plane_mask & 0x3;
+    }
+
+    /// Number of planes selected by the plane mask
+    pub fn selected_planes(&self) -> usize {
+        self.plane_mask.count_ones() as usize
+    }
+
+    /// The lit state of every pixel on one plane, row by row
+    pub fn plane(&self, plane: usize) -> &[bool] {
+        &self.planes[plane]
+    }
+
+    /// The planes each pixel of the back buffer is lit on, row by row. Bit n
+    /// of an entry is set if the pixel is lit on plane n
+    pub fn pixels(&self) -> Vec<u8> {
+        (0..self.width() * self.height())
+            .map(|index| {
+                (0..PLANE_COUNT)
+                    .filter(|plane| self.planes[*plane][index])
+                    .fold(0, |bits, plane| bits | (1 << plane))
+            })
+            .collect()
+    }
+
+    /// Sets whether sprites wrap around (true) or are clipped at (false) the
+    /// horizontal and vertical edges of the display
+    pub fn set_wrapping(&mut self, wrap_x: bool, wrap_y: bool) {
+        self.wrap_x = wrap_x;
+        self.wrap_y = wrap_y;
+    }
+
+    /// Clears the planes selected by the plane mask
     pub fn clear(&mut self) {
-        self.pixels.iter_mut().for_each(|x| *x = COLOR_EMPTY);
+        self.dirty = true;
+        for (index, plane) in self.planes.iter_mut().enumerate() {
+            if self.plane_mask & (1 << index) != 0 {
+                plane.iter_mut().for_each(|x| *x = false);
+            }
+        }
+    }
+
+    /// Number of times `update` has composed the display, including while
+    /// headless
+    pub fn update_count(&self) -> u64 {
+        self.updates
+    }
+
+    /// Sets the colors `render_buffer` uses for each combination of planes
+    pub fn set_palette(&mut self, palette: [u32; 4]) {
+        self.palette = palette;
+        self.dirty = true;
+    }
+
+    /// Has the display turned into colors by `hook` rather than the palette,
+    /// both in `render_buffer` and when shown on the backend
+    pub fn set_render_hook(&mut self, hook: RenderHook) {
+        self.render_hook = Some(hook);
+        self.dirty = true;
+    }
+
+    /// The back buffer as 0RGB colors, one per pixel, row by row. Made by
+    /// the render hook if one is set, and from the palette otherwise
+    pub fn render_buffer(&self) -> Vec<u32> {
+        self.compose(&self.pixels(), self.width(), self.height())
+    }
+
+    /// The back buffer as opaque RGBA8 bytes, row by row, with each pixel
+    /// scaled up to a `scale` by `scale` square. Colors come from
+    /// `render_buffer`, so the render hook is used if one is set
+    pub fn render_rgba(&self, scale: usize) -> Vec<u8> {
+        scale_colors(&self.render_buffer(), self.width(), scale)
+            .iter()
+            .flat_map(|color| {
+                let [_, r, g, b] = color.to_be_bytes();
+                [r, g, b, 0xFF]
+            })
+            .collect()
+    }
+
+    fn compose(&self, pixels: &[u8], width: usize, height: usize) -> Vec<u32> {
+        match &self.render_hook {
+            Some(hook) => {
+                let lit: Vec<bool> = pixels.iter().map(|planes| *planes != 0).collect();
+                hook(&lit, width, height)
+            }
+            None => {
+                let mut buffer = Vec::new();
+                fill_colors(&mut buffer, pixels, &self.palette);
+                buffer
+            }
+        }
+    }
+
+    /// The front buffer, in the same form as `pixels`. This is what `update`
+    /// shows
+    pub fn front_pixels(&self) -> &[u8] {
+        &self.front
+    }
+
+    /// Copies the back buffer to the front, ready for `update` to show.
+    /// Until then draws only change the back buffer, so a half drawn frame
+    /// is never shown
+    pub fn present(&mut self) {
+        self.front = self.pixels();
+        self.front_width = self.width();
+        self.front_height = self.height();
+    }
+
+    /// Composes the front buffer and shows it on the backend
+    pub fn update(&mut self) -> Result<(), Chip8Error> {
+        let (width, height) = (self.front_width, self.front_height);
+        self.updates += 1;
+
+        if self.render_hook.is_some() {
+            let colors = self.compose(&self.front, width, height);
+            if let Some(backend) = &mut self.backend {
+                backend.present_colors(&colors, width, height)?;
+            }
+        } else if let Some(backend) = &mut self.backend {
+            backend.present(&self.front, width, height)?;
+        }
+
+        Ok(())
     }
 
-    pub fn update(&mut self) {
-        self.window
-            .update_with_buffer(&self.pixels, DISPLAY_WIDTH, DISPLAY_HEIGHT)
-            .unwrap();
+    /// True if anything has been drawn, cleared or restyled since the last
+    /// `update_if_dirty`, so showing the display again would change it
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Calls `update` if the display is dirty, and otherwise only lets the
+    /// backend handle its events, to save redrawing an unchanged frame. The
+    /// display is clean afterwards
+    pub fn update_if_dirty(&mut self) -> Result<(), Chip8Error> {
+        if !self.dirty {
+            if let Some(backend) = &mut self.backend {
+                backend.poll_events();
+            }
+            return Ok(());
+        }
+
+        self.dirty = false;
+        self.update()
     }
 
     /// Wraps coordinates around the display in both x and y
-    pub fn get_wrapped_coordinates(x: usize, y: usize) -> (usize, usize) {
-        let x = x.rem_euclid(DISPLAY_WIDTH);
-        let y = y.rem_euclid(DISPLAY_HEIGHT);
+    pub fn get_wrapped_coordinates(&self, x: usize, y: usize) -> (usize, usize) {
+        let x = x.rem_euclid(self.width());
+        let y = y.rem_euclid(self.height());
 
         (x, y)
     }
@@ -59,48 +597,116 @@ impl Display {
     /// Given the coordinates of a pixel on the display, calculate the index of
     // the pixel array. This must be provided with a pre-wrapped value. See
     // get_wrapped_coordinates
-    fn coordinate_to_index(x: usize, y: usize) -> usize {
-        x + (y * DISPLAY_WIDTH)
+    fn coordinate_to_index(&self, x: usize, y: usize) -> usize {
+        x + (y * self.width())
     }
 
-    /// Draws sprite at specified coordinate
-    /// The return value will be true if this draw operation causes any pixel
-    /// to be erased
-    pub fn draw_sprite(&mut self, x: usize, y: usize, sprite_data: &[u8]) -> bool {
-        println!("Drawsprite at ({}, {})", x, y);
-        println!("Sprite Data: {:02X?}", sprite_data);
+    /// Number of rows of a sprite `rows` tall drawn at row `y` that fall off
+    /// the bottom of the display. Always 0 when wrapping vertically
+    pub fn clipped_rows(&self, y: usize, rows: usize) -> usize {
+        if self.wrap_y {
+            return 0;
+        }
 
-        let mut pixels_erased = false;
-        for (i, line) in sprite_data.iter().enumerate() {
+        rows - rows.min(self.height().saturating_sub(y))
+    }
+
+    /// Draws an 8 pixel wide sprite at specified coordinate on each selected
+    /// plane. With more than one plane selected the sprite data holds one
+    /// sprite per plane, one after the other, lowest plane first.
+    /// The return value is the number of pixels that were erased by this draw
+    /// operation
+    pub fn draw_sprite(&mut self, x: usize, y: usize, sprite_data: &[u8]) -> u32 {
+        self.draw_planes(x, y, sprite_data, 1)
+    }
+
+    /// Draws several sprites in turn, as `draw_sprite` would, for building
+    /// up a scene. Each entry is the x and y coordinates and the sprite
+    /// data. Returns true if any of the draws erased a pixel, including one
+    /// lit by an earlier sprite in the same batch
+    pub fn draw_sprites(&mut self, sprites: &[(usize, usize, &[u8])]) -> bool {
+        let mut erased = 0;
+        for (x, y, sprite_data) in sprites {
+            erased += self.draw_sprite(*x, *y, sprite_data);
+        }
+
+        erased > 0
+    }
+
+    /// Draws a 16x16 sprite, as used by Dxy0, on each selected plane. Each
+    /// row is two bytes, so each plane takes 32 bytes of sprite data. A
+    /// trailing odd byte is drawn as the left half of a row.
+    pub fn draw_wide_sprite(&mut self, x: usize, y: usize, sprite_data: &[u8]) -> u32 {
+        self.draw_planes(x, y, sprite_data, 2)
+    }
+
+    fn draw_planes(&mut self, x: usize, y: usize, sprite_data: &[u8], row_bytes: usize) -> u32 {
+        let selected: Vec<usize> = (0..PLANE_COUNT)
+            .filter(|plane| self.plane_mask & (1 << plane) != 0)
+            .collect();
+        if selected.is_empty() {
+            return 0;
+        }
+
+        let plane_size = sprite_data.len() / selected.len();
+        let mut pixels_erased = 0;
+        for (plane, sprite) in selected
+            .into_iter()
+            .zip(sprite_data.chunks(plane_size.max(1)))
+        {
+            pixels_erased += self.draw_on_plane(plane, x, y, sprite, row_bytes);
+        }
+
+        pixels_erased
+    }
+
+    fn draw_on_plane(
+        &mut self,
+        plane: usize,
+        x: usize,
+        y: usize,
+        sprite_data: &[u8],
+        row_bytes: usize,
+    ) -> u32 {
+        let (width, height) = (self.width(), self.height());
+
+        let mut pixels_erased = 0;
+        for (i, line) in sprite_data.chunks(row_bytes).enumerate() {
             let local_y = y + i;
-            for j in 0..8 {
+
+            // When clipping vertically, rows that fall off the bottom of the
+            // display are dropped entirely
+            if !self.wrap_y && local_y >= height {
+                break;
+            }
+
+            // The last row can be short if the data doesn't divide evenly
+            for j in 0..line.len() * 8 {
                 let local_x = x + j;
-                let (wrapped_x, wrapped_y) = Display::get_wrapped_coordinates(local_x, local_y);
-                let pixel_index = Display::coordinate_to_index(wrapped_x, wrapped_y);
+
+                if !self.wrap_x && local_x >= width {
+                    break;
+                }
+
+                let (wrapped_x, wrapped_y) = self.get_wrapped_coordinates(local_x, local_y);
+                let pixel_index = self.coordinate_to_index(wrapped_x, wrapped_y);
 
                 // The selector is a one bit mask that is used to extract the
                 // value of the sprite at this coordinate
-                let selector = 0b1000_0000u8 >> j;
-
-                let sprite_pixel_value = (line & selector) >> (7 - j);
-                let display_pixel_value = self.pixels[pixel_index]; // Maybe make reference
-
-                if sprite_pixel_value == 0x0 && display_pixel_value == COLOR_EMPTY {
-                    self.pixels[pixel_index] = COLOR_EMPTY;
-                } else if sprite_pixel_value == 0x0 && display_pixel_value == COLOR_FILLED {
-                    self.pixels[pixel_index] = COLOR_FILLED;
-                } else if sprite_pixel_value == 0x1 && display_pixel_value == COLOR_EMPTY {
-                    self.pixels[pixel_index] = COLOR_FILLED;
-                } else if sprite_pixel_value == 0x1 && display_pixel_value == COLOR_FILLED {
-                    // I'm pretty sure that the only way this operation would
-                    // erase an existing pixel is if both the sprite value is
-                    // filled and the existing display value is also filled,
-                    // therefor, I've added a check for this case.
-                    pixels_erased = true;
-                    self.pixels[pixel_index] = COLOR_EMPTY;
-                } else {
-                    panic!("No matching condition for drawing pixel. This shouldn't be possible");
+                let selector = 0b1000_0000u8 >> (j % 8);
+
+                let sprite_pixel_value = line[j / 8] & selector != 0;
+                let display_pixel_value = self.planes[plane][pixel_index];
+
+                // The only way this operation can erase an existing pixel is
+                // if both the sprite pixel and the display pixel are filled
+                if sprite_pixel_value && display_pixel_value {
+                    pixels_erased += 1;
+                }
+                if sprite_pixel_value {
+                    self.dirty = true;
                 }
+                self.planes[plane][pixel_index] = sprite_pixel_value ^ display_pixel_value;
             }
         }
 
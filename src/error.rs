@@ -0,0 +1,110 @@
+use core::fmt;
+
+use crate::PROGRAM_START;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chip8Error {
+    /// The fetched word doesn't decode to any supported instruction
+    UnknownOpcode(u16),
+    /// The program counter points outside of RAM
+    PcOutOfBounds(u16),
+    /// The program counter is odd and the strict alignment quirk is enabled
+    MisalignedPc(u16),
+    /// A CALL was made with every stack slot already in use
+    StackOverflow,
+    /// A RET was made with an empty stack
+    StackUnderflow,
+    /// An access went past the end of RAM. Holds the address the access
+    /// started at, or for `memory_slice` the first address outside RAM.
+    /// Addresses can be past 0xFFFF, e.g. with a large I plus an offset, so
+    /// it is a usize
+    MemoryOutOfBounds(usize),
+    /// The ROM doesn't fit in memory. Holds the size of the ROM
+    RomTooLarge(usize),
+    /// The ROM has no bytes, so there is nothing to run
+    EmptyRom,
+    /// The load address is below PROGRAM_START, so loading the ROM would
+    /// overwrite the fonts. Holds the load address
+    RomOverlapsFont(u16),
+    /// The data file would overwrite the fonts or the ROM. Holds the
+    /// address the data was to be loaded at
+    DataOverlaps(u16),
+    /// The data file runs past the end of RAM. Holds the size of the data
+    DataTooLarge(usize),
+    /// `step_back` was called with no earlier state left in the history
+    NoHistory,
+    /// The ROM looks gzip compressed but couldn't be decompressed
+    BadCompressedRom,
+    /// The opcode is one `Config::allowed_opcodes` rejects
+    DisallowedOpcode(u16),
+    /// The backend couldn't be set up, e.g. the window failed to open
+    DisplayInitFailed,
+    /// The backend failed to show a frame
+    DisplayUpdateFailed,
+    /// The bytes aren't a save state, or are cut short
+    BadSaveState,
+    /// The RAM in a save state doesn't match the checksum saved with it, so
+    /// the save state is corrupt
+    ChecksumMismatch,
+    /// A frame couldn't be written to `Config::capture_dir`
+    CaptureFailed,
+    /// The ROM file couldn't be read
+    RomReadFailed,
+    /// The data file couldn't be read
+    DataReadFailed,
+    /// A state's stack pointer is past the end of its stack, or of the
+    /// configured stack it is being restored into
+    InvalidState,
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::UnknownOpcode(opcode) => write!(f, "Invalid Instruction: {:#06X}", opcode),
+            Chip8Error::PcOutOfBounds(pc) => {
+                write!(f, "Program counter out of bounds: {:#06X}", pc)
+            }
+            Chip8Error::MisalignedPc(pc) => write!(f, "Misaligned program counter: {:#06X}", pc),
+            Chip8Error::StackOverflow => write!(f, "Stack overflow"),
+            Chip8Error::StackUnderflow => write!(f, "Stack underflow"),
+            Chip8Error::MemoryOutOfBounds(address) => {
+                write!(f, "Memory access out of bounds: {:#06X}", address)
+            }
+            Chip8Error::RomTooLarge(size) => write!(f, "ROM is too large: {} bytes", size),
+            Chip8Error::RomOverlapsFont(address) => write!(
+                f,
+                "ROM loaded at {:#06X} would overwrite the font, which ends at {:#06X}",
+                address, PROGRAM_START
+            ),
+            Chip8Error::EmptyRom => write!(f, "ROM is empty and has no instructions"),
+            Chip8Error::DataOverlaps(address) => write!(
+                f,
+                "Data loaded at {:#06X} would overwrite the font or the ROM",
+                address
+            ),
+            Chip8Error::DataTooLarge(size) => {
+                write!(f, "Data runs past the end of RAM: {} bytes", size)
+            }
+            Chip8Error::NoHistory => write!(f, "No earlier state to step back to"),
+            Chip8Error::BadCompressedRom => write!(f, "Compressed ROM is corrupt"),
+            Chip8Error::DisallowedOpcode(opcode) => {
+                write!(f, "Instruction not allowed: {:#06X}", opcode)
+            }
+            Chip8Error::DisplayInitFailed => write!(f, "Could not open the display"),
+            Chip8Error::DisplayUpdateFailed => write!(f, "Could not update the display"),
+            Chip8Error::BadSaveState => write!(f, "Not a valid save state"),
+            Chip8Error::ChecksumMismatch => {
+                write!(f, "Save state RAM doesn't match its checksum")
+            }
+            Chip8Error::CaptureFailed => write!(f, "Could not write a captured frame"),
+            Chip8Error::RomReadFailed => write!(f, "Could not read the ROM file"),
+            Chip8Error::DataReadFailed => write!(f, "Could not read the data file"),
+            Chip8Error::InvalidState => {
+                write!(f, "State has a stack pointer past the end of the stack")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Chip8Error {}
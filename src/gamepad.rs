@@ -0,0 +1,162 @@
+//! Gamepad input. A `GamepadSource` reports which buttons are held and a
+//! `GamepadBinding` says which CHIP-8 key each button presses. Together they
+//! make a `Keyboard`, so a gamepad feeds the keypad the same way the window
+//! does, and `Chip8::set_gamepad` ORs the two together every poll.
+//!
+//! Needs the `gamepad` feature. `GilrsSource` reads every connected gamepad
+//! through gilrs, and is what the chip8rs binary uses. Embedders that
+//! already use another library (SDL, ...) can wrap it in their own
+//! `GamepadSource` instead, and tests pass a mock.
+
+use crate::keyboard::Keyboard;
+use gilrs::Gilrs;
+
+/// Gamepad buttons, named by position as on most modern controllers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Button {
+    /// A on Xbox controllers, cross on PlayStation
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// Something that knows which gamepad buttons are held, e.g. a wrapper
+/// around a platform gamepad library, or a mock in tests
+pub trait GamepadSource {
+    /// Called once per poll, before any buttons are read. Sources that
+    /// need to pump events do it here
+    fn update(&mut self) {}
+
+    fn is_pressed(&self, button: Button) -> bool;
+}
+
+/// A `GamepadSource` backed by gilrs. A button counts as pressed if it is
+/// held on any connected gamepad
+pub struct GilrsSource {
+    gilrs: Gilrs,
+}
+
+impl GilrsSource {
+    /// Fails if the platform has no gamepad support. gilrs's error carries
+    /// a whole `Gilrs`, so it is boxed
+    pub fn new() -> Result<GilrsSource, Box<gilrs::Error>> {
+        Ok(GilrsSource {
+            gilrs: Gilrs::new().map_err(Box::new)?,
+        })
+    }
+}
+
+impl GamepadSource for GilrsSource {
+    /// Drains the event queue, which is what updates gilrs's idea of which
+    /// buttons are held
+    fn update(&mut self) {
+        while self.gilrs.next_event().is_some() {}
+    }
+
+    fn is_pressed(&self, button: Button) -> bool {
+        let button = gilrs_button(button);
+        self.gilrs
+            .gamepads()
+            .any(|(_, gamepad)| gamepad.is_pressed(button))
+    }
+}
+
+/// gilrs calls the bumpers triggers and the triggers triggers 2
+fn gilrs_button(button: Button) -> gilrs::Button {
+    match button {
+        Button::South => gilrs::Button::South,
+        Button::East => gilrs::Button::East,
+        Button::North => gilrs::Button::North,
+        Button::West => gilrs::Button::West,
+        Button::LeftBumper => gilrs::Button::LeftTrigger,
+        Button::RightBumper => gilrs::Button::RightTrigger,
+        Button::LeftTrigger => gilrs::Button::LeftTrigger2,
+        Button::RightTrigger => gilrs::Button::RightTrigger2,
+        Button::Select => gilrs::Button::Select,
+        Button::Start => gilrs::Button::Start,
+        Button::LeftThumb => gilrs::Button::LeftThumb,
+        Button::RightThumb => gilrs::Button::RightThumb,
+        Button::DPadUp => gilrs::Button::DPadUp,
+        Button::DPadDown => gilrs::Button::DPadDown,
+        Button::DPadLeft => gilrs::Button::DPadLeft,
+        Button::DPadRight => gilrs::Button::DPadRight,
+    }
+}
+
+/// The button bound to each CHIP-8 key, if any. A button can be bound to
+/// more than one key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GamepadBinding {
+    buttons: [Option<Button>; 16],
+}
+
+impl GamepadBinding {
+    /// A binding with no buttons bound
+    pub fn empty() -> GamepadBinding {
+        GamepadBinding {
+            buttons: [None; 16],
+        }
+    }
+
+    /// Binds `button` to a CHIP-8 key (0x0 - 0xF), replacing whatever the
+    /// key was bound to
+    pub fn bind(mut self, button: Button, key: u8) -> GamepadBinding {
+        self.buttons[(key & 0x0F) as usize] = Some(button);
+        self
+    }
+
+    /// The button bound to a CHIP-8 key (0x0 - 0xF)
+    pub fn button(&self, key: u8) -> Option<Button> {
+        self.buttons[(key & 0x0F) as usize]
+    }
+}
+
+impl Default for GamepadBinding {
+    /// The D-pad on 2, 4, 6 and 8, which most games use for movement, with
+    /// the face buttons on 5, 0, A and B and Start on F
+    fn default() -> GamepadBinding {
+        GamepadBinding::empty()
+            .bind(Button::DPadUp, 0x2)
+            .bind(Button::DPadLeft, 0x4)
+            .bind(Button::DPadRight, 0x6)
+            .bind(Button::DPadDown, 0x8)
+            .bind(Button::South, 0x5)
+            .bind(Button::East, 0x0)
+            .bind(Button::West, 0xA)
+            .bind(Button::North, 0xB)
+            .bind(Button::Start, 0xF)
+    }
+}
+
+/// Reads key presses from a gamepad through a binding
+pub struct GamepadKeyboard<'a> {
+    source: &'a dyn GamepadSource,
+    binding: &'a GamepadBinding,
+}
+
+impl<'a> GamepadKeyboard<'a> {
+    pub fn new(source: &'a dyn GamepadSource, binding: &'a GamepadBinding) -> GamepadKeyboard<'a> {
+        GamepadKeyboard { source, binding }
+    }
+}
+
+impl Keyboard for GamepadKeyboard<'_> {
+    fn is_down(&self, key: u8) -> bool {
+        self.binding
+            .button(key)
+            .is_some_and(|button| self.source.is_pressed(button))
+    }
+}
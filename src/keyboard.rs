@@ -0,0 +1,143 @@
+#[cfg(feature = "std")]
+use minifb::Key;
+
+#[cfg(feature = "std")]
+use crate::Display;
+
+/// The physical key bound to each CHIP-8 key. The keys form a 4x4 block on
+/// the left of a QWERTY keyboard laid out like the COSMAC VIP keypad:
+///
+/// ```text
+/// 1 2 3 4        1 2 3 C
+/// Q W E R   ->   4 5 6 D
+/// A S D F        7 8 9 E
+/// Z X C V        A 0 B F
+/// ```
+#[cfg(feature = "std")]
+pub const COSMAC_KEYMAP: [Key; 16] = [
+    Key::X,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Q,
+    Key::W,
+    Key::E,
+    Key::A,
+    Key::S,
+    Key::D,
+    Key::Z,
+    Key::C,
+    Key::Key4,
+    Key::R,
+    Key::F,
+    Key::V,
+];
+
+/// Binds each CHIP-8 key to the key with the same label, so 0-9 are the
+/// number keys and A-F are the letter keys
+#[cfg(feature = "std")]
+pub const NUMERIC_KEYMAP: [Key; 16] = [
+    Key::Key0,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+];
+
+/// A source of CHIP-8 key presses
+pub trait Keyboard {
+    /// Returns true if the given CHIP-8 key (0x0 - 0xF) is held down
+    fn is_down(&self, key: u8) -> bool;
+}
+
+/// State of the sixteen key CHIP-8 keypad
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Keypad {
+    keys: [bool; 16],
+}
+
+impl Keypad {
+    pub fn new() -> Keypad {
+        Keypad::default()
+    }
+
+    /// Builds a keypad from a bitmask where bit n is set if key n is down
+    pub fn from_bits(bits: u16) -> Keypad {
+        let mut keypad = Keypad::new();
+
+        for key in 0..16 {
+            keypad.keys[key] = bits & (1 << key) != 0;
+        }
+
+        keypad
+    }
+
+    /// Packs the keypad into a bitmask where bit n is set if key n is down
+    pub fn bits(&self) -> u16 {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter(|(_, down)| **down)
+            .fold(0, |bits, (key, _)| bits | (1 << key))
+    }
+
+    pub fn set(&mut self, key: u8, down: bool) {
+        self.keys[key as usize] = down;
+    }
+
+    /// A keypad with every key that is down on either keypad
+    pub fn union(&self, other: &Keypad) -> Keypad {
+        Keypad::from_bits(self.bits() | other.bits())
+    }
+
+    /// Returns the lowest key that is down now but was up in `previous`
+    pub fn newly_pressed(&self, previous: &Keypad) -> Option<u8> {
+        (0..16).find(|&key| self.keys[key as usize] && !previous.keys[key as usize])
+    }
+
+    /// Copies the state of every key from another keyboard
+    pub fn read_from(&mut self, keyboard: &dyn Keyboard) {
+        for key in 0..16 {
+            self.keys[key as usize] = keyboard.is_down(key);
+        }
+    }
+}
+
+impl Keyboard for Keypad {
+    fn is_down(&self, key: u8) -> bool {
+        self.keys[key as usize]
+    }
+}
+
+/// Reads key presses from a minifb window. The keymap translates each CHIP-8
+/// key to the physical key it is bound to
+#[cfg(feature = "std")]
+pub struct WindowKeyboard<'a> {
+    display: &'a Display,
+    keymap: &'a [Key],
+}
+
+#[cfg(feature = "std")]
+impl<'a> WindowKeyboard<'a> {
+    pub fn new(display: &'a Display, keymap: &'a [Key]) -> WindowKeyboard<'a> {
+        WindowKeyboard { display, keymap }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Keyboard for WindowKeyboard<'_> {
+    fn is_down(&self, key: u8) -> bool {
+        self.display.is_key_down(self.keymap[key as usize])
+    }
+}
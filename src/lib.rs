@@ -1,8 +1,73 @@
+//! A CHIP-8 interpreter. The interpreter core only needs `alloc`, so with
+//! default features turned off it builds as `no_std`. The `std` feature (on
+//! by default) adds the window, ROM files, replays and the assembler.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod cheat;
+pub use self::cheat::{parse_cheats, Cheat, CheatError};
+
+mod chip8;
+pub use self::chip8::{Chip8, ExecEvent, ReservedDraw, RunOutcome, StepOutcome, UninitRead};
+
 mod config;
-pub use self::config::Config;
+pub use self::config::{BackendKind, Config, ConfigBuilder};
+
+mod error;
+pub use self::error::Chip8Error;
 
 mod display;
-pub use self::display::Display;
+#[cfg(feature = "std")]
+pub use self::display::WindowBackend;
+pub use self::display::{
+    draw_grid, fill_colors, ppm, scale_colors, Backend, Display, RenderHook, DEFAULT_PALETTE,
+    DISPLAY_HEIGHT, DISPLAY_WIDTH, GRID_COLOR, HIRES_HEIGHT, HIRES_WIDTH, PLANE_COUNT,
+};
+
+#[cfg(feature = "gamepad")]
+mod gamepad;
+#[cfg(feature = "gamepad")]
+pub use self::gamepad::{Button, GamepadBinding, GamepadKeyboard, GamepadSource, GilrsSource};
+
+mod hash;
+
+mod keyboard;
+pub use self::keyboard::{Keyboard, Keypad};
+#[cfg(feature = "std")]
+pub use self::keyboard::{WindowKeyboard, COSMAC_KEYMAP, NUMERIC_KEYMAP};
+
+mod quirks;
+pub use self::quirks::{MemoryIncrement, Quirks};
 
 mod ram;
-pub use self::ram::Ram;
+pub use self::ram::{
+    big_font_address, font_address, AccessKind, FontLayout, MemoryAccess, Ram, ACCESS_LOG_LIMIT,
+    BIG_FONT_ADDRESS, BIG_FONT_STRIDE, FONT, FONT_SIZE, PROGRAM_START, RAM_SIZE, SPLASH_ROM,
+};
+
+mod random;
+pub use self::random::{FixedSequence, RandomSource, SeededRandom};
+
+#[cfg(feature = "std")]
+mod replay;
+#[cfg(feature = "std")]
+pub use self::replay::{Replay, ReplayFrame};
+
+mod state;
+pub use self::state::{Chip8Diff, Chip8State};
+
+#[cfg(feature = "std")]
+pub mod asm;
+#[cfg(feature = "chip8x")]
+pub mod chip8x;
+#[cfg(feature = "debug-server")]
+pub mod debug_server;
+pub mod disasm;
+#[cfg(feature = "std")]
+pub mod lint;
+pub mod romdb;
+pub mod selftest;
+pub mod timing;
+pub mod tui;
@@ -0,0 +1,202 @@
+//! Looks for mistakes in a ROM without running it. The linter follows every
+//! path through the code from PROGRAM_START, so data that is never executed
+//! isn't mistaken for bad instructions.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::disasm;
+use crate::PROGRAM_START;
+
+/// Deepest the call stack can go before CALL overflows it
+const STACK_SIZE: u8 = 16;
+
+/// What looks wrong at an address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    /// The word doesn't decode to an instruction this interpreter runs
+    UnknownOpcode(u16),
+    /// A JP to an address outside the ROM
+    JumpOutOfRom(u16),
+    /// A CALL to an address outside the ROM
+    CallOutOfRom(u16),
+    /// A DRW that can be reached without I having been set
+    DrawWithoutI,
+    /// A CALL that can be reached with the stack already full
+    StackOverflow,
+    /// A RET that can be reached with nothing on the stack
+    StackUnderflow,
+}
+
+/// A problem found by `lint_rom`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LintWarning {
+    /// Address of the instruction, as loaded at PROGRAM_START
+    pub address: u16,
+    pub kind: LintKind,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#06X}: ", self.address)?;
+
+        match self.kind {
+            LintKind::UnknownOpcode(opcode) => write!(f, "Unknown opcode {:#06X}", opcode),
+            LintKind::JumpOutOfRom(target) => {
+                write!(f, "Jump to {:#06X}, outside the ROM", target)
+            }
+            LintKind::CallOutOfRom(target) => {
+                write!(f, "Call to {:#06X}, outside the ROM", target)
+            }
+            LintKind::DrawWithoutI => write!(f, "Sprite drawn before I is set"),
+            LintKind::StackOverflow => write!(f, "Call could overflow the stack"),
+            LintKind::StackUnderflow => write!(f, "Return with an empty stack"),
+        }
+    }
+}
+
+/// The state the linter tracks along each path through the code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Path {
+    address: u16,
+    i_set: bool,
+    depth: u8,
+}
+
+/// Checks a ROM for suspicious code and returns the warnings, sorted by
+/// address.
+///
+/// Only code reachable from PROGRAM_START is checked. Reachability is
+/// followed through jumps, calls and both sides of every skip, but not
+/// through `JP V0, nnn`, since its target depends on V0.
+///
+/// ```
+/// use chip8rs::asm::assemble;
+/// use chip8rs::lint::{lint_rom, LintKind};
+///
+/// let rom = assemble("DRW V0, V1, 5\nhalt: JP halt").unwrap();
+/// let warnings = lint_rom(&rom);
+///
+/// assert_eq!(warnings.len(), 1);
+/// assert_eq!(warnings[0].address, 0x200);
+/// assert_eq!(warnings[0].kind, LintKind::DrawWithoutI);
+/// ```
+pub fn lint_rom(bytes: &[u8]) -> Vec<LintWarning> {
+    let start = PROGRAM_START as u16;
+    let end = PROGRAM_START + bytes.len();
+    let in_rom = |address: u16| address >= start && (address as usize) < end;
+
+    let mut warnings = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![Path {
+        address: start,
+        i_set: false,
+        depth: 0,
+    }];
+
+    while let Some(path) = pending.pop() {
+        // A path that reaches an address already seen in the same state
+        // would find nothing new
+        if !visited.insert(path) {
+            continue;
+        }
+
+        let offset = (path.address - start) as usize;
+        if offset + 1 >= bytes.len() {
+            continue;
+        }
+
+        let opcode = u16::from_be_bytes([bytes[offset], bytes[offset + 1]]);
+        let nnn = opcode & 0x0FFF;
+        let next = Path {
+            address: path.address + 2,
+            ..path
+        };
+
+        let mut warn = |kind| {
+            let warning = LintWarning {
+                address: path.address,
+                kind,
+            };
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        };
+
+        // SYS calls are ignored at best, so reaching one usually means data
+        // is being run as code
+        if let Some("SYS") | None = disasm::mnemonic(opcode) {
+            warn(LintKind::UnknownOpcode(opcode));
+            continue;
+        }
+
+        match opcode >> 12 {
+            0x0 if opcode == 0x00EE => {
+                if path.depth == 0 {
+                    warn(LintKind::StackUnderflow);
+                }
+            }
+            0x1 => {
+                if !in_rom(nnn) {
+                    warn(LintKind::JumpOutOfRom(nnn));
+                } else if nnn != path.address {
+                    pending.push(Path {
+                        address: nnn,
+                        ..path
+                    });
+                }
+            }
+            0x2 => {
+                if !in_rom(nnn) {
+                    warn(LintKind::CallOutOfRom(nnn));
+                } else if path.depth >= STACK_SIZE {
+                    warn(LintKind::StackOverflow);
+                } else {
+                    pending.push(Path {
+                        address: nnn,
+                        depth: path.depth + 1,
+                        ..path
+                    });
+                }
+
+                // Paths end at RET rather than returning to their caller, so
+                // the subroutine is given the benefit of the doubt that it
+                // might have set I
+                pending.push(Path {
+                    i_set: true,
+                    ..next
+                });
+            }
+            0x3 | 0x4 | 0x5 | 0x9 | 0xE => {
+                pending.push(next);
+                pending.push(Path {
+                    address: path.address + 4,
+                    ..path
+                });
+            }
+            0xA => pending.push(Path {
+                i_set: true,
+                ..next
+            }),
+            0xB => {}
+            0xD => {
+                if !path.i_set {
+                    warn(LintKind::DrawWithoutI);
+                }
+
+                pending.push(next);
+            }
+            // Fx29 and Fx30 point I at a font character
+            0xF if matches!(opcode & 0x00FF, 0x29 | 0x30) => {
+                pending.push(Path {
+                    i_set: true,
+                    ..next
+                });
+            }
+            _ => pending.push(next),
+        }
+    }
+
+    warnings.sort_by_key(|warning| warning.address);
+    warnings
+}
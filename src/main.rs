@@ -2,33 +2,362 @@ extern crate minifb;
 
 use minifb::Key;
 use std::env;
+use std::fs;
+use std::io;
 use std::io::stdout;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use chip8rs::Config;
-
-mod chip8;
-use chip8::Chip8;
+#[cfg(feature = "debug-server")]
+use chip8rs::debug_server::DebugServer;
+use chip8rs::selftest;
+use chip8rs::timing::{self, FrameWait, RateMeter, SyncMode};
+use chip8rs::{BackendKind, Chip8, Chip8Error, Config, Replay};
+#[cfg(feature = "gamepad")]
+use chip8rs::{GamepadBinding, GilrsSource};
 
 fn main() {
-    let config = Config::new(env::args()).unwrap_or_else(|err| {
+    let mut config = Config::new(env::args()).unwrap_or_else(|err| {
         eprintln!("Problem parsing arguments: {}", err);
         process::exit(1);
     });
 
-    let mut chip8 = Chip8::new(&config);
+    if config.selftest {
+        run_selftest(&config);
+    }
+
+    let roms = match &config.rom_dir {
+        Some(rom_dir) => list_roms(Path::new(rom_dir)).unwrap_or_else(|err| {
+            eprintln!("Problem reading ROM directory: {}", err);
+            process::exit(1);
+        }),
+        None => Vec::new(),
+    };
+
+    if config.embedded_rom {
+        eprintln!("No ROM given, so running the built in demo. Pass a ROM path to run a game");
+    } else if config.rom_path.is_empty() {
+        match roms.first() {
+            Some(rom) => config.rom_path = rom.to_string_lossy().into_owned(),
+            None => {
+                eprintln!("No ROMs found in the ROM directory");
+                process::exit(1);
+            }
+        }
+    }
+
+    // Index of the running ROM in the directory listing, if it is in there
+    let mut rom_index = roms
+        .iter()
+        .position(|rom| rom == Path::new(&config.rom_path));
+
+    let mut chip8 = Chip8::new(&config).unwrap_or_else(|err| {
+        eprintln!("Problem starting the emulator: {}", err);
+        process::exit(1);
+    });
+    warn_about_entry_point(&chip8, &config);
+
+    if let Some(replay_path) = &config.replay_path {
+        let replay = Replay::load(Path::new(replay_path)).unwrap_or_else(|err| {
+            eprintln!("Problem loading replay: {}", err);
+            process::exit(1);
+        });
+
+        chip8.start_playback(replay);
+    }
+
+    if let Some(record_path) = &config.record_path {
+        chip8.start_recording(Path::new(record_path));
+    }
+
+    // Without gamepad support the keyboard still works, so this isn't fatal
+    #[cfg(feature = "gamepad")]
+    match GilrsSource::new() {
+        Ok(source) => chip8.set_gamepad(Box::new(source), GamepadBinding::default()),
+        Err(err) => eprintln!("Problem reading gamepads: {}", err),
+    }
+
+    // The terminal backend draws over stdout, so the state dumps are left out
+    let show_state = config.headless || config.backend != BackendKind::Terminal;
+
+    // Vsync waits on the window, so without one the loop sleeps instead
+    let sync_mode = match config.sync_mode {
+        SyncMode::Vsync if config.headless || config.backend != BackendKind::Window => {
+            SyncMode::Sleep
+        }
+        sync_mode => sync_mode,
+    };
+
+    #[cfg(feature = "debug-server")]
+    let debug_server = config.debug_server.as_ref().map(|address| {
+        DebugServer::bind(address.as_str()).unwrap_or_else(|err| {
+            eprintln!("Problem starting the debug server: {}", err);
+            process::exit(1);
+        })
+    });
+
+    let mut last_frame = Instant::now();
+    let mut leftover = Duration::from_secs(0);
+    let mut rate_meter = RateMeter::new();
+    let mut warnings_shown = WarningsShown::default();
 
     while chip8.window_is_open() && !chip8.window_is_key_down(Key::Escape) {
-        print!("{}[2J", 27 as char);
-        stdout().flush().expect("Failed to flush stdout");
+        let frame_start = Instant::now();
+
+        // While paused the state report is only printed when stepping, so
+        // it isn't cleared away straight after
+        if show_state && !chip8.is_paused() {
+            print!("{}[2J", 27 as char);
+            stdout().flush().expect("Failed to flush stdout");
+
+            chip8.debug_print_ram();
+            stdout().flush().expect("Failed to flush stdout");
+
+            print!("{}", chip8.state_report());
+            stdout().flush().expect("Failed to flush stdout");
+        }
+
+        chip8.poll_keyboard();
+
+        #[cfg(feature = "debug-server")]
+        if let Some(server) = &debug_server {
+            if let Err(err) = server.poll(&mut chip8) {
+                eprintln!("Debug server error: {}", err);
+            }
+        }
+
+        if config.debug {
+            match chip8.handle_debug_keys() {
+                Ok(true) if show_state => {
+                    print!("{}", chip8.state_report());
+                    stdout().flush().expect("Failed to flush stdout");
+                }
+                Ok(_) => {}
+                // Stepping back past the start of the history isn't fatal
+                Err(err @ Chip8Error::NoHistory) => eprintln!("{}", err),
+                Err(err) => exit_with_error(&mut chip8, err),
+            }
+        }
+
+        // A new ROM starts with nothing to warn about
+        if !roms.is_empty() && handle_rom_keys(&mut chip8, &config, &roms, &mut rom_index) {
+            warnings_shown = WarningsShown::default();
+        }
+
+        // Holding the turbo key runs more instructions per frame. The timers
+        // still follow real time so game logic keeps its pace
+        let multiplier = if chip8.window_is_key_down(Key::Tab) {
+            config.turbo_multiplier
+        } else {
+            1
+        };
+
+        // Run however many instructions are due for the real time that has
+        // passed since the last frame, so slow frames are caught up on
+        let now = Instant::now();
+        let elapsed = now - last_frame;
+        last_frame = now;
+
+        let hz = config.cpu_hz.saturating_mul(multiplier);
+        let mut cycles = timing::cycles_due(elapsed, hz, &mut leftover);
+
+        // Replays run exactly as many instructions as were recorded
+        if let Some(recorded) = chip8.playback_cycles() {
+            cycles = recorded;
+        }
+
+        let cycles_before = chip8.cycle_count();
+        if let Err(err) = chip8.run_for(cycles, elapsed) {
+            exit_with_error(&mut chip8, err);
+        }
+        print_new_warnings(&chip8, &mut warnings_shown);
+
+        if config.show_stats {
+            let cycles_run = chip8.cycle_count() - cycles_before;
+            if let Some(rates) = rate_meter.frame(elapsed, cycles_run) {
+                chip8.set_title(&format!(
+                    "Chip8-rs - {:.0} FPS, {:.0} IPS - ESC to exit",
+                    rates.frames_per_sec, rates.cycles_per_sec
+                ));
+            }
+        }
+
+        // Rendering carries on while paused so the window keeps processing
+        // events
+        if let Err(err) = chip8.render() {
+            shutdown(&mut chip8);
+            eprintln!("Problem drawing the display: {}", err);
+            process::exit(1);
+        }
+
+        match timing::frame_wait(sync_mode, config.target_fps, frame_start.elapsed()) {
+            FrameWait::None => {}
+            FrameWait::Sleep(wait) => thread::sleep(wait),
+            FrameWait::Spin(wait) => spin_for(wait),
+        }
+    }
+
+    shutdown(&mut chip8);
+
+    if config.profile {
+        print_profile(&chip8);
+    }
+}
+
+/// Prints which quirks the interpreter follows, then exits with 0 if they
+/// all match the config and 1 otherwise
+fn run_selftest(config: &Config) -> ! {
+    let results = selftest::run(config.quirks).unwrap_or_else(|err| {
+        eprintln!("Self-test failed to run: {}", err);
+        process::exit(1);
+    });
+
+    println!(
+        "{:<10} {:<12} {:<12} Result",
+        "Quirk", "Expected", "Observed"
+    );
+    for result in &results {
+        println!("{}", result);
+    }
+
+    if results.iter().all(|result| result.passed()) {
+        process::exit(0);
+    } else {
+        process::exit(1);
+    }
+}
+
+/// Lists the time spent on each kind of instruction, slowest first
+fn print_profile(chip8: &Chip8) {
+    println!("{:<6} {:>12} {:>12}", "Opcode", "Time (ms)", "Count");
+    for (mnemonic, time, count) in chip8.profile_report() {
+        println!(
+            "{:<6} {:>12.3} {:>12}",
+            mnemonic,
+            time.as_secs_f64() * 1000.0,
+            count
+        );
+    }
+}
+
+/// Busy-waits for `wait`, for when sleeping wakes up too late
+fn spin_for(wait: Duration) {
+    let start = Instant::now();
+    while start.elapsed() < wait {
+        std::hint::spin_loop();
+    }
+}
+
+/// Lists the files in a ROM directory, sorted by name
+fn list_roms(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut roms = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            roms.push(path);
+        }
+    }
+
+    roms.sort();
+    Ok(roms)
+}
 
-        chip8.debug_print_ram();
-        stdout().flush().expect("Failed to flush stdout");
+/// With a ROM directory, Page Down switches to the next ROM and Page Up to
+/// the previous one, wrapping around at either end. Returns true if a new
+/// ROM was loaded
+fn handle_rom_keys(
+    chip8: &mut Chip8,
+    config: &Config,
+    roms: &[PathBuf],
+    rom_index: &mut Option<usize>,
+) -> bool {
+    let next = if chip8.window_is_key_pressed(Key::PageDown) {
+        rom_index.map_or(0, |index| (index + 1) % roms.len())
+    } else if chip8.window_is_key_pressed(Key::PageUp) {
+        rom_index.map_or(0, |index| (index + roms.len() - 1) % roms.len())
+    } else {
+        return false;
+    };
 
-        chip8.debug_print_registers();
-        stdout().flush().expect("Failed to flush stdout");
+    *rom_index = Some(next);
 
-        chip8.run_instruction();
+    let loaded = fs::read(&roms[next])
+        .map_err(|err| err.to_string())
+        .and_then(|rom| chip8.load_rom(&rom).map_err(|err| err.to_string()));
+    match loaded {
+        Ok(()) => {
+            warn_about_entry_point(chip8, config);
+            true
+        }
+        Err(err) => {
+            eprintln!("Problem loading {}: {}", roms[next].display(), err);
+            false
+        }
     }
 }
+
+/// How many of the machine's warnings have been printed so far
+#[derive(Default)]
+struct WarningsShown {
+    uninit_reads: usize,
+    reserved_draws: usize,
+}
+
+/// Prints the warnings the machine has recorded since the last call
+fn print_new_warnings(chip8: &Chip8, shown: &mut WarningsShown) {
+    let uninit_reads = chip8.uninit_reads();
+    for read in uninit_reads.iter().skip(shown.uninit_reads) {
+        eprintln!(
+            "Warning: V{:X} read at {:#06X} before it was written",
+            read.register, read.pc
+        );
+    }
+    shown.uninit_reads = uninit_reads.len();
+
+    let reserved_draws = chip8.reserved_draws();
+    for draw in reserved_draws.iter().skip(shown.reserved_draws) {
+        eprintln!(
+            "Warning: sprite drawn at {:#06X} from reserved memory at {:#06X}",
+            draw.pc, draw.i
+        );
+    }
+    shown.reserved_draws = reserved_draws.len();
+}
+
+/// Warns if execution starts outside the ROM that was just loaded
+fn warn_about_entry_point(chip8: &Chip8, config: &Config) {
+    if chip8.entry_point_outside_rom() {
+        eprintln!(
+            "Warning: entry point {:#06X} is outside the ROM loaded at {:#06X}",
+            config.entry_point.unwrap_or(config.load_address),
+            config.load_address
+        );
+    }
+}
+
+/// Saves anything still pending and restores the terminal before exiting
+fn shutdown(chip8: &mut Chip8) {
+    chip8.shutdown().unwrap_or_else(|err| {
+        eprintln!("Problem saving recording: {}", err);
+        process::exit(1);
+    });
+}
+
+/// Reports an emulation error along with the code around the PC, then exits
+fn exit_with_error(chip8: &mut Chip8, err: Chip8Error) -> ! {
+    // The terminal backend has to be closed before anything is printed
+    shutdown(chip8);
+
+    eprintln!("Emulation error: {}", err);
+
+    let pc = chip8.state().pc;
+    for (address, instruction) in chip8.disasm_window(4) {
+        let marker = if address == pc { "->" } else { "  " };
+        eprintln!("{} {:#06X}  {}", marker, address, instruction);
+    }
+
+    process::exit(1);
+}
@@ -0,0 +1,204 @@
+/// How far Fx55/Fx65 move I after storing or loading V0 through Vx
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryIncrement {
+    /// I is left unchanged
+    None,
+    /// I ends up pointing at the last register stored or loaded, as on
+    /// CHIP-48
+    X,
+    /// I ends up pointing after the last register stored or loaded, as on
+    /// the COSMAC VIP
+    XPlusOne,
+}
+
+impl MemoryIncrement {
+    /// The amount added to I by Fx55/Fx65 with the given x
+    pub fn amount(self, x: u16) -> u16 {
+        match self {
+            MemoryIncrement::None => 0,
+            MemoryIncrement::X => x,
+            MemoryIncrement::XPlusOne => x + 1,
+        }
+    }
+}
+
+/// Behaviours that differ between CHIP-8 interpreters. ROMs written for one
+/// interpreter may rely on its particular set of quirks.
+///
+/// The default matches the behaviour described in Cowgod's technical
+/// reference. Presets are provided for the common interpreters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// 8xy6/8xyE shift Vx in place. When false Vy is shifted and the result
+    /// stored in Vx, as on the COSMAC VIP
+    pub shift: bool,
+    /// Bnnn jumps to nnn + Vx (where x is the high nibble of nnn) instead of
+    /// nnn + V0
+    pub jump: bool,
+    /// 8xy6/8xyE write the shifted out bit to VF before storing the result
+    /// in Vx, so with x = F the result overwrites the flag. When false the
+    /// flag is written last and wins
+    pub shift_vf_first: bool,
+    /// 8xy1/8xy2/8xy3 reset VF to 0
+    pub vf_reset: bool,
+    /// How Fx55/Fx65 move I
+    pub memory_increment: MemoryIncrement,
+    /// Sprites wrap around the left and right edges of the display. They are
+    /// clipped instead when false
+    pub wrap_x: bool,
+    /// Sprites wrap around the top and bottom edges of the display. They are
+    /// clipped instead when false
+    pub wrap_y: bool,
+    /// Dxyn takes the starting coordinates modulo the display size, so a
+    /// sprite drawn past an edge starts back on the screen. Only the start
+    /// wraps; the sprite's pixels are still clipped or wrapped according to
+    /// wrap_x and wrap_y. When false a sprite starting off the screen is
+    /// clipped away entirely
+    pub wrap_start: bool,
+    /// Dxyn waits for the start of the next frame before execution continues.
+    /// A frame starts with each timer tick, so the wait follows emulated time
+    pub display_wait: bool,
+    /// Fx1E sets VF to 1 when I goes past the end of RAM (0xFFF with the
+    /// default `Config::ram_size`), and 0 otherwise
+    pub i_overflow: bool,
+    /// Fx1E wraps I around at the end of RAM, so it always points into RAM. When
+    /// false I is a full 16 bit register and can be left pointing past the
+    /// end of RAM, in which case instructions that use it fail. VF is set
+    /// by i_overflow before the wrap
+    pub i_wrap: bool,
+    /// Fetching an instruction from an odd address is an error. When false
+    /// (as on most interpreters) the two bytes at the odd address are read
+    /// as normal, so the program carries on one byte out of step with its
+    /// original instruction boundaries
+    pub strict_alignment: bool,
+    /// Sprite rows clipped off the bottom of the display count as a
+    /// collision, so Dxyn sets VF even if nothing was erased. SCHIP 1.1 does
+    /// this. Only has an effect when sprites are clipped vertically
+    pub clipped_rows_collide: bool,
+    /// 0nnn (other than 00E0 and 00EE) is an error instead of being ignored
+    pub strict_sys: bool,
+    /// The stack pointer wraps around the stack, so a CALL with every slot
+    /// in use (`Config::stack_size`, 16 by default) overwrites the first
+    /// return address and a RET with nothing on the stack takes the last
+    /// one. When false these are stack overflow and underflow errors
+    pub wrap_stack: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks {
+            shift: true,
+            jump: false,
+            shift_vf_first: false,
+            vf_reset: false,
+            memory_increment: MemoryIncrement::None,
+            wrap_x: true,
+            wrap_y: true,
+            wrap_start: true,
+            display_wait: false,
+            i_overflow: false,
+            i_wrap: false,
+            strict_alignment: false,
+            clipped_rows_collide: false,
+            strict_sys: false,
+            wrap_stack: false,
+        }
+    }
+}
+
+impl Quirks {
+    /// The original COSMAC VIP interpreter
+    pub fn cosmac() -> Quirks {
+        Quirks {
+            shift: false,
+            jump: false,
+            shift_vf_first: false,
+            vf_reset: true,
+            memory_increment: MemoryIncrement::XPlusOne,
+            wrap_x: false,
+            wrap_y: false,
+            wrap_start: true,
+            display_wait: true,
+            i_overflow: false,
+            i_wrap: false,
+            strict_alignment: false,
+            clipped_rows_collide: false,
+            strict_sys: false,
+            wrap_stack: false,
+        }
+    }
+
+    /// CHIP-48 on the HP-48 calculators
+    pub fn chip48() -> Quirks {
+        Quirks {
+            shift: true,
+            jump: true,
+            shift_vf_first: false,
+            vf_reset: false,
+            memory_increment: MemoryIncrement::X,
+            wrap_x: false,
+            wrap_y: false,
+            wrap_start: true,
+            display_wait: false,
+            i_overflow: false,
+            i_wrap: false,
+            strict_alignment: false,
+            clipped_rows_collide: false,
+            strict_sys: false,
+            wrap_stack: false,
+        }
+    }
+
+    /// SUPER-CHIP 1.1
+    pub fn schip() -> Quirks {
+        Quirks {
+            shift: true,
+            jump: true,
+            shift_vf_first: false,
+            vf_reset: false,
+            memory_increment: MemoryIncrement::None,
+            wrap_x: false,
+            wrap_y: false,
+            wrap_start: true,
+            display_wait: false,
+            i_overflow: false,
+            i_wrap: false,
+            strict_alignment: false,
+            clipped_rows_collide: true,
+            strict_sys: false,
+            wrap_stack: false,
+        }
+    }
+
+    /// XO-CHIP, as implemented by Octo
+    pub fn xochip() -> Quirks {
+        Quirks {
+            shift: false,
+            jump: false,
+            shift_vf_first: false,
+            vf_reset: false,
+            memory_increment: MemoryIncrement::XPlusOne,
+            wrap_x: true,
+            wrap_y: true,
+            wrap_start: true,
+            display_wait: false,
+            i_overflow: false,
+            i_wrap: false,
+            strict_alignment: false,
+            clipped_rows_collide: false,
+            strict_sys: false,
+            wrap_stack: false,
+        }
+    }
+
+    /// Looks up a preset by the name used on the command line
+    pub fn preset(name: &str) -> Option<Quirks> {
+        match name {
+            "cosmac" => Some(Quirks::cosmac()),
+            "chip48" => Some(Quirks::chip48()),
+            "schip" => Some(Quirks::schip()),
+            "xochip" => Some(Quirks::xochip()),
+            _ => None,
+        }
+    }
+}
@@ -1,53 +1,452 @@
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+#[cfg(feature = "std")]
 use std::fs;
+#[cfg(feature = "std")]
 use std::path::Path;
-use std::process;
+
+use crate::romdb;
+use crate::{Chip8Error, Config};
+
+/// Number of bytes of addressable memory
+pub const RAM_SIZE: usize = 4096;
+
+/// Address programs are loaded at and start running from
+pub const PROGRAM_START: usize = 0x200;
+
+/// Number of bytes in the small 4x5 hexadecimal font
+pub const FONT_SIZE: usize = 80;
+
+/// Address of the SCHIP 8x10 font. It sits directly after the small font
+pub const BIG_FONT_ADDRESS: usize = 0x50;
+
+/// Number of bytes used by each character of the big font
+pub const BIG_FONT_STRIDE: usize = 10;
+
+/// Most entries the memory access log holds. When it fills up the oldest
+/// half is dropped, so a long trace keeps the most recent accesses
+pub const ACCESS_LOG_LIMIT: usize = 0x10000;
+
+/// A small demo ROM, run when no ROM is given so the emulator works with no
+/// arguments. Assembled from roms/splash.asm
+pub const SPLASH_ROM: &[u8] = include_bytes!("../roms/splash.ch8");
+
+/// The built in 4x5 hexadecimal font, loaded at address 0. Each character
+/// is 5 bytes, one per row
+pub const FONT: [u8; FONT_SIZE] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+/// Number of bytes in each character of the small font
+const SMALL_FONT_STRIDE: usize = 5;
+
+/// Where the two fonts sit in RAM and how many bytes apart their characters
+/// are. Fx29 and Fx30 both look characters up through it, so moving one
+/// font can't leave the other opcode pointing into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FontLayout {
+    pub small_base: u16,
+    /// At least the 5 bytes of a small character
+    pub small_stride: u16,
+    pub big_base: u16,
+    /// At least the 10 bytes of a big character
+    pub big_stride: u16,
+}
+
+impl FontLayout {
+    /// The small font at 0 with the big font straight after it
+    pub const STANDARD: FontLayout = FontLayout {
+        small_base: 0x0,
+        small_stride: SMALL_FONT_STRIDE as u16,
+        big_base: BIG_FONT_ADDRESS as u16,
+        big_stride: BIG_FONT_STRIDE as u16,
+    };
+
+    /// Address of the small font character for a hex digit. Only the low
+    /// nibble of `digit` is used
+    pub fn small_address(&self, digit: u8) -> u16 {
+        self.small_base + (digit & 0x0F) as u16 * self.small_stride
+    }
+
+    /// Address of the big font character for a hex digit. Only the low
+    /// nibble of `digit` is used
+    pub fn big_address(&self, digit: u8) -> u16 {
+        self.big_base + (digit & 0x0F) as u16 * self.big_stride
+    }
+
+    /// Memory taken up by the small font
+    pub fn small_range(&self) -> Range<usize> {
+        let start = self.small_base as usize;
+        start..start + 15 * self.small_stride as usize + SMALL_FONT_STRIDE
+    }
+
+    /// Memory taken up by the big font
+    pub fn big_range(&self) -> Range<usize> {
+        let start = self.big_base as usize;
+        start..start + 15 * self.big_stride as usize + BIG_FONT_STRIDE
+    }
+
+    /// Checks that characters don't run into each other and that both fonts
+    /// fit below PROGRAM_START without overlapping. The big font is only
+    /// checked when it is loaded
+    pub fn validate(&self, big_font: bool) -> Result<(), &'static str> {
+        if (self.small_stride as usize) < SMALL_FONT_STRIDE {
+            return Err("Small font stride must be at least 5 bytes");
+        }
+
+        if self.small_range().end > PROGRAM_START {
+            return Err("Small font must end below the program");
+        }
+
+        if !big_font {
+            return Ok(());
+        }
+
+        if (self.big_stride as usize) < BIG_FONT_STRIDE {
+            return Err("Big font stride must be at least 10 bytes");
+        }
+
+        if self.big_range().end > PROGRAM_START {
+            return Err("Big font must end below the program");
+        }
+
+        let (small, big) = (self.small_range(), self.big_range());
+        if small.start < big.end && big.start < small.end {
+            return Err("Small and big fonts overlap");
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for FontLayout {
+    fn default() -> FontLayout {
+        FontLayout::STANDARD
+    }
+}
+
+/// Address of the small font character for a hex digit in the standard
+/// layout. Only the low nibble of `digit` is used
+pub fn font_address(digit: u8) -> u16 {
+    FontLayout::STANDARD.small_address(digit)
+}
+
+/// Address of the big font character for a hex digit in the standard
+/// layout. Only the low nibble of `digit` is used
+pub fn big_font_address(digit: u8) -> u16 {
+    FontLayout::STANDARD.big_address(digit)
+}
+
+const BIG_FONT: [u8; 160] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+/// What a memory access was for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    /// Reading an instruction
+    Fetch,
+    /// Reading sprite data for a draw
+    Sprite,
+    /// Any other load or store made by an instruction
+    Data,
+}
+
+/// A single byte read from or written to RAM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub address: usize,
+    pub write: bool,
+    pub kind: AccessKind,
+}
+
+/// Decompresses a gzip compressed ROM, unpacking at most `limit` bytes.
+/// Anything else is returned unchanged
+#[cfg(feature = "flate2")]
+fn decompress(rom: &[u8], limit: usize) -> Result<Cow<'_, [u8]>, Chip8Error> {
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    /// The first two bytes of a gzip file
+    const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+    if !rom.starts_with(&GZIP_MAGIC) {
+        return Ok(Cow::Borrowed(rom));
+    }
+
+    let mut data = Vec::new();
+    GzDecoder::new(rom)
+        .take(limit as u64)
+        .read_to_end(&mut data)
+        .map_err(|_| Chip8Error::BadCompressedRom)?;
+
+    Ok(Cow::Owned(data))
+}
+
+/// Without the `flate2` feature ROMs are always loaded as they are
+#[cfg(not(feature = "flate2"))]
+fn decompress(rom: &[u8], _limit: usize) -> Result<Cow<'_, [u8]>, Chip8Error> {
+    Ok(Cow::Borrowed(rom))
+}
 
 pub struct Ram {
-    memory: [u8; 4096],
+    /// `Config::ram_size` bytes long
+    memory: Vec<u8>,
+    /// Only allocated once tracing is enabled, so there is no cost otherwise
+    access_log: Option<Vec<MemoryAccess>>,
+    /// Hash of the ROM that was loaded, if this RAM was built from one
+    rom_hash: Option<u64>,
+    /// Size in bytes of the ROM that was loaded
+    rom_size: Option<usize>,
+    /// Where `load_rom` puts the ROM. See `Config::load_address`
+    load_address: usize,
+    /// The offset and bytes last given to `load_data`, which `load_rom`
+    /// writes again after the new ROM
+    data: Option<(usize, Vec<u8>)>,
 }
 
 impl Ram {
-    pub fn new(rom_path: &str) -> Ram {
+    /// Reads the ROM at `config.rom_path`, or takes SPLASH_ROM if
+    /// `config.embedded_rom` is set, and loads it along with the fonts and
+    /// any data file. Fails if either file can't be read or doesn't fit
+    #[cfg(feature = "std")]
+    pub fn new(config: &Config) -> Result<Ram, Chip8Error> {
         // Read ROM data
-        let rom_path = Path::new(rom_path);
-        let rom_data = fs::read(rom_path).unwrap_or_else(|err| {
-            eprintln!("Error reading ROM: {}", err);
-            process::exit(1);
-        });
+        let rom_data = if config.embedded_rom {
+            SPLASH_ROM.to_vec()
+        } else {
+            fs::read(Path::new(&config.rom_path)).map_err(|_| Chip8Error::RomReadFailed)?
+        };
+
+        let mut ram = Ram::with_fonts(config);
+        ram.load_rom(&rom_data)?;
+
+        if let Some(data_path) = &config.data_file {
+            let data = fs::read(data_path).map_err(|_| Chip8Error::DataReadFailed)?;
+            ram.load_data(&data, config.data_offset as usize)?;
+        }
+
+        Ok(ram)
+    }
+
+    /// Creates RAM holding only the fonts chosen in the config, ready for
+    /// `load_rom`. With `config.init_font` off the RAM is left zeroed
+    pub fn with_fonts(config: &Config) -> Ram {
+        let mut ram = Ram {
+            memory: vec![0x0; config.ram_size],
+            access_log: None,
+            rom_hash: None,
+            rom_size: None,
+            load_address: config.load_address as usize,
+            data: None,
+        };
+
+        if !config.init_font {
+            return ram;
+        }
 
+        // A user supplied font replaces the built in one
+        let font = config.font_bytes.as_deref().unwrap_or(&FONT);
+        let layout = config.font_layout;
+        for (digit, character) in font.chunks(SMALL_FONT_STRIDE).enumerate() {
+            ram.write_data(layout.small_address(digit as u8) as usize, character);
+        }
+
+        if config.big_font {
+            for (digit, character) in BIG_FONT.chunks(BIG_FONT_STRIDE).enumerate() {
+                ram.write_data(layout.big_address(digit as u8) as usize, character);
+            }
+        }
+
+        ram
+    }
+
+    /// Creates RAM holding a copy of the given memory. Missing bytes are
+    /// zeroed and anything past RAM_SIZE is ignored
+    pub fn from_memory(memory: &[u8]) -> Ram {
+        Ram::from_memory_sized(memory, RAM_SIZE)
+    }
+
+    /// Like `from_memory`, with `size` bytes of RAM instead of RAM_SIZE
+    pub fn from_memory_sized(memory: &[u8], size: usize) -> Ram {
         let mut ram = Ram {
-            memory: [0x0; 4096],
+            memory: vec![0x0; size],
+            access_log: None,
+            rom_hash: None,
+            rom_size: None,
+            load_address: PROGRAM_START,
+            data: None,
         };
 
-        // Initialize Sprites
-        ram.write_data(
-            0x0,
-            &[
-                0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-                0x20, 0x60, 0x20, 0x20, 0x70, // 1
-                0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-                0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-                0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-                0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-                0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-                0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-                0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-                0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-                0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-                0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-                0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-                0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-                0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-                0xF0, 0x80, 0xF0, 0x80, 0x80, // F
-            ],
-        );
-
-        // Load ROM
-        ram.write_data(0x200, &rom_data);
+        let size = memory.len().min(size);
+        ram.write_data(0x0, &memory[..size]);
 
         ram
     }
 
+    /// Replaces the program with a new ROM, loaded at the load address.
+    /// Everything from PROGRAM_START up is cleared first so nothing of the
+    /// previous program is left behind, while the fonts below it are kept.
+    /// A load address below PROGRAM_START is refused, as the ROM would
+    /// overwrite the fonts. Any data from `load_data` is written again, so
+    /// a ROM that would overlap it is refused too.
+    ///
+    /// With the `flate2` feature, gzip compressed ROMs are decompressed
+    /// first.
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), Chip8Error> {
+        // Reading one byte more than fits is enough to reject the ROM as too
+        // large below, without unpacking all of an oversized file
+        let rom = decompress(rom, self.size() - PROGRAM_START + 1)?;
+        let rom = rom.as_ref();
+
+        // Without this the first fetch would read zeroed memory and report
+        // an unknown opcode, which says nothing about the real problem
+        if rom.is_empty() {
+            return Err(Chip8Error::EmptyRom);
+        }
+
+        if self.load_address < PROGRAM_START {
+            return Err(Chip8Error::RomOverlapsFont(self.load_address as u16));
+        }
+
+        if rom.len() > self.size().saturating_sub(self.load_address) {
+            return Err(Chip8Error::RomTooLarge(rom.len()));
+        }
+
+        let rom_end = self.load_address + rom.len();
+        if let Some((offset, data)) = &self.data {
+            if *offset < rom_end && offset + data.len() > self.load_address {
+                return Err(Chip8Error::DataOverlaps(*offset as u16));
+            }
+        }
+
+        self.memory[PROGRAM_START..]
+            .iter_mut()
+            .for_each(|x| *x = 0x0);
+        self.write_data(self.load_address, rom);
+        self.rom_hash = Some(romdb::rom_hash(rom));
+        self.rom_size = Some(rom.len());
+
+        if let Some((offset, data)) = self.data.take() {
+            self.write_data(offset, &data);
+            self.data = Some((offset, data));
+        }
+
+        Ok(())
+    }
+
+    /// Writes a block of data at `offset`, for ROMs that keep a large table
+    /// in a file of its own. Fails rather than overwriting the fonts or the
+    /// loaded ROM, or running past the end of RAM
+    pub fn load_data(&mut self, data: &[u8], offset: usize) -> Result<(), Chip8Error> {
+        let end = offset + data.len();
+        if end > self.size() {
+            return Err(Chip8Error::DataTooLarge(data.len()));
+        }
+
+        let rom_end = self.load_address + self.rom_size.unwrap_or(0);
+        let overlaps_rom = offset < rom_end && end > self.load_address;
+        if offset < PROGRAM_START || overlaps_rom {
+            return Err(Chip8Error::DataOverlaps(offset as u16));
+        }
+
+        self.write_data(offset, data);
+        self.data = Some((offset, data.to_vec()));
+
+        Ok(())
+    }
+
+    /// `romdb::rom_hash` of the loaded ROM, after decompression. None if the RAM wasn't loaded
+    /// from a ROM file
+    pub fn rom_hash(&self) -> Option<u64> {
+        self.rom_hash
+    }
+
+    /// Address the ROM is loaded at
+    pub fn load_address(&self) -> usize {
+        self.load_address
+    }
+
+    /// Size of the loaded ROM, after decompression. None if the RAM wasn't
+    /// loaded from a ROM file
+    pub fn rom_size(&self) -> Option<usize> {
+        self.rom_size
+    }
+
+    /// Starts recording every access made through `record_access`
+    pub fn enable_access_log(&mut self) {
+        if self.access_log.is_none() {
+            self.access_log = Some(Vec::new());
+        }
+    }
+
+    pub fn access_log(&self) -> Option<&[MemoryAccess]> {
+        self.access_log.as_deref()
+    }
+
+    pub fn clear_access_log(&mut self) {
+        if let Some(log) = &mut self.access_log {
+            log.clear();
+        }
+    }
+
+    /// Records an access to `size` bytes starting at `address`, one entry
+    /// per byte. Does nothing unless the access log is enabled. See
+    /// ACCESS_LOG_LIMIT
+    pub fn record_access(&mut self, address: usize, size: usize, write: bool, kind: AccessKind) {
+        if let Some(log) = &mut self.access_log {
+            if log.len() + size > ACCESS_LOG_LIMIT {
+                log.drain(..ACCESS_LOG_LIMIT / 2);
+            }
+            log.extend((address..address + size).map(|address| MemoryAccess {
+                address,
+                write,
+                kind,
+            }));
+        }
+    }
+
+    /// Bytes of RAM. See `Config::ram_size`
+    pub fn size(&self) -> usize {
+        self.memory.len()
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
     pub fn write_data(&mut self, index: usize, data: &[u8]) {
         for (i, byte) in data.iter().enumerate() {
             self.memory[i + index] = *byte;
@@ -58,17 +457,70 @@ impl Ram {
         &self.memory[index]
     }
 
-    pub fn read_word(&self, index: usize) -> u16 {
-        let bytes = self.read_bytes(index, 2);
+    /// Reads a single byte, failing if it is past the end of RAM
+    pub fn read_byte_checked(&self, index: usize) -> Result<u8, Chip8Error> {
+        match self.memory.get(index) {
+            Some(byte) => Ok(*byte),
+            None => Err(Chip8Error::MemoryOutOfBounds(index)),
+        }
+    }
 
+    /// Writes a single byte, failing if it is past the end of RAM
+    pub fn write_byte(&mut self, index: usize, value: u8) -> Result<(), Chip8Error> {
+        match self.memory.get_mut(index) {
+            Some(byte) => {
+                *byte = value;
+                Ok(())
+            }
+            None => Err(Chip8Error::MemoryOutOfBounds(index)),
+        }
+    }
+
+    /// Reads the word at `index`. CHIP-8 is big-endian, so the byte at
+    /// `index` is the high byte. Panics if the word runs past the end of
+    /// RAM; see `read_word_checked`
+    pub fn read_word(&self, index: usize) -> u16 {
         // Build word from two bytes
-        ((bytes[0] as u16) << 8) | (bytes[1] as u16)
+        ((self.memory[index] as u16) << 8) | (self.memory[index + 1] as u16)
+    }
+
+    /// Reads the big-endian word at `index`, failing if either byte is past
+    /// the end of RAM
+    pub fn read_word_checked(&self, index: usize) -> Result<u16, Chip8Error> {
+        let bytes = self.read_bytes(index, 2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
     }
 
-    pub fn read_bytes(&self, index: usize, size: usize) -> &[u8] {
-        &self.memory[index..index + size]
+    /// Reads `size` bytes starting at `index`. Fails rather than wrapping if
+    /// any of them are past the end of RAM
+    pub fn read_bytes(&self, index: usize, size: usize) -> Result<&[u8], Chip8Error> {
+        match self.memory.get(index..index + size) {
+            Some(bytes) => Ok(bytes),
+            None => Err(Chip8Error::MemoryOutOfBounds(index)),
+        }
+    }
+
+    /// Every byte of RAM along with its address, from 0 up
+    pub fn iter_memory(&self) -> impl Iterator<Item = (usize, u8)> + '_ {
+        self.memory.iter().copied().enumerate()
+    }
+
+    /// The bytes in `range`. Fails with the first address past the end of
+    /// RAM if the range runs off it, or with `range.end` if the range runs
+    /// backwards
+    pub fn memory_slice(&self, range: Range<usize>) -> Result<&[u8], Chip8Error> {
+        if range.start > range.end {
+            return Err(Chip8Error::MemoryOutOfBounds(range.end));
+        }
+
+        let first_outside = range.start.max(self.size());
+        match self.memory.get(range) {
+            Some(bytes) => Ok(bytes),
+            None => Err(Chip8Error::MemoryOutOfBounds(first_outside)),
+        }
     }
 
+    #[cfg(feature = "std")]
     pub fn debug_print_ram(&self) {
         // NOTE: This is set to only show the beginning of ram for testing
         println!("{:02X?}", &self.memory[0..1024]);
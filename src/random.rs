@@ -0,0 +1,61 @@
+use alloc::vec::Vec;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Where RND (Cxkk) gets its random bytes from
+pub trait RandomSource {
+    fn next_byte(&mut self) -> u8;
+}
+
+/// A pseudo random generator. The same seed always gives the same bytes
+pub struct SeededRandom {
+    rng: StdRng,
+}
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> SeededRandom {
+        SeededRandom {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Seeds the generator from the operating system, so every run is
+    /// different
+    #[cfg(feature = "std")]
+    pub fn from_entropy() -> SeededRandom {
+        SeededRandom {
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl RandomSource for SeededRandom {
+    fn next_byte(&mut self) -> u8 {
+        self.rng.gen()
+    }
+}
+
+/// Hands out the given bytes in order, starting again from the first once
+/// they run out. Lets tests decide exactly what RND produces
+pub struct FixedSequence {
+    bytes: Vec<u8>,
+    next: usize,
+}
+
+impl FixedSequence {
+    /// `bytes` must not be empty
+    pub fn new(bytes: Vec<u8>) -> FixedSequence {
+        assert!(!bytes.is_empty(), "FixedSequence needs at least one byte");
+
+        FixedSequence { bytes, next: 0 }
+    }
+}
+
+impl RandomSource for FixedSequence {
+    fn next_byte(&mut self) -> u8 {
+        let byte = self.bytes[self.next];
+        self.next = (self.next + 1) % self.bytes.len();
+
+        byte
+    }
+}
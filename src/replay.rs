@@ -0,0 +1,139 @@
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Identifies a .c8r replay file. The last byte is the format version
+const MAGIC: &[u8; 4] = b"C8R\x01";
+
+/// Everything needed to reproduce a session: the RNG seed, and for each
+/// frame the keys that were held, how many instructions ran and which random
+/// bytes were drawn.
+///
+/// The file format is the magic bytes, followed by the seed as a big endian
+/// u64, followed by each frame in turn. A frame is the key bitmask (u16), the
+/// number of instructions (u32), the number of random bytes (u16) and then
+/// the random bytes themselves. All values are big endian.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replay {
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayFrame {
+    /// Bit n is set if CHIP-8 key n was down during the frame
+    pub keys: u16,
+    /// Number of instructions executed during the frame
+    pub cycles: u32,
+    /// Bytes drawn by RND during the frame, in order
+    pub random: Vec<u8>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Replay {
+        Replay {
+            seed,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        Replay::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// Saves the replay as CSV for spreadsheets and other tools. See
+    /// `to_csv`
+    pub fn save_csv(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_csv())
+    }
+
+    /// Lists the frames as CSV with the columns `frame,keys_bitmask,rng_byte`,
+    /// one row per frame after a header row. The random bytes drawn during a
+    /// frame share its row, separated by spaces, and the column is empty if
+    /// there were none. All numbers are decimal
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("frame,keys_bitmask,rng_byte\n");
+
+        for (index, frame) in self.frames.iter().enumerate() {
+            let random: Vec<String> = frame.random.iter().map(|byte| byte.to_string()).collect();
+            csv += &format!("{},{},{}\n", index, frame.keys, random.join(" "));
+        }
+
+        csv
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&self.seed.to_be_bytes());
+
+        for frame in &self.frames {
+            bytes.extend_from_slice(&frame.keys.to_be_bytes());
+            bytes.extend_from_slice(&frame.cycles.to_be_bytes());
+            bytes.extend_from_slice(&(frame.random.len() as u16).to_be_bytes());
+            bytes.extend_from_slice(&frame.random);
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Replay> {
+        let mut reader = Reader { bytes, position: 0 };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid_data("Not a chip8rs replay file"));
+        }
+
+        let mut replay = Replay::new(u64::from_be_bytes(reader.take_array()?));
+
+        while !reader.is_empty() {
+            let keys = u16::from_be_bytes(reader.take_array()?);
+            let cycles = u32::from_be_bytes(reader.take_array()?);
+            let count = u16::from_be_bytes(reader.take_array()?);
+            let random = reader.take(count as usize)?.to_vec();
+
+            replay.frames.push(ReplayFrame {
+                keys,
+                cycles,
+                random,
+            });
+        }
+
+        Ok(replay)
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn take(&mut self, size: usize) -> io::Result<&'a [u8]> {
+        let end = self.position + size;
+        if end > self.bytes.len() {
+            return Err(invalid_data("Replay file is truncated"));
+        }
+
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
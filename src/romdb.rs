@@ -0,0 +1,86 @@
+//! Known ROMs and the quirks they need. Many games only run correctly on the
+//! interpreter they were written for, so when a ROM is recognised its
+//! recommended quirks are used unless the user chose some explicitly.
+
+use crate::hash;
+use crate::{Config, Quirks};
+
+/// A ROM with known quirk requirements
+pub struct KnownRom {
+    /// `rom_hash` of the exact ROM file
+    pub hash: u64,
+    pub name: &'static str,
+    /// Builds the quirks the ROM needs, usually one of the `Quirks` presets
+    pub quirks: fn() -> Quirks,
+}
+
+/// The built in table. Entries are only added once the ROM has been checked
+/// against the preset, so a wrong guess never overrides the defaults. So far
+/// that means the ROMs in tests/roms, which tests/roms.rs runs with these
+/// presets.
+pub const KNOWN_ROMS: &[KnownRom] = &[
+    // SHR and SHL shift Vx in place
+    KnownRom {
+        hash: 0x65B3_ECE8_8D1B_533D,
+        name: "arithmetic.ch8",
+        quirks: Quirks::schip,
+    },
+    // The last box wraps round the bottom edge
+    KnownRom {
+        hash: 0xA15C_EAF0_DBF6_DE81,
+        name: "drawing.ch8",
+        quirks: Quirks::xochip,
+    },
+    // JP V0 jumps from V0, not Vx
+    KnownRom {
+        hash: 0xEA5E_D9AD_9732_9BF8,
+        name: "jumps.ch8",
+        quirks: Quirks::cosmac,
+    },
+];
+
+/// Hashes a ROM with 64 bit FNV-1a, which is stable between releases
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    hash::fnv1a(rom.iter().copied())
+}
+
+/// Looks a ROM up in the given table
+pub fn find(table: &[KnownRom], hash: u64) -> Option<&KnownRom> {
+    table.iter().find(|rom| rom.hash == hash)
+}
+
+/// Works out which quirks to run a ROM with. Quirks chosen explicitly in the
+/// config always win, then a match in the table, then the config defaults.
+///
+/// ```
+/// use chip8rs::romdb::{self, KnownRom};
+/// use chip8rs::{Config, Quirks};
+///
+/// let rom = [0x00, 0xE0, 0x12, 0x00];
+/// let table = [KnownRom {
+///     hash: romdb::rom_hash(&rom),
+///     name: "Test ROM",
+///     quirks: Quirks::schip,
+/// }];
+///
+/// let config = Config::builder().rom_path("test.ch8").build().unwrap();
+/// let hash = Some(romdb::rom_hash(&rom));
+/// assert_eq!(romdb::select_quirks(&table, hash, &config), Quirks::schip());
+///
+/// let config = Config::builder()
+///     .rom_path("test.ch8")
+///     .quirks(Quirks::cosmac())
+///     .build()
+///     .unwrap();
+/// assert_eq!(romdb::select_quirks(&table, hash, &config), Quirks::cosmac());
+/// ```
+pub fn select_quirks(table: &[KnownRom], hash: Option<u64>, config: &Config) -> Quirks {
+    if config.quirks_explicit {
+        return config.quirks;
+    }
+
+    match hash.and_then(|hash| find(table, hash)) {
+        Some(rom) => (rom.quirks)(),
+        None => config.quirks,
+    }
+}
@@ -0,0 +1,231 @@
+//! Checks which quirks the interpreter actually follows by running a small
+//! built in ROM and reading the answers back off the display. This catches a
+//! quirk that is set in the config but not honoured by an instruction.
+//!
+//! The ROM runs one check per quirk and marks the outcome with a pixel in
+//! row 0 (or by where a sprite ends up):
+//!
+//! ```text
+//! ; shift: SHR V1, V2 shifts V1 in place (2) or shifts V2 into V1 (0)
+//!     LD V1, 4
+//!     LD V2, 1
+//!     SHR V1, V2
+//!     LD V0, 0
+//!     SNE V1, 2
+//!     CALL draw_mark          ; (0, 0) lit when shifting in place
+//! ; jump: JP V0, nnn adds V2 (the high nibble of nnn) instead of V0
+//!     LD V0, 0
+//!     LD V2, 2
+//!     JP V0, jump_base
+//! jump_base:
+//!     JP jump_done
+//!     JP jump_on
+//! jump_on:
+//!     LD V0, 8
+//!     CALL draw_mark          ; (8, 0) lit when Vx is added
+//! jump_done:
+//! ; vf_reset: OR resets VF
+//!     LD VF, 5
+//!     LD V1, 0
+//!     OR V1, V1
+//!     LD V0, 16
+//!     SNE VF, 0
+//!     CALL draw_mark          ; (16, 0) lit when VF was reset
+//! ; memory: how far LD [I], V1 moves I, read back through LD V0, [I]
+//!     LD I, scratch
+//!     LD V0, 0xAA
+//!     LD V1, 0xBB
+//!     LD [I], V1
+//!     LD V0, [I]
+//!     LD V5, V0
+//!     LD V0, 24
+//!     SNE V5, 0xBB
+//!     CALL draw_mark          ; (24, 0) lit when I moved by x
+//!     LD V0, 32
+//!     SNE V5, 0
+//!     CALL draw_mark          ; (32, 0) lit when I moved by x + 1
+//! ; clipping: a row drawn at x = 60 wraps onto the left edge of row 2
+//!     LD I, row
+//!     LD V0, 60
+//!     LD V1, 2
+//!     DRW V0, V1, 1           ; (0, 2) lit when sprites wrap
+//! halt:
+//!     JP halt
+//! draw_mark:
+//!     LD I, mark
+//!     LD V3, 0
+//!     DRW V0, V3, 1
+//!     RET
+//! mark:
+//!     DW 0x8000
+//! row:
+//!     DW 0xFF00
+//! scratch:
+//!     DW 0x0000
+//!     DW 0x0000
+//! ```
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Chip8, Chip8Error, Config, MemoryIncrement, Quirks};
+
+/// The self-check ROM, assembled from the source above to be loaded at
+/// PROGRAM_START
+pub const ROM: [u8; 88] = [
+    0x61, 0x04, 0x62, 0x01, 0x81, 0x26, 0x60, 0x00, 0x41, 0x02, 0x22, 0x48, 0x60, 0x00, 0x62, 0x02,
+    0xB2, 0x12, 0x12, 0x1A, 0x12, 0x16, 0x60, 0x08, 0x22, 0x48, 0x6F, 0x05, 0x61, 0x00, 0x81, 0x11,
+    0x60, 0x10, 0x4F, 0x00, 0x22, 0x48, 0xA2, 0x54, 0x60, 0xAA, 0x61, 0xBB, 0xF1, 0x55, 0xF0, 0x65,
+    0x85, 0x00, 0x60, 0x18, 0x45, 0xBB, 0x22, 0x48, 0x60, 0x20, 0x45, 0x00, 0x22, 0x48, 0xA2, 0x52,
+    0x60, 0x3C, 0x61, 0x02, 0xD0, 0x11, 0x12, 0x46, 0xA2, 0x50, 0x63, 0x00, 0xD0, 0x31, 0x00, 0xEE,
+    0x80, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// Most frames the ROM is given to halt. It needs far fewer, even with
+/// display_wait holding each draw until the next frame
+const MAX_FRAMES: u32 = 100;
+
+/// Instructions run in each frame
+const CYCLES_PER_FRAME: u32 = 100;
+
+/// How one quirk was configured compared with how the interpreter behaved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuirkResult {
+    pub name: &'static str,
+    /// The behaviour the quirks ask for
+    pub expected: &'static str,
+    /// The behaviour the ROM saw
+    pub observed: &'static str,
+}
+
+impl QuirkResult {
+    pub fn passed(&self) -> bool {
+        self.expected == self.observed
+    }
+}
+
+impl fmt::Display for QuirkResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<10} {:<12} {:<12} {}",
+            self.name,
+            self.expected,
+            self.observed,
+            if self.passed() { "PASS" } else { "FAIL" }
+        )
+    }
+}
+
+/// Runs the self-check ROM with `quirks` and returns a result for each
+/// quirk checked
+///
+/// ```
+/// use chip8rs::selftest;
+/// use chip8rs::Quirks;
+///
+/// let results = selftest::run(Quirks::cosmac()).unwrap();
+/// assert!(results.iter().all(|result| result.passed()));
+/// ```
+pub fn run(quirks: Quirks) -> Result<Vec<QuirkResult>, Chip8Error> {
+    let config = Config::builder()
+        .rom_path("selftest")
+        .headless(true)
+        .quirks(quirks)
+        .build()
+        .expect("The self-test config is valid");
+
+    let mut chip8 = Chip8::from_rom(&config, &ROM)?;
+    for _ in 0..MAX_FRAMES {
+        if chip8.is_halted() {
+            break;
+        }
+
+        chip8.run_frame(CYCLES_PER_FRAME)?;
+    }
+
+    Ok(read_results(&chip8, &quirks))
+}
+
+/// Reads the self-check ROM's markers off the display of a machine that has
+/// run it, and compares them with `quirks`
+pub fn read_results(chip8: &Chip8, quirks: &Quirks) -> Vec<QuirkResult> {
+    let lit = |x, y| chip8.is_pixel_lit(x, y);
+
+    let memory = if lit(24, 0) {
+        MemoryIncrement::X
+    } else if lit(32, 0) {
+        MemoryIncrement::XPlusOne
+    } else {
+        MemoryIncrement::None
+    };
+
+    vec![
+        QuirkResult {
+            name: "shift",
+            expected: shift_name(quirks.shift),
+            observed: shift_name(lit(0, 0)),
+        },
+        QuirkResult {
+            name: "jump",
+            expected: jump_name(quirks.jump),
+            observed: jump_name(lit(8, 0)),
+        },
+        QuirkResult {
+            name: "vf_reset",
+            expected: vf_reset_name(quirks.vf_reset),
+            observed: vf_reset_name(lit(16, 0)),
+        },
+        QuirkResult {
+            name: "memory",
+            expected: memory_name(quirks.memory_increment),
+            observed: memory_name(memory),
+        },
+        QuirkResult {
+            name: "clipping",
+            expected: clipping_name(quirks.wrap_x),
+            observed: clipping_name(lit(0, 2)),
+        },
+    ]
+}
+
+fn shift_name(shift: bool) -> &'static str {
+    if shift {
+        "Vx"
+    } else {
+        "Vy"
+    }
+}
+
+fn jump_name(jump: bool) -> &'static str {
+    if jump {
+        "nnn + Vx"
+    } else {
+        "nnn + V0"
+    }
+}
+
+fn vf_reset_name(vf_reset: bool) -> &'static str {
+    if vf_reset {
+        "reset"
+    } else {
+        "kept"
+    }
+}
+
+fn memory_name(memory_increment: MemoryIncrement) -> &'static str {
+    match memory_increment {
+        MemoryIncrement::None => "I",
+        MemoryIncrement::X => "I + x",
+        MemoryIncrement::XPlusOne => "I + x + 1",
+    }
+}
+
+fn clipping_name(wrap_x: bool) -> &'static str {
+    if wrap_x {
+        "wrap"
+    } else {
+        "clip"
+    }
+}
@@ -0,0 +1,209 @@
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+use core::ops::Range;
+
+use crate::hash;
+use crate::{Chip8Error, RAM_SIZE};
+
+/// Identifies a save state. The last byte is the format version
+const MAGIC: &[u8; 4] = b"C8S\x01";
+
+/// Bytes taken by V0-VF, I, PC, SP, DT, ST and the stack depth in a save
+/// state
+const REGISTERS_SIZE: usize = 16 + 2 + 2 + 1 + 1 + 1 + 1;
+
+/// A snapshot of everything that makes up a running machine, apart from the
+/// display. This can be captured from a running machine with `Chip8::state`
+/// and used to build a new one with `Chip8::from_state`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chip8State {
+    pub vx: [u8; 16],
+    pub i: u16,
+    pub pc: u16,
+    pub sp: u8,
+    pub dt: u8,
+    pub st: u8,
+    /// Return addresses, one per stack slot
+    pub stack: Vec<u16>,
+    /// Full contents of RAM. Must be RAM_SIZE bytes long
+    pub memory: Vec<u8>,
+}
+
+impl Chip8State {
+    /// CRC-32 of the full RAM
+    pub fn ram_checksum(&self) -> u32 {
+        hash::crc32(&self.memory)
+    }
+
+    /// Serializes the state as a save state. The header is the magic bytes
+    /// and `ram_checksum` as a big endian u32. After it come V0-VF, I, PC,
+    /// SP, DT, ST, the number of stack slots as a byte, the stack and then
+    /// RAM, with 16 bit values big endian
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&self.ram_checksum().to_be_bytes());
+        bytes.extend_from_slice(&self.vx);
+        bytes.extend_from_slice(&self.i.to_be_bytes());
+        bytes.extend_from_slice(&self.pc.to_be_bytes());
+        bytes.extend_from_slice(&[self.sp, self.dt, self.st, self.stack.len() as u8]);
+        for address in self.stack.iter() {
+            bytes.extend_from_slice(&address.to_be_bytes());
+        }
+        bytes.extend_from_slice(&self.memory);
+
+        bytes
+    }
+
+    /// Reads a save state made by `to_bytes`. Fails with ChecksumMismatch
+    /// if RAM has changed since it was saved, and with InvalidState if the
+    /// stack pointer is past the end of the saved stack
+    pub fn from_bytes(bytes: &[u8]) -> Result<Chip8State, Chip8Error> {
+        let header = MAGIC.len() + 4;
+        if bytes.len() < header + REGISTERS_SIZE || !bytes.starts_with(MAGIC) {
+            return Err(Chip8Error::BadSaveState);
+        }
+
+        let checksum = u32::from_be_bytes(bytes[MAGIC.len()..header].try_into().unwrap());
+        let registers = &bytes[header..];
+        let depth = registers[REGISTERS_SIZE - 1] as usize;
+        let ram_start = REGISTERS_SIZE + depth * 2;
+        if registers.len() != ram_start + RAM_SIZE {
+            return Err(Chip8Error::BadSaveState);
+        }
+
+        let word = |offset: usize| u16::from_be_bytes([registers[offset], registers[offset + 1]]);
+        let stack = (0..depth)
+            .map(|slot| word(REGISTERS_SIZE + slot * 2))
+            .collect();
+
+        let state = Chip8State {
+            vx: registers[..16].try_into().unwrap(),
+            i: word(16),
+            pc: word(18),
+            sp: registers[20],
+            dt: registers[21],
+            st: registers[22],
+            stack,
+            memory: registers[ram_start..].to_vec(),
+        };
+
+        if state.ram_checksum() != checksum {
+            return Err(Chip8Error::ChecksumMismatch);
+        }
+
+        if state.sp as usize > state.stack.len() {
+            return Err(Chip8Error::InvalidState);
+        }
+
+        Ok(state)
+    }
+
+    /// Compares this state against another, listing everything that differs
+    pub fn diff(&self, other: &Chip8State) -> Chip8Diff {
+        let registers = (0..self.vx.len())
+            .filter(|&x| self.vx[x] != other.vx[x])
+            .map(|x| (x, self.vx[x], other.vx[x]))
+            .collect();
+
+        // Slots past the end of the shallower stack count as empty
+        let slot = |stack: &[u16], index: usize| stack.get(index).copied().unwrap_or(0);
+        let stack = (0..self.stack.len().max(other.stack.len()))
+            .filter(|&index| slot(&self.stack, index) != slot(&other.stack, index))
+            .map(|index| (index, slot(&self.stack, index), slot(&other.stack, index)))
+            .collect();
+
+        // Group consecutive differing bytes into ranges to keep the report
+        // short. Memory past the end of the shorter state counts as changed
+        let mut ram: Vec<Range<usize>> = Vec::new();
+        let size = self.memory.len().max(other.memory.len());
+        for addr in 0..size {
+            if self.memory.get(addr) == other.memory.get(addr) {
+                continue;
+            }
+
+            match ram.last_mut() {
+                Some(range) if range.end == addr => range.end += 1,
+                _ => ram.push(addr..addr + 1),
+            }
+        }
+
+        Chip8Diff {
+            registers,
+            stack,
+            ram,
+            pc: changed(self.pc, other.pc),
+            i: changed(self.i, other.i),
+            sp: changed(self.sp, other.sp),
+            dt: changed(self.dt, other.dt),
+            st: changed(self.st, other.st),
+        }
+    }
+}
+
+fn changed<T: PartialEq>(a: T, b: T) -> Option<(T, T)> {
+    if a != b {
+        Some((a, b))
+    } else {
+        None
+    }
+}
+
+/// Everything that differs between two states. Values are listed as
+/// (this, other) pairs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Chip8Diff {
+    /// (register index, this value, other value)
+    pub registers: Vec<(usize, u8, u8)>,
+    /// (stack slot, this value, other value)
+    pub stack: Vec<(usize, u16, u16)>,
+    /// Ranges of RAM addresses whose contents differ
+    pub ram: Vec<Range<usize>>,
+    pub pc: Option<(u16, u16)>,
+    pub i: Option<(u16, u16)>,
+    pub sp: Option<(u8, u8)>,
+    pub dt: Option<(u8, u8)>,
+    pub st: Option<(u8, u8)>,
+}
+
+impl Chip8Diff {
+    /// True when both states were identical
+    pub fn is_empty(&self) -> bool {
+        *self == Chip8Diff::default()
+    }
+}
+
+impl fmt::Display for Chip8Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No differences");
+        }
+
+        if let Some((a, b)) = self.pc {
+            writeln!(f, "PC: {:#06X} -> {:#06X}", a, b)?;
+        }
+        if let Some((a, b)) = self.i {
+            writeln!(f, "I: {:#06X} -> {:#06X}", a, b)?;
+        }
+        if let Some((a, b)) = self.sp {
+            writeln!(f, "SP: {:#04X} -> {:#04X}", a, b)?;
+        }
+        if let Some((a, b)) = self.dt {
+            writeln!(f, "DT: {:#04X} -> {:#04X}", a, b)?;
+        }
+        if let Some((a, b)) = self.st {
+            writeln!(f, "ST: {:#04X} -> {:#04X}", a, b)?;
+        }
+        for (x, a, b) in &self.registers {
+            writeln!(f, "V{:X}: {:#04X} -> {:#04X}", x, a, b)?;
+        }
+        for (slot, a, b) in &self.stack {
+            writeln!(f, "stack[{}]: {:#06X} -> {:#06X}", slot, a, b)?;
+        }
+        for range in &self.ram {
+            writeln!(f, "RAM: {:#06X}..{:#06X}", range.start, range.end)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use core::time::Duration;
+
+/// Rate at which the display is refreshed and the timers count down
+pub const FRAME_RATE: u32 = 60;
+
+pub(crate) const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Calculates how many instructions should be executed in a single frame
+///
+/// The multiplier is used by the turbo key to run the CPU faster than the
+/// configured clock. Pass 1 for normal speed. At least one instruction is
+/// always run so a very low clock speed doesn't stall the emulator.
+pub fn cycles_per_frame(cpu_hz: u32, multiplier: u32) -> u32 {
+    let cycles = cpu_hz.saturating_mul(multiplier) / FRAME_RATE;
+
+    cycles.max(1)
+}
+
+/// How many machine cycles each kind of instruction takes, grouped by the
+/// opcode's high nibble. The frame budget from `cycles_per_frame` or
+/// `cycles_due` is spent against these costs, so expensive instructions
+/// leave room for fewer others. The default costs 1 for everything, which
+/// makes the budget a plain instruction count.
+///
+/// ```
+/// use chip8rs::timing::CostModel;
+///
+/// // On the COSMAC VIP a sprite draw takes far longer than arithmetic
+/// let model = CostModel::flat().with_cost(0xD, 10);
+/// assert_eq!(model.cost(0xD015), 10);
+/// assert_eq!(model.cost(0x7001), 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostModel {
+    costs: [u32; 16],
+}
+
+impl CostModel {
+    /// Every instruction costs 1 cycle
+    pub fn flat() -> CostModel {
+        CostModel { costs: [1; 16] }
+    }
+
+    /// Sets the cost of every opcode whose high nibble is `group`, e.g. 0xD
+    /// for Dxyn. Costs of 0 are treated as 1 so a frame always ends
+    pub fn with_cost(mut self, group: u8, cost: u32) -> CostModel {
+        self.costs[(group & 0x0F) as usize] = cost.max(1);
+        self
+    }
+
+    /// Cycles taken by `opcode`
+    pub fn cost(&self, opcode: u16) -> u32 {
+        self.costs[(opcode >> 12) as usize]
+    }
+}
+
+impl Default for CostModel {
+    fn default() -> CostModel {
+        CostModel::flat()
+    }
+}
+
+/// Longest stretch of time the scheduler will try to catch up on at once. If
+/// a frame takes longer than this (e.g. the window was being dragged) the
+/// extra time is dropped rather than running a huge burst of instructions,
+/// which would only make the next frame slower still.
+pub const MAX_CATCH_UP: Duration = Duration::from_millis(100);
+
+/// Calculates how many instructions are due after `elapsed` real time has
+/// passed, running at `hz` instructions per second
+///
+/// Time that isn't long enough for a whole instruction is carried over in
+/// `leftover` so the total number of instructions tracks real time, even when
+/// frames are uneven.
+pub fn cycles_due(elapsed: Duration, hz: u32, leftover: &mut Duration) -> u32 {
+    let available = (*leftover + elapsed).min(MAX_CATCH_UP);
+    let available_nanos = available.as_nanos();
+    let hz = hz as u128;
+
+    let cycles = available_nanos * hz / NANOS_PER_SEC;
+    let remainder = available_nanos * hz % NANOS_PER_SEC;
+    *leftover = Duration::from_nanos((remainder / hz.max(1)) as u64);
+
+    cycles as u32
+}
+
+/// Works out how long to sleep at the end of a frame to hold `target_fps`,
+/// given how long the frame took to run. A target of 0 means unlimited, so
+/// no sleep is needed. Frames that overran their budget don't sleep at all.
+pub fn frame_sleep(target_fps: u32, frame_time: Duration) -> Duration {
+    if target_fps == 0 {
+        return Duration::from_secs(0);
+    }
+
+    let budget = Duration::from_secs(1) / target_fps;
+
+    budget
+        .checked_sub(frame_time)
+        .unwrap_or_else(|| Duration::from_secs(0))
+}
+
+/// A source of monotonic time. Lets tests run the profiler and the timers
+/// with a fake clock
+pub trait Clock {
+    /// Time since some fixed starting point. Never goes backwards
+    fn now(&self) -> Duration;
+}
+
+/// The system's monotonic clock, counting from when it was created
+#[cfg(feature = "std")]
+pub struct SystemClock {
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// How the main loop waits out the rest of each frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Sleep the thread, which frees up the CPU
+    Sleep,
+    /// Busy-wait, which wakes up on time at the cost of a full core
+    Spin,
+    /// Let the window's update limiter hold the frame rate. Only possible
+    /// with a window
+    Vsync,
+}
+
+/// What the main loop should do at the end of a frame. See `frame_wait`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameWait {
+    /// Start the next frame straight away
+    None,
+    /// Sleep for this long
+    Sleep(Duration),
+    /// Busy-wait for this long
+    Spin(Duration),
+}
+
+/// Works out how the main loop should wait at the end of a frame that took
+/// `frame_time`, to hold `target_fps` with the given sync mode. See
+/// `frame_sleep` for how long the wait is. With `Vsync` the loop never
+/// waits itself, since presenting the frame already did.
+pub fn frame_wait(mode: SyncMode, target_fps: u32, frame_time: Duration) -> FrameWait {
+    let wait = frame_sleep(target_fps, frame_time);
+    if wait == Duration::from_secs(0) {
+        return FrameWait::None;
+    }
+
+    match mode {
+        SyncMode::Sleep => FrameWait::Sleep(wait),
+        SyncMode::Spin => FrameWait::Spin(wait),
+        SyncMode::Vsync => FrameWait::None,
+    }
+}
+
+/// Measured frame and instruction rates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rates {
+    pub frames_per_sec: f64,
+    pub cycles_per_sec: f64,
+}
+
+/// Measures frames and instructions per second over one second windows
+#[derive(Debug, Default)]
+pub struct RateMeter {
+    elapsed: Duration,
+    frames: u32,
+    cycles: u64,
+}
+
+impl RateMeter {
+    pub fn new() -> RateMeter {
+        RateMeter::default()
+    }
+
+    /// Records a frame that took `elapsed` and ran `cycles` instructions.
+    /// Once a second or more has been recorded the rates over that time are
+    /// returned and measuring starts again.
+    pub fn frame(&mut self, elapsed: Duration, cycles: u64) -> Option<Rates> {
+        self.elapsed += elapsed;
+        self.frames += 1;
+        self.cycles += cycles;
+
+        if self.elapsed < Duration::from_secs(1) {
+            return None;
+        }
+
+        let seconds = self.elapsed.as_secs_f64();
+        let rates = Rates {
+            frames_per_sec: self.frames as f64 / seconds,
+            cycles_per_sec: self.cycles as f64 / seconds,
+        };
+        *self = RateMeter::new();
+
+        Some(rates)
+    }
+}
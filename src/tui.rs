@@ -0,0 +1,245 @@
+//! Draws the display in a terminal, for running over SSH or anywhere else
+//! without a desktop. The renderer itself needs the `tui` feature, but the
+//! text conversion is always available.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Converts a framebuffer to text, packing two rows of pixels into each line
+/// with half-block characters. `pixels` is row-major and `width` pixels wide.
+/// Lines are separated with `\n`, and an odd final row is drawn as if the row
+/// below it were unlit.
+///
+/// ```
+/// use chip8rs::tui::half_blocks;
+///
+/// // The top half of the "0" font sprite
+/// let pixels: Vec<bool> = [
+///     "####", //
+///     "#..#", //
+///     "#..#", //
+///     "#..#",
+/// ]
+/// .iter()
+/// .flat_map(|row| row.chars().map(|c| c == '#'))
+/// .collect();
+///
+/// assert_eq!(half_blocks(&pixels, 4), "█▀▀█\n█  █");
+/// ```
+pub fn half_blocks(pixels: &[bool], width: usize) -> String {
+    let rows: Vec<&[bool]> = pixels.chunks(width).collect();
+
+    rows.chunks(2)
+        .map(|pair| {
+            let top = pair[0];
+            let bottom = pair.get(1);
+
+            (0..width)
+                .map(|x| {
+                    let lower = bottom.is_some_and(|row| row[x]);
+                    match (top[x], lower) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(feature = "tui")]
+pub use self::terminal::TerminalBackend;
+
+#[cfg(feature = "tui")]
+mod terminal {
+    use std::io::{stdout, Stdout, Write};
+    use std::time::{Duration, Instant};
+
+    use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+    use crossterm::{cursor, execute, queue, terminal};
+    use minifb::Key;
+
+    use super::half_blocks;
+    use crate::display::Backend;
+    use crate::Chip8Error;
+
+    /// Terminals only report key presses and their repeats, never releases,
+    /// so a key counts as held for this long after it was last reported
+    const KEY_HOLD: Duration = Duration::from_millis(200);
+
+    /// Shows the display in the terminal and reads keys from it. The terminal
+    /// is put into raw mode on the alternate screen until this is dropped
+    pub struct TerminalBackend {
+        out: Stdout,
+        open: bool,
+        /// When each key was last reported by the terminal
+        held: Vec<(Key, Instant)>,
+        /// Keys that went down since the previous frame
+        pressed: Vec<Key>,
+    }
+
+    impl TerminalBackend {
+        pub fn new() -> Result<TerminalBackend, Chip8Error> {
+            let mut out = stdout();
+
+            terminal::enable_raw_mode().map_err(|_| Chip8Error::DisplayInitFailed)?;
+            execute!(
+                out,
+                terminal::EnterAlternateScreen,
+                terminal::Clear(terminal::ClearType::All),
+                cursor::Hide
+            )
+            .map_err(|_| Chip8Error::DisplayInitFailed)?;
+
+            Ok(TerminalBackend {
+                out,
+                open: true,
+                held: Vec::new(),
+                pressed: Vec::new(),
+            })
+        }
+
+        /// Reads every key event that is waiting, without blocking
+        fn read_events(&mut self) {
+            let now = Instant::now();
+            self.pressed.clear();
+            self.held
+                .retain(|(_, seen)| now.duration_since(*seen) < KEY_HOLD);
+
+            while event::poll(Duration::from_secs(0)).unwrap_or(false) {
+                let key_event = match event::read() {
+                    Ok(Event::Key(key_event)) => key_event,
+                    _ => continue,
+                };
+
+                if key_event.kind == KeyEventKind::Release {
+                    continue;
+                }
+
+                // Raw mode swallows Ctrl-C, so treat it as closing the window
+                if key_event.code == KeyCode::Char('c')
+                    && key_event.modifiers.contains(KeyModifiers::CONTROL)
+                {
+                    self.open = false;
+                    continue;
+                }
+
+                if let Some(key) = to_minifb_key(&key_event) {
+                    if !self.is_key_down(key) {
+                        self.pressed.push(key);
+                    }
+                    self.held.retain(|(held, _)| *held != key);
+                    self.held.push((key, now));
+                }
+            }
+        }
+    }
+
+    impl Drop for TerminalBackend {
+        fn drop(&mut self) {
+            let _ = execute!(self.out, cursor::Show, terminal::LeaveAlternateScreen);
+            let _ = terminal::disable_raw_mode();
+        }
+    }
+
+    impl Backend for TerminalBackend {
+        fn is_open(&self) -> bool {
+            self.open
+        }
+
+        fn is_key_down(&self, key: Key) -> bool {
+            self.held.iter().any(|(held, _)| *held == key)
+        }
+
+        fn is_key_pressed(&self, key: Key) -> bool {
+            self.pressed.contains(&key)
+        }
+
+        fn present(
+            &mut self,
+            pixels: &[u8],
+            width: usize,
+            _height: usize,
+        ) -> Result<(), Chip8Error> {
+            self.read_events();
+
+            // The terminal is monochrome, so a pixel lit on any plane is shown
+            let lit: Vec<bool> = pixels.iter().map(|planes| *planes != 0).collect();
+
+            // Raw mode doesn't return the cursor to the start of the line.
+            // Clearing first stops a 128x64 frame being left behind after
+            // switching back to 64x32
+            let art = half_blocks(&lit, width).replace('\n', "\r\n");
+            let _ = queue!(
+                self.out,
+                cursor::MoveTo(0, 0),
+                terminal::Clear(terminal::ClearType::All)
+            );
+            let _ = self.out.write_all(art.as_bytes());
+            let _ = self.out.flush();
+
+            Ok(())
+        }
+
+        fn poll_events(&mut self) {
+            self.read_events();
+        }
+    }
+
+    /// Maps a terminal key to the minifb key the rest of the emulator expects
+    fn to_minifb_key(key_event: &KeyEvent) -> Option<Key> {
+        let key = match key_event.code {
+            KeyCode::Esc => Key::Escape,
+            KeyCode::Tab => Key::Tab,
+            KeyCode::F(5) => Key::F5,
+            KeyCode::PageUp => Key::PageUp,
+            KeyCode::PageDown => Key::PageDown,
+            KeyCode::Char(' ') => Key::Space,
+            KeyCode::Char(c) => match c.to_ascii_lowercase() {
+                '0' => Key::Key0,
+                '1' => Key::Key1,
+                '2' => Key::Key2,
+                '3' => Key::Key3,
+                '4' => Key::Key4,
+                '5' => Key::Key5,
+                '6' => Key::Key6,
+                '7' => Key::Key7,
+                '8' => Key::Key8,
+                '9' => Key::Key9,
+                'a' => Key::A,
+                'b' => Key::B,
+                'c' => Key::C,
+                'd' => Key::D,
+                'e' => Key::E,
+                'f' => Key::F,
+                'g' => Key::G,
+                'h' => Key::H,
+                'i' => Key::I,
+                'j' => Key::J,
+                'k' => Key::K,
+                'l' => Key::L,
+                'm' => Key::M,
+                'n' => Key::N,
+                'o' => Key::O,
+                'p' => Key::P,
+                'q' => Key::Q,
+                'r' => Key::R,
+                's' => Key::S,
+                't' => Key::T,
+                'u' => Key::U,
+                'v' => Key::V,
+                'w' => Key::W,
+                'x' => Key::X,
+                'y' => Key::Y,
+                'z' => Key::Z,
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Some(key)
+    }
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use chip8rs::disasm;
+use chip8rs::{Chip8Error, StepOutcome};
+
+fn no_wide_sprites(opcode: u16) -> bool {
+    opcode & 0xF00F != 0xD000
+}
+
+#[test]
+fn disallowed_opcodes_are_errors() {
+    // DRW V0, V0, 0
+    let config = common::config_with(|b| b.allowed_opcodes(no_wide_sprites));
+    let mut chip8 = common::machine_with(&config, &[0xD0, 0x00], |_| {});
+
+    assert_eq!(chip8.step(), Err(Chip8Error::DisallowedOpcode(0xD000)));
+    assert_eq!(chip8.state().pc, 0x200);
+}
+
+#[test]
+fn allowed_opcodes_run_as_normal() {
+    // DRW V0, V0, 5
+    let config = common::config_with(|b| b.allowed_opcodes(no_wide_sprites));
+    let mut chip8 = common::machine_with(&config, &[0xD0, 0x05], |_| {});
+
+    assert_eq!(chip8.step(), Ok(StepOutcome::Executed));
+    assert_eq!(chip8.state().pc, 0x202);
+}
+
+#[test]
+fn classic_opcodes_exclude_the_extensions() {
+    for opcode in [0x00FE, 0x00FF, 0xD120, 0xF330, 0xF201].iter() {
+        assert!(!disasm::is_classic(*opcode), "{:#06X}", opcode);
+    }
+
+    for opcode in [0x00E0, 0x00EE, 0xD125, 0xF329, 0xF233, 0x8126].iter() {
+        assert!(disasm::is_classic(*opcode), "{:#06X}", opcode);
+    }
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "std")]
+
+use chip8rs::asm::{assemble, AsmError};
+use chip8rs::disasm;
+
+#[test]
+fn assembles_a_small_program() {
+    let source = "
+        ; Draw the digit in V0 forever
+        start:
+            CLS
+            LD V0, 0x0A
+            LD F, V0
+            DRW V1, V2, 5
+        loop: JP loop
+    ";
+
+    assert_eq!(
+        assemble(source).unwrap(),
+        vec![0x00, 0xE0, 0x60, 0x0A, 0xF0, 0x29, 0xD1, 0x25, 0x12, 0x08]
+    );
+}
+
+#[test]
+fn accepts_decimal_and_lower_case() {
+    assert_eq!(assemble("ld v3, 42").unwrap(), vec![0x63, 0x2A]);
+}
+
+#[test]
+fn round_trips_through_the_disassembler() {
+    let opcodes: [u16; 36] = [
+        0x00E0, 0x00EE, 0x00FE, 0x00FF, 0x0123, 0x1234, 0x2345, 0x3456, 0x4567, 0x5670, 0x6789,
+        0x789A, 0x8120, 0x8121, 0x8122, 0x8123, 0x8124, 0x8125, 0x8106, 0x8127, 0x810E, 0x9AB0,
+        0xABCD, 0xBCDE, 0xCDEF, 0xD12F, 0xE19E, 0xE1A1, 0xF107, 0xF10A, 0xF115, 0xF118, 0xF11E,
+        0xF129, 0xF230, 0xF301,
+    ];
+
+    for opcode in opcodes.iter() {
+        let source = disasm::disassemble(*opcode);
+        let bytes = assemble(&source).unwrap();
+
+        assert_eq!(bytes, opcode.to_be_bytes().to_vec(), "{}", source);
+    }
+
+    for source in ["LD B, V4", "LD [I], V5", "LD V6, [I]"].iter() {
+        let bytes = assemble(source).unwrap();
+        let opcode = u16::from_be_bytes([bytes[0], bytes[1]]);
+
+        assert_eq!(disasm::disassemble(opcode), *source);
+    }
+}
+
+#[test]
+fn reports_the_line_of_an_error() {
+    let err = assemble("CLS\nLD V0, 0x100\n").unwrap_err();
+
+    assert_eq!(
+        err,
+        AsmError {
+            line: 2,
+            message: String::from("Byte out of range: 0x100"),
+        }
+    );
+}
+
+#[test]
+fn rejects_unknown_labels() {
+    assert!(assemble("JP nowhere").is_err());
+}
+
+#[test]
+fn shifts_keep_the_vy_field_through_a_round_trip() {
+    for (opcode, text) in [
+        (0x8126, "SHR V1, V2"),
+        (0x812E, "SHL V1, V2"),
+        (0x8106, "SHR V1"),
+    ] {
+        let bytes = assemble(text).unwrap();
+        assert_eq!(bytes, u16::to_be_bytes(opcode).to_vec());
+
+        let source = disasm::disassemble(opcode);
+        assert_eq!(source, text);
+        assert_eq!(assemble(&source).unwrap(), bytes);
+    }
+}
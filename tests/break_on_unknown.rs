@@ -0,0 +1,58 @@
+mod common;
+
+use chip8rs::{Chip8Error, Quirks, StepOutcome};
+
+// LD V0, 7; LD I, 0x300; an unknown word; LD V1, 1
+const PROGRAM: [u8; 8] = [0x60, 0x07, 0xA3, 0x00, 0xFF, 0xFF, 0x61, 0x01];
+
+#[test]
+fn unknown_opcode_is_an_error_by_default() {
+    let mut chip8 = common::machine(&PROGRAM);
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+
+    assert_eq!(chip8.step(), Err(Chip8Error::UnknownOpcode(0xFFFF)));
+}
+
+#[test]
+fn unknown_opcode_halts_with_the_pc_on_it() {
+    let config = common::config_with(|b| b.break_on_unknown(true));
+    let mut chip8 = common::machine_with(&config, &PROGRAM, |_| {});
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    let before = chip8.state();
+
+    assert_eq!(chip8.step(), Ok(StepOutcome::UnknownOpcode(0xFFFF)));
+    assert!(chip8.is_halted());
+    assert!(chip8.is_paused());
+    assert_eq!(chip8.state(), before);
+    assert_eq!(chip8.state().pc, 0x204);
+    assert_eq!(chip8.state().vx[0], 7);
+    assert_eq!(chip8.state().i, 0x300);
+}
+
+#[test]
+fn run_frame_stops_at_an_unknown_opcode() {
+    let config = common::config_with(|b| b.break_on_unknown(true));
+    let mut chip8 = common::machine_with(&config, &PROGRAM, |_| {});
+
+    chip8.run_frame(100).unwrap();
+
+    assert_eq!(chip8.cycle_count(), 2);
+    assert_eq!(chip8.state().pc, 0x204);
+    assert!(chip8.is_halted());
+}
+
+#[test]
+fn strict_sys_breaks_on_sys() {
+    let config = common::config_with(|b| {
+        b.break_on_unknown(true).quirks(Quirks {
+            strict_sys: true,
+            ..Quirks::cosmac()
+        })
+    });
+    let mut chip8 = common::machine_with(&config, &[0x02, 0x50], |_| {});
+
+    assert_eq!(chip8.step(), Ok(StepOutcome::UnknownOpcode(0x0250)));
+    assert_eq!(chip8.state().pc, 0x200);
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use chip8rs::{Chip8, Config};
+
+#[test]
+fn call_targets_are_collected_and_deduplicated() {
+    // CALL 0x300; CALL 0x208; CALL 0x300; JP 0x206
+    let program = [0x23, 0x00, 0x22, 0x08, 0x23, 0x00, 0x12, 0x06];
+    let chip8 = common::machine(&program);
+
+    let targets: Vec<u16> = chip8.find_call_targets().into_iter().collect();
+    assert_eq!(targets, [0x208, 0x300]);
+}
+
+#[test]
+fn call_targets_only_come_from_the_loaded_rom() {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .build()
+        .unwrap();
+    let mut chip8 = Chip8::from_rom(&config, &[0x22, 0x40, 0x12, 0x02]).unwrap();
+    chip8.load_data(&[0x2F, 0xFF], 0x300).unwrap();
+
+    assert_eq!(
+        chip8.find_call_targets().into_iter().collect::<Vec<_>>(),
+        [0x240]
+    );
+}
+
+#[test]
+fn jump_to_moves_execution() {
+    // JP 0x200; LD V0, 7
+    let mut chip8 = common::machine(&[0x12, 0x00, 0x60, 0x07]);
+
+    chip8.jump_to(0x202);
+    chip8.step().unwrap();
+
+    assert_eq!(chip8.state().vx[0], 7);
+    assert_eq!(chip8.state().pc, 0x204);
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "std")]
+
+use std::fs;
+use std::path::Path;
+
+use chip8rs::{Chip8, Config};
+
+const HEADER: &[u8] = b"P6\n64 32\n255\n";
+
+fn capture_machine(dir: &Path, program: &[u8]) -> Chip8 {
+    let _ = fs::remove_dir_all(dir);
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .capture_dir(dir.to_str().unwrap())
+        .build()
+        .unwrap();
+
+    Chip8::from_rom(&config, program).unwrap()
+}
+
+fn captured_frames(dir: &Path) -> Vec<Vec<u8>> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+
+    paths.iter().map(|path| fs::read(path).unwrap()).collect()
+}
+
+#[test]
+fn each_frame_is_written_as_a_ppm() {
+    let dir = std::env::temp_dir().join("chip8rs_capture_frames");
+    // DRW V0, V0, 5 with I at the "0" glyph, then JP to itself
+    let mut chip8 = capture_machine(&dir, &[0xD0, 0x05, 0x12, 0x02]);
+
+    for _ in 0..3 {
+        chip8.run_frame(10).unwrap();
+        chip8.render().unwrap();
+    }
+
+    let frames = captured_frames(&dir);
+    assert_eq!(frames.len(), 3);
+    for frame in &frames {
+        assert!(frame.starts_with(HEADER));
+        assert_eq!(frame.len(), HEADER.len() + 64 * 32 * 3);
+    }
+
+    // The top left pixel of the "0" glyph is lit
+    assert_eq!(
+        frames[0][HEADER.len()..HEADER.len() + 3],
+        [0xFF, 0xFF, 0xFF]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn at_most_one_frame_is_written_per_tick() {
+    let dir = std::env::temp_dir().join("chip8rs_capture_throttle");
+    let mut chip8 = capture_machine(&dir, &[0x12, 0x00]);
+
+    chip8.run_frame(10).unwrap();
+    chip8.render().unwrap();
+    chip8.render().unwrap();
+    chip8.render().unwrap();
+    chip8.run_frame(10).unwrap();
+    chip8.render().unwrap();
+
+    assert_eq!(captured_frames(&dir).len(), 2);
+    fs::remove_dir_all(&dir).unwrap();
+}
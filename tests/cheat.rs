@@ -0,0 +1,84 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use chip8rs::asm::assemble;
+use chip8rs::{parse_cheats, Cheat, CheatError};
+
+#[test]
+fn cheat_files_are_parsed() {
+    let text = "# Lives\n0x3A0=9\n\nfreeze 928 = 0x10\n";
+
+    assert_eq!(
+        parse_cheats(text),
+        Ok(vec![
+            Cheat {
+                address: 0x3A0,
+                value: 9,
+                freeze: false,
+            },
+            Cheat {
+                address: 0x3A0,
+                value: 0x10,
+                freeze: true,
+            },
+        ])
+    );
+}
+
+#[test]
+fn bad_cheat_lines_are_rejected() {
+    assert_eq!(
+        parse_cheats("0x300=1\n0x300"),
+        Err(CheatError {
+            line: 2,
+            message: "Expected address=value",
+        })
+    );
+    assert_eq!(
+        parse_cheats("0x1000=1").unwrap_err().message,
+        "Address is outside RAM"
+    );
+    assert_eq!(
+        parse_cheats("0x300=256").unwrap_err().message,
+        "Value doesn't fit in a byte"
+    );
+}
+
+#[test]
+fn plain_cheats_are_poked_once() {
+    let program = assemble("LD I, 0x300\nLD V0, 1\nLD [I], V0").unwrap();
+    let config = common::config_with(|b| b.cheats(parse_cheats("0x300=7").unwrap()));
+    let mut chip8 = common::machine_with(&config, &program, |_| {});
+
+    assert_eq!(chip8.read_byte_checked(0x300), Ok(7));
+
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+    assert_eq!(chip8.read_byte_checked(0x300), Ok(1));
+}
+
+#[test]
+fn freeze_cheats_hold_their_value() {
+    // Counts the byte at 0x300 up forever
+    let program = assemble(
+        "LD I, 0x300
+        loop:
+            LD V0, [I]
+            ADD V0, 1
+            LD [I], V0
+            JP loop",
+    )
+    .unwrap();
+    let config = common::config_with(|b| b.cheats(parse_cheats("freeze 0x300=5").unwrap()));
+    let mut chip8 = common::machine_with(&config, &program, |_| {});
+
+    for _ in 0..20 {
+        chip8.step().unwrap();
+        assert_eq!(chip8.read_byte_checked(0x300), Ok(5));
+    }
+
+    // The program kept reading the frozen value back
+    assert_eq!(chip8.state().vx[0], 6);
+}
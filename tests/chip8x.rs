@@ -0,0 +1,100 @@
+#![cfg(feature = "chip8x")]
+
+mod common;
+
+use chip8rs::chip8x::{ColorLayer, BACKGROUND_COLORS, FOREGROUND_COLORS};
+use chip8rs::{Chip8, Chip8State, Config, DISPLAY_WIDTH};
+
+/// Builds a machine with CHIP-8X on that runs `program` from 0x200
+fn chip8x_machine<F>(program: &[u8], setup: F) -> Chip8
+where
+    F: FnOnce(&mut Chip8State),
+{
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .chip8x(true)
+        .build()
+        .unwrap();
+
+    common::machine_with(&config, program, setup)
+}
+
+#[test]
+fn background_opcode_steps_through_the_colors() {
+    // 02A0, four times over
+    let mut chip8 = chip8x_machine(&[0x02, 0xA0, 0x02, 0xA0, 0x02, 0xA0, 0x02, 0xA0], |_| {});
+    assert_eq!(
+        chip8.color_layer().unwrap().background(),
+        BACKGROUND_COLORS[0]
+    );
+
+    for color in [1, 2, 3, 0] {
+        chip8.step().unwrap();
+        assert_eq!(
+            chip8.color_layer().unwrap().background(),
+            BACKGROUND_COLORS[color]
+        );
+    }
+    assert_eq!(chip8.state().pc, 0x208);
+}
+
+#[test]
+fn background_opcode_leaves_the_pixels_alone() {
+    // LD I, 0x200; DRW V0, V0, 4; 02A0
+    let program = [0xA2, 0x00, 0xD0, 0x04, 0x02, 0xA0];
+    let mut plain = common::machine(&program);
+    let mut colored = chip8x_machine(&program, |_| {});
+
+    for _ in 0..3 {
+        plain.step().unwrap();
+        colored.step().unwrap();
+    }
+
+    assert_eq!(colored.framebuffer_ascii(), plain.framebuffer_ascii());
+    // Unlit pixels take the new background, and lit ones stay white
+    let buffer = colored.render_buffer();
+    assert_eq!(buffer[DISPLAY_WIDTH - 1], BACKGROUND_COLORS[1]);
+    assert_eq!(buffer[0], FOREGROUND_COLORS[7]);
+}
+
+#[test]
+fn zone_opcode_colors_blocks_of_zones() {
+    // B100 with V1 = 0x11, V2 = 0x00 and V0 = 1 (red)
+    let mut chip8 = chip8x_machine(&[0xB1, 0x00], |state| {
+        state.vx[0] = 1;
+        state.vx[1] = 0x11;
+        state.vx[2] = 0x00;
+    });
+    chip8.step().unwrap();
+
+    let colors = chip8.color_layer().unwrap();
+    let red = FOREGROUND_COLORS[1];
+    // Zone columns 1 and 2, zone row 0
+    assert_eq!(colors.foreground(8, 0), red);
+    assert_eq!(colors.foreground(23, 3), red);
+    assert_ne!(colors.foreground(7, 0), red);
+    assert_ne!(colors.foreground(24, 0), red);
+    assert_ne!(colors.foreground(8, 4), red);
+}
+
+#[test]
+fn row_opcode_colors_rows_of_one_column() {
+    let mut colors = ColorLayer::new();
+    colors.set_rows(20, 5, 3, 4);
+
+    let green = FOREGROUND_COLORS[4];
+    assert_eq!(colors.foreground(16, 5), green);
+    assert_eq!(colors.foreground(23, 7), green);
+    assert_ne!(colors.foreground(16, 8), green);
+    assert_ne!(colors.foreground(24, 5), green);
+}
+
+#[test]
+fn bnnn_is_still_a_jump_without_chip8x() {
+    // JP V0, 0x300
+    let mut chip8 = common::machine(&[0xB3, 0x00]);
+    chip8.step().unwrap();
+
+    assert_eq!(chip8.state().pc, 0x300);
+}
@@ -0,0 +1,51 @@
+//! Helpers shared by the integration tests
+
+// Each test crate only uses some of these
+#![allow(dead_code)]
+
+use chip8rs::{Chip8, Chip8State, Config, ConfigBuilder, RAM_SIZE};
+
+/// A config for a headless machine. The ROM path is never read when the
+/// machine is built with `machine`
+pub fn config() -> Config {
+    config_with(|builder| builder)
+}
+
+/// `config` with more settings, e.g. `config_with(|b| b.history_size(8))`
+pub fn config_with<F>(settings: F) -> Config
+where
+    F: FnOnce(ConfigBuilder) -> ConfigBuilder,
+{
+    settings(Config::builder().rom_path("unused.ch8").headless(true))
+        .build()
+        .unwrap()
+}
+
+/// Builds a headless machine that runs `program` from 0x200
+pub fn machine(program: &[u8]) -> Chip8 {
+    machine_with(&config(), program, |_| {})
+}
+
+/// Builds a machine that runs `program` from 0x200, letting the caller adjust
+/// the starting state first
+pub fn machine_with<F>(config: &Config, program: &[u8], setup: F) -> Chip8
+where
+    F: FnOnce(&mut Chip8State),
+{
+    let mut memory = vec![0; RAM_SIZE];
+    memory[0x200..0x200 + program.len()].copy_from_slice(program);
+
+    let mut state = Chip8State {
+        vx: [0; 16],
+        i: 0,
+        pc: 0x200,
+        sp: 0,
+        dt: 0,
+        st: 0,
+        stack: vec![0; 16],
+        memory,
+    };
+    setup(&mut state);
+
+    Chip8::from_state(config, state).unwrap()
+}
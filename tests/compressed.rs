@@ -0,0 +1,42 @@
+#![cfg(feature = "flate2")]
+
+use std::io::Write;
+
+use chip8rs::{Ram, RAM_SIZE};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn compressed_rom_loads_the_same_as_uncompressed() {
+    let rom = include_bytes!("roms/golden.ch8");
+
+    let mut plain = Ram::from_memory(&[]);
+    plain.load_rom(rom).unwrap();
+
+    let mut compressed = Ram::from_memory(&[]);
+    compressed.load_rom(&gzip(rom)).unwrap();
+
+    assert_eq!(
+        compressed.read_bytes(0, RAM_SIZE).unwrap(),
+        plain.read_bytes(0, RAM_SIZE).unwrap()
+    );
+    assert_eq!(compressed.rom_hash(), plain.rom_hash());
+}
+
+#[test]
+fn corrupt_compressed_rom_is_an_error() {
+    let mut rom = gzip(include_bytes!("roms/golden.ch8"));
+    rom.truncate(12);
+
+    let mut ram = Ram::from_memory(&[]);
+    assert_eq!(
+        ram.load_rom(&rom),
+        Err(chip8rs::Chip8Error::BadCompressedRom)
+    );
+}
@@ -0,0 +1,66 @@
+use chip8rs::timing::SyncMode;
+use chip8rs::{BackendKind, Config, Quirks};
+
+#[test]
+fn builder_applies_defaults_for_unset_fields() {
+    let config = Config::builder().rom_path("game.ch8").build().unwrap();
+
+    assert_eq!(config.rom_path, "game.ch8");
+    assert!(!config.embedded_rom);
+    assert_eq!(config.scale, 16);
+    assert_eq!(config.cpu_hz, 500);
+    assert_eq!(config.turbo_multiplier, 4);
+    assert!(!config.headless);
+    assert_eq!(config.backend, BackendKind::Window);
+    assert!(!config.debug);
+    assert_eq!(config.target_fps, 60);
+    assert_eq!(config.sync_mode, SyncMode::Sleep);
+    assert_eq!(config.quirks, Quirks::default());
+    assert!(!config.quirks_explicit);
+    assert_eq!(config.ram_size, 4096);
+    assert_eq!(config.load_address, 0x200);
+    assert_eq!(config.stack_size, 16);
+    assert_eq!(config.history_size, 0);
+    assert_eq!(config.seed, None);
+}
+
+#[test]
+fn builder_sets_only_what_it_is_given() {
+    let config = Config::builder()
+        .rom_path("game.ch8")
+        .scale(4)
+        .cpu_hz(1000)
+        .quirks(Quirks::schip())
+        .headless(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(config.scale, 4);
+    assert_eq!(config.cpu_hz, 1000);
+    assert_eq!(config.quirks, Quirks::schip());
+    assert!(config.quirks_explicit);
+    assert!(config.headless);
+    assert_eq!(config.turbo_multiplier, 4);
+    assert_eq!(config.target_fps, 60);
+}
+
+#[test]
+fn builder_without_a_rom_runs_the_splash() {
+    let config = Config::builder().build().unwrap();
+
+    assert!(config.embedded_rom);
+}
+
+#[test]
+fn builder_rejects_invalid_values() {
+    assert!(Config::builder().scale(3).build().is_err());
+    assert!(Config::builder().cpu_hz(0).build().is_err());
+    assert!(Config::builder().stack_size(0).build().is_err());
+    assert!(Config::builder().ram_size(0x2FF).build().is_err());
+    assert!(Config::builder().ram_size(0x10001).build().is_err());
+    assert!(Config::builder()
+        .ram_size(0x800)
+        .load_address(0x800)
+        .build()
+        .is_err());
+}
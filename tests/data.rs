@@ -0,0 +1,69 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::fs;
+
+use chip8rs::{Chip8, Chip8Error, Config};
+
+#[test]
+fn data_file_is_loaded_alongside_the_rom() {
+    let dir = std::env::temp_dir();
+    let rom_path = dir.join("chip8rs_data_rom.ch8");
+    let data_path = dir.join("chip8rs_data_table.bin");
+    fs::write(&rom_path, [0x12, 0x00]).unwrap();
+    fs::write(&data_path, [0xDE, 0xAD, 0xBE, 0xEF]).unwrap();
+
+    let config = Config::builder()
+        .rom_path(rom_path.to_str().unwrap())
+        .data_file(data_path.to_str().unwrap())
+        .data_offset(0x400)
+        .headless(true)
+        .build()
+        .unwrap();
+    let chip8 = Chip8::new(&config).unwrap();
+    fs::remove_file(&rom_path).unwrap();
+    fs::remove_file(&data_path).unwrap();
+
+    assert_eq!(chip8.memory_slice(0x200..0x202), Ok(&[0x12, 0x00][..]));
+    assert_eq!(
+        chip8.memory_slice(0x400..0x404),
+        Ok(&[0xDE, 0xAD, 0xBE, 0xEF][..])
+    );
+    // Nothing in between
+    assert!(chip8
+        .memory_slice(0x202..0x400)
+        .unwrap()
+        .iter()
+        .all(|byte| *byte == 0));
+}
+
+#[test]
+fn data_may_not_overlap_the_rom_or_the_font() {
+    let mut chip8 = Chip8::from_rom(&common::config(), &[0x60, 0x01, 0x12, 0x02]).unwrap();
+
+    assert_eq!(
+        chip8.load_data(&[1, 2], 0x202),
+        Err(Chip8Error::DataOverlaps(0x202))
+    );
+    assert_eq!(
+        chip8.load_data(&[1, 2], 0x1FF),
+        Err(Chip8Error::DataOverlaps(0x1FF))
+    );
+    assert_eq!(
+        chip8.load_data(&[1, 2], 0x1FE),
+        Err(Chip8Error::DataOverlaps(0x1FE))
+    );
+    assert_eq!(chip8.load_data(&[1, 2], 0x204), Ok(()));
+}
+
+#[test]
+fn data_must_fit_in_ram() {
+    let mut chip8 = Chip8::from_rom(&common::config(), &[0x12, 0x00]).unwrap();
+
+    assert_eq!(
+        chip8.load_data(&[0; 4], 0xFFE),
+        Err(Chip8Error::DataTooLarge(4))
+    );
+    assert_eq!(chip8.load_data(&[0; 2], 0xFFE), Ok(()));
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use chip8rs::{Backend, Chip8, Chip8Error, Config};
+use minifb::Key;
+
+/// A window where the key in the shared cell has just been pressed
+struct PressedKey(Rc<Cell<Option<Key>>>);
+
+impl Backend for PressedKey {
+    fn is_open(&self) -> bool {
+        true
+    }
+
+    fn is_key_down(&self, key: Key) -> bool {
+        self.0.get() == Some(key)
+    }
+
+    fn is_key_pressed(&self, key: Key) -> bool {
+        self.0.get() == Some(key)
+    }
+
+    fn present(&mut self, _pixels: &[u8], _width: usize, _height: usize) -> Result<(), Chip8Error> {
+        Ok(())
+    }
+}
+
+/// A debug mode machine counting up in V0, and the key its window reports
+fn debug_machine() -> (Chip8, Rc<Cell<Option<Key>>>) {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .debug(true)
+        .build()
+        .unwrap();
+    // ADD V0, 1; JP 0x200
+    let mut chip8 = common::machine_with(&config, &[0x70, 0x01, 0x12, 0x00], |_| {});
+    let key = Rc::new(Cell::new(None));
+    chip8.set_backend(Box::new(PressedKey(key.clone())));
+
+    (chip8, key)
+}
+
+#[test]
+fn debug_mode_runs_nothing_until_the_step_key() {
+    let (mut chip8, key) = debug_machine();
+    assert!(chip8.is_paused());
+
+    for _ in 0..3 {
+        assert_eq!(chip8.handle_debug_keys(), Ok(false));
+        chip8.run_frame(10).unwrap();
+    }
+    key.set(Some(Key::Enter));
+    assert_eq!(chip8.handle_debug_keys(), Ok(false));
+    assert_eq!(chip8.cycle_count(), 0);
+
+    key.set(Some(Key::Space));
+    assert_eq!(chip8.handle_debug_keys(), Ok(true));
+    assert_eq!(chip8.cycle_count(), 1);
+    assert_eq!(chip8.state().vx[0], 1);
+    assert!(chip8.is_paused());
+}
+
+#[test]
+fn continue_key_resumes_running() {
+    let (mut chip8, key) = debug_machine();
+
+    key.set(Some(Key::F5));
+    assert_eq!(chip8.handle_debug_keys(), Ok(false));
+    assert!(!chip8.is_paused());
+
+    chip8.run_frame(10).unwrap();
+    assert_eq!(chip8.cycle_count(), 10);
+
+    // Space does nothing while running
+    key.set(Some(Key::Space));
+    assert_eq!(chip8.handle_debug_keys(), Ok(false));
+    assert_eq!(chip8.cycle_count(), 10);
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "debug-server")]
+
+mod common;
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chip8rs::debug_server::DebugServer;
+
+/// Sends a request, then polls the server until another thread has read
+/// the response, and returns the response. The request is sent before the
+/// first poll, so the server never waits on it
+fn request(server: &DebugServer, chip8: &mut chip8rs::Chip8, request: &str) -> String {
+    let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let client = thread::spawn(move || {
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    });
+
+    while !client.is_finished() {
+        server.poll(chip8).unwrap();
+        thread::yield_now();
+    }
+
+    client.join().unwrap()
+}
+
+#[test]
+fn state_reports_the_pc() {
+    let server = DebugServer::bind("127.0.0.1:0").unwrap();
+    // LD V0, 0x2A; JP 0x202
+    let mut chip8 = common::machine(&[0x60, 0x2A, 0x12, 0x02]);
+
+    let response = request(&server, &mut chip8, "GET /state HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    assert!(response.contains(r#""pc":512"#), "{}", response);
+
+    let response = request(&server, &mut chip8, "POST /step HTTP/1.1\r\n\r\n");
+    assert!(response.contains(r#""pc":514"#), "{}", response);
+    assert!(response.contains(r#""v":[42,"#), "{}", response);
+}
+
+#[test]
+fn ram_serves_a_bounds_checked_range() {
+    let server = DebugServer::bind("127.0.0.1:0").unwrap();
+    let mut chip8 = common::machine(&[0x60, 0x2A]);
+
+    let response = request(
+        &server,
+        &mut chip8,
+        "GET /ram?start=0x200&end=0x202 HTTP/1.1\r\n\r\n",
+    );
+    assert!(
+        response.ends_with(r#"{"start":512,"bytes":[96,42]}"#),
+        "{}",
+        response
+    );
+
+    let response = request(
+        &server,
+        &mut chip8,
+        "GET /ram?start=0xFFF&end=0x1001 HTTP/1.1\r\n\r\n",
+    );
+    assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+}
+
+#[test]
+fn silent_client_does_not_hold_up_the_frame() {
+    let server = DebugServer::bind("127.0.0.1:0").unwrap();
+    let mut chip8 = common::machine(&[]);
+    let _silent = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+
+    let started = Instant::now();
+    server.poll(&mut chip8).unwrap();
+    assert!(started.elapsed() < Duration::from_millis(500));
+
+    // Later requests are still answered
+    let response = request(&server, &mut chip8, "GET /state HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+}
+
+#[test]
+fn at_most_four_connections_are_answered_per_poll() {
+    let server = DebugServer::bind("127.0.0.1:0").unwrap();
+    let mut chip8 = common::machine(&[]);
+    let mut clients: Vec<TcpStream> = (0..5)
+        .map(|_| {
+            let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+            stream.write_all(b"GET /state HTTP/1.1\r\n\r\n").unwrap();
+            stream
+        })
+        .collect();
+
+    server.poll(&mut chip8).unwrap();
+
+    let mut last = clients.pop().unwrap();
+    for mut client in clients {
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    }
+
+    // The fifth waits for the next poll
+    last.set_read_timeout(Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(last.read(&mut [0; 16]).is_err());
+
+    server.poll(&mut chip8).unwrap();
+    let mut response = String::new();
+    last.set_read_timeout(None).unwrap();
+    last.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+}
@@ -0,0 +1,484 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use chip8rs::{
+    draw_grid, fill_colors, scale_colors, Backend, Chip8, Chip8Error, Config, Display,
+    DISPLAY_WIDTH, FONT, HIRES_HEIGHT, HIRES_WIDTH,
+};
+use minifb::Key;
+use std::cell::Cell;
+use std::rc::Rc;
+
+const SQUARE: [u8; 4] = [0xF0, 0xF0, 0xF0, 0xF0];
+
+fn lit_count(pixels: &[bool]) -> usize {
+    pixels.iter().filter(|lit| **lit).count()
+}
+
+#[test]
+fn cls_blanks_the_whole_hires_display() {
+    let mut display = Display::headless();
+    display.set_hires(true);
+
+    // Draw into the far corner, which only exists in hi-res
+    display.draw_sprite(HIRES_WIDTH - 8, HIRES_HEIGHT - 4, &SQUARE);
+    display.draw_sprite(0, 0, &SQUARE);
+    assert_eq!(display.plane(0).len(), HIRES_WIDTH * HIRES_HEIGHT);
+    assert_eq!(lit_count(display.plane(0)), 32);
+
+    display.clear();
+    assert_eq!(display.plane(0).len(), HIRES_WIDTH * HIRES_HEIGHT);
+    assert_eq!(lit_count(display.plane(0)), 0);
+}
+
+#[test]
+fn draw_sprites_reports_collisions_across_the_batch() {
+    let mut display = Display::headless();
+
+    let apart: [(usize, usize, &[u8]); 3] = [(0, 0, &SQUARE), (8, 0, &SQUARE), (16, 0, &SQUARE)];
+    assert!(!display.draw_sprites(&apart));
+    assert_eq!(lit_count(display.plane(0)), 48);
+
+    // The second square overlaps the first one drawn above
+    let overlapping: [(usize, usize, &[u8]); 2] = [(32, 0, &SQUARE), (2, 2, &SQUARE)];
+    assert!(display.draw_sprites(&overlapping));
+}
+
+#[test]
+fn cls_only_clears_the_selected_plane() {
+    let mut display = Display::headless();
+
+    // One square per plane
+    display.set_plane_mask(0x3);
+    display.draw_sprite(0, 0, &[SQUARE, SQUARE].concat());
+    assert_eq!(lit_count(display.plane(0)), 16);
+    assert_eq!(lit_count(display.plane(1)), 16);
+
+    display.set_plane_mask(0x1);
+    display.clear();
+    assert_eq!(lit_count(display.plane(0)), 0);
+    assert_eq!(lit_count(display.plane(1)), 16);
+}
+
+/// Draws the font's "E" at (2, 1) and returns the top left of the screen
+fn font_e_corner(program_prefix: &[u8], width: usize) -> Vec<String> {
+    // LD V0, 0xE; LD F, V0; LD V1, 2; LD V2, 1; DRW V1, V2, 5
+    let program = [
+        program_prefix,
+        &[0x60, 0x0E, 0xF0, 0x29, 0x61, 0x02, 0x62, 0x01, 0xD1, 0x25],
+    ]
+    .concat();
+    // Test machines start with empty RAM, so the font character is put in
+    // place by hand
+    let mut chip8 = common::machine_with(&common::config(), &program, |state| {
+        state.memory[0xE * 5..0xF * 5].copy_from_slice(&[0xF0, 0x80, 0xF0, 0x80, 0xF0]);
+    });
+    chip8
+        .run_until(|chip8| chip8.state().pc == 0x200 + program.len() as u16, 10)
+        .unwrap();
+
+    let ascii = chip8.framebuffer_ascii();
+    let lines: Vec<&str> = ascii.lines().collect();
+    assert!(lines.iter().all(|line| line.len() == width));
+
+    lines[..7]
+        .iter()
+        .map(|line| line[..8].to_string())
+        .collect()
+}
+
+const FONT_E: [&str; 7] = [
+    "........", //
+    "..####..", //
+    "..#.....", //
+    "..####..", //
+    "..#.....", //
+    "..####..", //
+    "........",
+];
+
+#[test]
+fn framebuffer_ascii_shows_a_font_sprite() {
+    assert_eq!(font_e_corner(&[], DISPLAY_WIDTH), FONT_E);
+}
+
+#[test]
+fn framebuffer_ascii_shows_the_hires_display() {
+    // HIGH
+    assert_eq!(font_e_corner(&[0x00, 0xFF], HIRES_WIDTH), FONT_E);
+}
+
+#[test]
+fn plane_bits_select_the_palette_color() {
+    let palette = [0x101010, 0x202020, 0x303030, 0x404040];
+    let mut display = Display::headless();
+
+    // Two rows per plane. The pixel at the start of the first row is lit on
+    // both planes, the one below it only on plane 1
+    display.set_plane_mask(0x3);
+    display.draw_sprite(0, 0, &[0x80, 0x00, 0x80, 0x80]);
+
+    let mut buffer = Vec::new();
+    fill_colors(&mut buffer, &display.pixels(), &palette);
+
+    assert_eq!(buffer.len(), display.pixels().len());
+    assert_eq!(buffer[0], 0x404040);
+    assert_eq!(buffer[DISPLAY_WIDTH], 0x303030);
+    assert_eq!(buffer[1], 0x101010);
+
+    display.set_plane_mask(0x1);
+    display.draw_sprite(1, 0, &[0x80]);
+    fill_colors(&mut buffer, &display.pixels(), &palette);
+    assert_eq!(buffer[1], 0x202020);
+}
+
+/// Runs a program that draws the top row of the "0" font character at the
+/// top left of the display
+fn machine_with_a_line() -> Chip8 {
+    // LD I, 0x206; DRW V0, V0, 1; JP 0x204; DW 0xF000
+    let program = [0xA2, 0x06, 0xD0, 0x01, 0x12, 0x04, 0xF0, 0x00];
+    let mut chip8 = common::machine(&program);
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+
+    chip8
+}
+
+#[test]
+fn render_buffer_uses_the_palette_without_a_hook() {
+    let chip8 = machine_with_a_line();
+    let buffer = chip8.render_buffer();
+
+    assert_eq!(buffer.len(), DISPLAY_WIDTH * 32);
+    assert_eq!(&buffer[..5], &[0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0xFFFFFF, 0]);
+}
+
+#[test]
+fn render_rgba_scales_the_display_into_bytes() {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .palette([0x102030, 0xC0FFEE, 0, 0])
+        .build()
+        .unwrap();
+    // DRW V0, V0, 1 with a single lit pixel in the top left
+    let mut chip8 = common::machine_with(&config, &[0xD0, 0x01], |state| state.memory[0] = 0x80);
+    chip8.step().unwrap();
+
+    let scale = 3;
+    let rgba = chip8.render_rgba(scale);
+    assert_eq!(rgba.len(), DISPLAY_WIDTH * scale * 32 * scale * 4);
+
+    // The lit pixel covers the top left 3x3 square
+    let row_bytes = DISPLAY_WIDTH * scale * 4;
+    for y in 0..scale {
+        for x in 0..scale {
+            let offset = y * row_bytes + x * 4;
+            assert_eq!(&rgba[offset..offset + 4], &[0xC0, 0xFF, 0xEE, 0xFF]);
+        }
+    }
+    assert_eq!(&rgba[scale * 4..scale * 4 + 4], &[0x10, 0x20, 0x30, 0xFF]);
+    assert_eq!(
+        &rgba[scale * row_bytes..scale * row_bytes + 4],
+        &[0x10, 0x20, 0x30, 0xFF]
+    );
+}
+
+#[test]
+fn render_hook_replaces_the_palette() {
+    let mut chip8 = machine_with_a_line();
+    chip8.set_render_hook(|lit, width, height| {
+        assert_eq!((width, height), (DISPLAY_WIDTH, 32));
+        lit.iter()
+            .map(|lit| if *lit { 0x000000 } else { 0xFFFFFF })
+            .collect()
+    });
+
+    let buffer = chip8.render_buffer();
+
+    let mut expected = vec![0xFFFFFF; DISPLAY_WIDTH * 32];
+    expected[..4].copy_from_slice(&[0; 4]);
+    assert_eq!(buffer, expected);
+}
+
+/// Draws three copies of the "0" font character side by side in one frame,
+/// then renders once, and returns the machine
+fn three_draws_in_one_frame(coalesce_draws: bool) -> Chip8 {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .coalesce_draws(coalesce_draws)
+        .build()
+        .unwrap();
+    // LD V1, 5; LD V2, 10; DRW V0, V0, 5; DRW V1, V0, 5; DRW V2, V0, 5
+    let program = [0x61, 0x05, 0x62, 0x0A, 0xD0, 0x05, 0xD1, 0x05, 0xD2, 0x05];
+    let mut chip8 = common::machine_with(&config, &program, |state| {
+        state.memory[..5].copy_from_slice(&FONT[..5]);
+    });
+
+    chip8.run_frame(5).unwrap();
+    chip8.render().unwrap();
+    chip8
+}
+
+#[test]
+fn coalesced_draws_are_composed_once_per_frame() {
+    let chip8 = three_draws_in_one_frame(true);
+
+    assert_eq!(chip8.render_count(), 1);
+    let top_row = chip8
+        .framebuffer_ascii()
+        .lines()
+        .next()
+        .unwrap()
+        .to_string();
+    assert!(top_row.starts_with("####.####.####."));
+}
+
+#[test]
+fn uncoalesced_draws_are_each_shown_as_they_happen() {
+    let chip8 = three_draws_in_one_frame(false);
+
+    // The render at the end of the frame has nothing new to show
+    assert_eq!(chip8.render_count(), 3);
+    assert!(!chip8.is_dirty());
+}
+
+#[test]
+fn drawing_only_reaches_the_front_buffer_on_present() {
+    let mut display = Display::headless();
+
+    display.draw_sprite(0, 0, &SQUARE);
+    assert!(display.front_pixels().iter().all(|planes| *planes == 0));
+
+    display.present();
+    assert_eq!(display.front_pixels(), &display.pixels()[..]);
+    assert_eq!(lit_count(display.plane(0)), 16);
+    assert_eq!(
+        display
+            .front_pixels()
+            .iter()
+            .filter(|planes| **planes != 0)
+            .count(),
+        16
+    );
+}
+
+/// A backend whose every update fails, as a window that has gone away might
+struct FailingBackend;
+
+impl Backend for FailingBackend {
+    fn is_open(&self) -> bool {
+        true
+    }
+
+    fn is_key_down(&self, _key: Key) -> bool {
+        false
+    }
+
+    fn is_key_pressed(&self, _key: Key) -> bool {
+        false
+    }
+
+    fn present(&mut self, _pixels: &[u8], _width: usize, _height: usize) -> Result<(), Chip8Error> {
+        Err(Chip8Error::DisplayUpdateFailed)
+    }
+}
+
+#[test]
+fn headless_render_succeeds() {
+    let mut chip8 = common::machine(&[]);
+
+    assert_eq!(chip8.render(), Ok(()));
+}
+
+#[test]
+fn update_failures_surface_as_errors() {
+    let mut chip8 = common::machine(&[]);
+    chip8.set_backend(Box::new(FailingBackend));
+
+    assert_eq!(chip8.render(), Err(Chip8Error::DisplayUpdateFailed));
+}
+
+#[test]
+fn erasures_are_counted_per_rendered_frame() {
+    // LD F, V0; DRW V0, V0, 5, twice over, rendering after each draw
+    let program = [0xF0, 0x29, 0xD0, 0x05, 0xD0, 0x05];
+    let mut chip8 = common::machine_with(&common::config(), &program, |state| {
+        state.memory[..5].copy_from_slice(&FONT[..5]);
+    });
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    chip8.render().unwrap();
+    assert_eq!(chip8.erasures_last_frame(), 0);
+
+    // Drawing the same sprite again erases every pixel the first draw lit
+    chip8.step().unwrap();
+    chip8.render().unwrap();
+    let lit_in_zero: u32 = FONT[..5].iter().map(|row| row.count_ones()).sum();
+    assert_eq!(chip8.erasures_last_frame(), lit_in_zero);
+
+    chip8.render().unwrap();
+    assert_eq!(chip8.erasures_last_frame(), 0);
+}
+
+#[test]
+fn grid_lines_fall_on_the_edges_of_scaled_pixels() {
+    // A 2x1 display with the left pixel lit, at scale 4
+    let mut scaled = scale_colors(&[0xFFFFFF, 0x000000], 2, 4);
+    assert_eq!(scaled.len(), 8 * 4);
+    draw_grid(&mut scaled, 8, 4);
+
+    let at = |x: usize, y: usize| scaled[y * 8 + x];
+    let line_on_white = 0x7F7F7F + 0x404040;
+    let line_on_black = 0x404040;
+
+    // The top row and each pixel's left column are grid lines
+    assert_eq!(at(0, 0), line_on_white);
+    assert_eq!(at(3, 0), line_on_white);
+    assert_eq!(at(0, 2), line_on_white);
+    assert_eq!(at(4, 1), line_on_black);
+    assert_eq!(at(6, 0), line_on_black);
+
+    // Everything else keeps the pixel's color
+    assert_eq!(at(1, 1), 0xFFFFFF);
+    assert_eq!(at(3, 3), 0xFFFFFF);
+    assert_eq!(at(5, 1), 0x000000);
+    assert_eq!(at(7, 3), 0x000000);
+}
+
+#[test]
+fn grid_is_left_out_at_scale_1() {
+    let mut colors = vec![0xFFFFFF; 4];
+    draw_grid(&mut colors, 2, 1);
+
+    assert_eq!(colors, [0xFFFFFF; 4]);
+}
+
+#[test]
+fn grid_does_not_touch_the_display() {
+    let mut display = Display::headless();
+    display.draw_sprite(0, 0, &[0x80]);
+    let before = display.pixels();
+
+    let mut scaled = scale_colors(&display.render_buffer(), display.width(), 8);
+    draw_grid(&mut scaled, display.width() * 8, 8);
+
+    assert_eq!(display.pixels(), before);
+    assert_eq!(display.pixels()[0], 1);
+}
+
+#[test]
+fn render_clears_the_dirty_flag_and_drawing_sets_it() {
+    // LD I, 0x200; DRW V0, V0, 2, which draws these two instructions
+    let mut chip8 = common::machine(&[0xA2, 0x00, 0xD0, 0x02]);
+
+    chip8.render().unwrap();
+    assert!(!chip8.is_dirty());
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert!(chip8.is_dirty());
+
+    chip8.render().unwrap();
+    assert!(!chip8.is_dirty());
+}
+
+/// Counts the frames it is given and the times it is asked for events
+#[derive(Default)]
+struct CountingBackend {
+    frames: Rc<Cell<u32>>,
+    polls: Rc<Cell<u32>>,
+}
+
+impl Backend for CountingBackend {
+    fn is_open(&self) -> bool {
+        true
+    }
+
+    fn is_key_down(&self, _key: Key) -> bool {
+        false
+    }
+
+    fn is_key_pressed(&self, _key: Key) -> bool {
+        false
+    }
+
+    fn present(&mut self, _pixels: &[u8], _width: usize, _height: usize) -> Result<(), Chip8Error> {
+        self.frames.set(self.frames.get() + 1);
+        Ok(())
+    }
+
+    fn poll_events(&mut self) {
+        self.polls.set(self.polls.get() + 1);
+    }
+}
+
+#[test]
+fn clean_frames_only_poll_for_events() {
+    let backend = CountingBackend::default();
+    let (frames, polls) = (backend.frames.clone(), backend.polls.clone());
+    let mut display = Display::with_backend(Box::new(backend));
+
+    display.update_if_dirty().unwrap();
+    display.update_if_dirty().unwrap();
+    display.update_if_dirty().unwrap();
+    assert_eq!((frames.get(), polls.get()), (1, 2));
+
+    display.draw_sprite(0, 0, &SQUARE);
+    display.update_if_dirty().unwrap();
+    assert_eq!((frames.get(), polls.get()), (2, 2));
+
+    display.clear();
+    display.update_if_dirty().unwrap();
+    assert_eq!((frames.get(), polls.get()), (3, 2));
+}
+
+#[test]
+fn total_collisions_counts_each_erased_pixel() {
+    // LD I, line; DRW V0, V0, 1; DRW V1, V0, 1; LD I, dot; DRW V1, V0, 1,
+    // twice; line: 0xE0 (three pixels); dot: 0x80 (one pixel)
+    let program = [
+        0xA2, 0x0C, 0xD0, 0x01, 0xD1, 0x01, 0xA2, 0x0D, 0xD1, 0x01, 0xD1, 0x01, 0xE0, 0x80,
+    ];
+    let mut chip8 = common::machine_with(&common::config(), &program, |state| {
+        state.vx[1] = 1;
+    });
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.total_collisions(), 0);
+    assert_eq!(chip8.state().vx[0xF], 0);
+
+    // The second line is one pixel to the right, so two pixels overlap
+    chip8.step().unwrap();
+    assert_eq!(chip8.total_collisions(), 2);
+    assert_eq!(chip8.state().vx[0xF], 1);
+
+    // x = 1 was erased by the second line, so the dot lights it again
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.total_collisions(), 2);
+    assert_eq!(chip8.state().vx[0xF], 0);
+
+    // The same dot again erases exactly one pixel
+    chip8.step().unwrap();
+    assert_eq!(chip8.total_collisions(), 3);
+    assert_eq!(chip8.state().vx[0xF], 1);
+}
+
+#[test]
+fn wide_sprite_with_an_odd_length_draws_a_half_row() {
+    let mut display = Display::headless();
+
+    // One full 16 pixel row, then a row with only its left byte
+    let erased = display.draw_wide_sprite(0, 0, &[0xFF, 0xFF, 0xF0]);
+    assert_eq!(erased, 0);
+
+    let lit = display.plane(0);
+    assert_eq!(lit_count(lit), 20);
+    assert!(lit[DISPLAY_WIDTH..DISPLAY_WIDTH + 4].iter().all(|lit| *lit));
+    assert!(!lit[DISPLAY_WIDTH + 4]);
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "std")]
+
+use chip8rs::{AccessKind, Chip8, Chip8Error, Config, MemoryAccess};
+
+#[test]
+fn execution_starts_from_the_entry_point() {
+    let rom_path = format!("{}/tests/roms/jumps.ch8", env!("CARGO_MANIFEST_DIR"));
+    let config = Config::builder()
+        .rom_path(&rom_path)
+        .headless(true)
+        .trace_memory(true)
+        .entry_point(0x210)
+        .build()
+        .unwrap();
+
+    let mut chip8 = Chip8::new(&config).unwrap();
+    assert_eq!(chip8.state().pc, 0x210);
+
+    chip8.step().unwrap();
+    assert_eq!(
+        chip8.memory_access_log().unwrap()[0],
+        MemoryAccess {
+            address: 0x210,
+            write: false,
+            kind: AccessKind::Fetch,
+        }
+    );
+}
+
+#[test]
+fn entry_point_outside_ram_is_rejected() {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .entry_point(0xFFF)
+        .build();
+
+    assert!(config.is_err());
+}
+
+fn config_loading_at(load_address: u16) -> Config {
+    Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .load_address(load_address)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn rom_is_loaded_and_started_at_the_load_address() {
+    let chip8 = Chip8::from_rom(&config_loading_at(0x300), &[0x13, 0x00]).unwrap();
+
+    assert_eq!(chip8.state().pc, 0x300);
+    assert_eq!(&chip8.state().memory[0x300..0x302], &[0x13, 0x00]);
+    assert_eq!(&chip8.state().memory[0x200..0x202], &[0x00, 0x00]);
+}
+
+#[test]
+fn load_address_inside_the_font_is_rejected() {
+    let result = Chip8::from_rom(&config_loading_at(0x100), &[0x11, 0x00]);
+
+    assert_eq!(result.err(), Some(Chip8Error::RomOverlapsFont(0x100)));
+}
+
+#[test]
+fn entry_point_past_the_rom_is_reported() {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .entry_point(0x300)
+        .build()
+        .unwrap();
+
+    let chip8 = Chip8::from_rom(&config, &[0x12, 0x00]).unwrap();
+    assert!(chip8.entry_point_outside_rom());
+
+    let chip8 = Chip8::from_rom(&config_loading_at(0x200), &[0x12, 0x00]).unwrap();
+    assert!(!chip8.entry_point_outside_rom());
+}
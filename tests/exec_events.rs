@@ -0,0 +1,70 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use chip8rs::asm::assemble;
+use chip8rs::ExecEvent;
+
+#[test]
+fn exec_events_describe_each_instruction() {
+    let program = assemble("LD V0, 0xFF\nLD V1, 1\nADD V0, V1\nJP 0x200").unwrap();
+    let mut chip8 = common::machine(&program);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let collected = Rc::clone(&events);
+    chip8.on_exec(move |event| collected.borrow_mut().push(event));
+
+    for _ in 0..4 {
+        chip8.step().unwrap();
+    }
+
+    assert_eq!(
+        *events.borrow(),
+        vec![
+            ExecEvent {
+                pc: 0x200,
+                opcode: 0x60FF,
+                mnemonic: "LD",
+                pc_after: 0x202,
+                vf_changed: false,
+            },
+            ExecEvent {
+                pc: 0x202,
+                opcode: 0x6101,
+                mnemonic: "LD",
+                pc_after: 0x204,
+                vf_changed: false,
+            },
+            ExecEvent {
+                pc: 0x204,
+                opcode: 0x8014,
+                mnemonic: "ADD",
+                pc_after: 0x206,
+                vf_changed: true,
+            },
+            ExecEvent {
+                pc: 0x206,
+                opcode: 0x1200,
+                mnemonic: "JP",
+                pc_after: 0x200,
+                vf_changed: false,
+            },
+        ]
+    );
+}
+
+#[test]
+fn failed_instructions_are_not_reported() {
+    // An unknown opcode
+    let mut chip8 = common::machine(&[0xFF, 0xFF]);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let collected = Rc::clone(&events);
+    chip8.on_exec(move |event| collected.borrow_mut().push(event));
+
+    assert!(chip8.step().is_err());
+    assert!(events.borrow().is_empty());
+}
@@ -0,0 +1,184 @@
+#![cfg(feature = "std")]
+
+use chip8rs::{
+    big_font_address, font_address, Chip8, Config, FontLayout, BIG_FONT_ADDRESS, BIG_FONT_STRIDE,
+    FONT, PROGRAM_START,
+};
+
+fn machine() -> Chip8 {
+    let rom_path = format!("{}/tests/roms/golden.ch8", env!("CARGO_MANIFEST_DIR"));
+    let config = Config::builder()
+        .rom_path(&rom_path)
+        .headless(true)
+        .build()
+        .unwrap();
+
+    Chip8::new(&config).unwrap()
+}
+
+#[test]
+fn font_is_loaded_where_font_address_says() {
+    let chip8 = machine();
+    let memory = chip8.state().memory;
+
+    assert_eq!(font_address(0xA), 0x32);
+    assert_eq!(&memory[0x32..0x37], &FONT[0x32..0x37]);
+    assert_eq!(&memory[..FONT.len()], &FONT[..]);
+}
+
+#[test]
+fn font_addresses_only_use_the_low_nibble() {
+    assert_eq!(font_address(0x1F), font_address(0xF));
+    assert_eq!(big_font_address(0x12), big_font_address(0x2));
+    assert_eq!(big_font_address(0x0) as usize, BIG_FONT_ADDRESS);
+}
+
+#[test]
+fn fonts_are_left_out_when_init_font_is_off() {
+    let config = |init_font| {
+        Config::builder()
+            .rom_path("unused.ch8")
+            .headless(true)
+            .big_font(true)
+            .init_font(init_font)
+            .build()
+            .unwrap()
+    };
+
+    let chip8 = Chip8::from_rom(&config(false), &[0x12, 0x00]).unwrap();
+    let memory = chip8.state().memory;
+    assert!(memory[..PROGRAM_START].iter().all(|byte| *byte == 0));
+
+    let chip8 = Chip8::from_rom(&config(true), &[0x12, 0x00]).unwrap();
+    let memory = chip8.state().memory;
+    assert_eq!(&memory[..FONT.len()], &FONT[..]);
+}
+
+#[test]
+fn big_font_fills_ten_bytes_per_digit_after_the_small_font() {
+    let config = |big_font| {
+        Config::builder()
+            .rom_path("unused.ch8")
+            .headless(true)
+            .big_font(big_font)
+            .build()
+            .unwrap()
+    };
+    let big_font_end = BIG_FONT_ADDRESS + 16 * BIG_FONT_STRIDE;
+
+    let memory = Chip8::from_rom(&config(true), &[0x12, 0x00])
+        .unwrap()
+        .state()
+        .memory;
+    let eight = big_font_address(0x8) as usize;
+    assert_eq!(
+        &memory[eight..eight + BIG_FONT_STRIDE],
+        &[0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF]
+    );
+    assert!(memory[BIG_FONT_ADDRESS..big_font_end]
+        .chunks(BIG_FONT_STRIDE)
+        .all(|digit| digit.iter().any(|byte| *byte != 0)));
+    assert!(memory[big_font_end..PROGRAM_START]
+        .iter()
+        .all(|byte| *byte == 0));
+
+    let memory = Chip8::from_rom(&config(false), &[0x12, 0x00])
+        .unwrap()
+        .state()
+        .memory;
+    assert!(memory[BIG_FONT_ADDRESS..big_font_end]
+        .iter()
+        .all(|byte| *byte == 0));
+}
+
+#[test]
+fn font_bytes_replace_the_small_font() {
+    let font: Vec<u8> = (0..FONT.len() as u8).collect();
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .font_bytes(font.clone())
+        .build()
+        .unwrap();
+
+    let memory = Chip8::from_rom(&config, &[0x12, 0x00])
+        .unwrap()
+        .state()
+        .memory;
+    assert_eq!(&memory[..FONT.len()], &font[..]);
+}
+
+/// Runs LD V0, 3; LD F, V0; LD V1, 3; LD HF, V1 with both fonts loaded and
+/// returns I after each of the font lookups
+fn digit_3_addresses(font_layout: FontLayout) -> (u16, u16) {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .big_font(true)
+        .font_layout(font_layout)
+        .build()
+        .unwrap();
+    let program = [0x60, 0x03, 0xF0, 0x29, 0x61, 0x03, 0xF1, 0x30];
+    let mut chip8 = Chip8::from_rom(&config, &program).unwrap();
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    let small = chip8.state().i;
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+
+    (small, chip8.state().i)
+}
+
+#[test]
+fn fx29_and_fx30_use_their_own_font_regions() {
+    let (small, big) = digit_3_addresses(FontLayout::STANDARD);
+
+    assert_eq!(small, 3 * 5);
+    assert_eq!(big, BIG_FONT_ADDRESS as u16 + 3 * 10);
+}
+
+#[test]
+fn fx29_and_fx30_follow_a_moved_layout() {
+    let layout = FontLayout {
+        small_base: 0x100,
+        small_stride: 8,
+        big_base: 0x20,
+        big_stride: 12,
+    };
+    let (small, big) = digit_3_addresses(layout);
+    assert_eq!(small, 0x100 + 3 * 8);
+    assert_eq!(big, 0x20 + 3 * 12);
+
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .big_font(true)
+        .font_layout(layout)
+        .build()
+        .unwrap();
+    let memory = Chip8::from_rom(&config, &[0x12, 0x00])
+        .unwrap()
+        .state()
+        .memory;
+    assert_eq!(&memory[small as usize..small as usize + 5], &FONT[15..20]);
+    assert_eq!(memory[big as usize + 10..big as usize + 12], [0, 0]);
+}
+
+#[test]
+fn overlapping_font_layouts_are_rejected() {
+    let build = |big_base| {
+        Config::builder()
+            .rom_path("unused.ch8")
+            .big_font(true)
+            .font_layout(FontLayout {
+                big_base,
+                ..FontLayout::STANDARD
+            })
+            .build()
+    };
+
+    assert!(build(0x40).is_err());
+    assert!(build(0x50).is_ok());
+    assert!(build(0x1F0).is_err());
+}
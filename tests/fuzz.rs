@@ -0,0 +1,102 @@
+mod common;
+
+use std::panic;
+
+use chip8rs::{Chip8State, Config, Quirks, RAM_SIZE};
+
+/// Runs every possible opcode from a fresh machine and returns the opcodes
+/// that panicked. Errors are fine. Each opcode is placed at 0x200 before
+/// `setup` adjusts the state, and two steps are run so anything the first
+/// instruction leaves behind is used
+fn panicking_opcodes<F>(config: &Config, setup: F) -> Vec<u16>
+where
+    F: Fn(u16, &mut Chip8State) + panic::RefUnwindSafe,
+{
+    (0..=u16::MAX)
+        .filter(|opcode| {
+            panic::catch_unwind(|| {
+                let mut chip8 = common::machine_with(config, &opcode.to_be_bytes(), |state| {
+                    setup(*opcode, state)
+                });
+                let _ = chip8.step();
+                let _ = chip8.step();
+            })
+            .is_err()
+        })
+        .collect()
+}
+
+#[test]
+fn no_opcode_panics_from_a_blank_machine() {
+    assert_eq!(panicking_opcodes(&common::config(), |_, _| {}), []);
+}
+
+#[test]
+fn no_opcode_panics_with_every_register_at_its_limit() {
+    let panicked = panicking_opcodes(&common::config(), |_, state| {
+        state.vx = [0xFF; 16];
+        state.i = 0xFFFF;
+        state.sp = 16;
+        state.stack = vec![0xFFFF; 16];
+        state.dt = 0xFF;
+        state.st = 0xFF;
+    });
+
+    assert_eq!(panicked, []);
+}
+
+#[test]
+fn no_opcode_panics_with_i_at_the_end_of_ram() {
+    let panicked = panicking_opcodes(&common::config(), |_, state| {
+        state.i = 0xFFF;
+        state.vx = [0x0F; 16];
+    });
+
+    assert_eq!(panicked, []);
+}
+
+#[test]
+fn no_opcode_panics_from_the_last_word_of_ram() {
+    let panicked = panicking_opcodes(&common::config(), |opcode, state| {
+        state.memory[RAM_SIZE - 2..].copy_from_slice(&opcode.to_be_bytes());
+        state.pc = (RAM_SIZE - 2) as u16;
+        state.i = 0xFFF;
+        state.vx = [0xFF; 16];
+    });
+
+    assert_eq!(panicked, []);
+}
+
+#[test]
+fn no_opcode_panics_with_sprites_clipped() {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .quirks(Quirks {
+            wrap_start: false,
+            strict_alignment: true,
+            strict_sys: true,
+            ..Quirks::schip()
+        })
+        .build()
+        .unwrap();
+
+    let panicked = panicking_opcodes(&config, |_, state| {
+        state.vx = [0xFF; 16];
+        state.i = 0xF00;
+    });
+
+    assert_eq!(panicked, []);
+}
+
+#[test]
+fn no_opcode_panics_with_strict_uninit() {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .strict_uninit(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(panicking_opcodes(&config, |_, _| {}), []);
+}
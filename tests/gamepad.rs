@@ -0,0 +1,96 @@
+#![cfg(feature = "gamepad")]
+
+mod common;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use chip8rs::{
+    Backend, Button, Chip8Error, GamepadBinding, GamepadKeyboard, GamepadSource, Keyboard,
+};
+use minifb::Key;
+
+/// A gamepad where only the button in the shared cell is held
+struct MockGamepad(Rc<Cell<Option<Button>>>);
+
+impl GamepadSource for MockGamepad {
+    fn is_pressed(&self, button: Button) -> bool {
+        self.0.get() == Some(button)
+    }
+}
+
+/// A window where only the given key is held
+struct HeldKey(Key);
+
+impl Backend for HeldKey {
+    fn is_open(&self) -> bool {
+        true
+    }
+
+    fn is_key_down(&self, key: Key) -> bool {
+        key == self.0
+    }
+
+    fn is_key_pressed(&self, key: Key) -> bool {
+        key == self.0
+    }
+
+    fn present(&mut self, _pixels: &[u8], _width: usize, _height: usize) -> Result<(), Chip8Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn bound_button_presses_its_key() {
+    let gamepad = MockGamepad(Rc::new(Cell::new(Some(Button::South))));
+    let binding = GamepadBinding::empty().bind(Button::South, 0x7);
+    let keyboard = GamepadKeyboard::new(&gamepad, &binding);
+
+    assert!(keyboard.is_down(0x7));
+    assert!((0..16)
+        .filter(|&key| key != 0x7)
+        .all(|key| !keyboard.is_down(key)));
+}
+
+#[test]
+fn unbound_button_presses_nothing() {
+    let gamepad = MockGamepad(Rc::new(Cell::new(Some(Button::Select))));
+    let binding = GamepadBinding::default();
+    let keyboard = GamepadKeyboard::new(&gamepad, &binding);
+
+    assert!((0..16).all(|key| !keyboard.is_down(key)));
+}
+
+#[test]
+fn gamepad_keys_follow_the_buttons_each_poll() {
+    let held = Rc::new(Cell::new(None));
+    let mut chip8 = common::machine(&[]);
+    chip8.set_gamepad(
+        Box::new(MockGamepad(Rc::clone(&held))),
+        GamepadBinding::default(),
+    );
+
+    held.set(Some(Button::DPadUp));
+    chip8.poll_keyboard();
+    assert_eq!(chip8.key_held_frames(0x2), 1);
+
+    held.set(None);
+    chip8.poll_keyboard();
+    assert_eq!(chip8.key_held_frames(0x2), 0);
+}
+
+#[test]
+fn keyboard_and_gamepad_are_combined() {
+    let held = Rc::new(Cell::new(Some(Button::South)));
+    let mut chip8 = common::machine(&[]);
+    chip8.set_backend(Box::new(HeldKey(Key::Key1)));
+    chip8.set_gamepad(
+        Box::new(MockGamepad(Rc::clone(&held))),
+        GamepadBinding::default(),
+    );
+
+    chip8.poll_keyboard();
+    assert_eq!(chip8.key_held_frames(0x1), 1);
+    assert_eq!(chip8.key_held_frames(0x5), 1);
+    assert_eq!(chip8.key_held_frames(0x2), 0);
+}
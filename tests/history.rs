@@ -0,0 +1,118 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use chip8rs::asm::assemble;
+use chip8rs::Chip8Error;
+
+const PROGRAM: &str = "
+    LD V0, 0x42
+    LD I, 0x300
+    LD [I], V0
+    ADD V0, 1
+";
+
+#[test]
+fn stepping_back_restores_the_earlier_state() {
+    let mut chip8 = common::machine_with(
+        &common::config_with(|b| b.history_size(8)),
+        &assemble(PROGRAM).unwrap(),
+        |_| {},
+    );
+
+    chip8.step().unwrap();
+    let after_first = chip8.state();
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().memory[0x300], 0x42);
+
+    chip8.step_back().unwrap();
+    chip8.step_back().unwrap();
+
+    assert_eq!(chip8.state(), after_first);
+    assert_eq!(chip8.state().pc, 0x202);
+    assert_eq!(chip8.state().vx[0], 0x42);
+    assert_eq!(chip8.state().i, 0);
+    assert_eq!(chip8.state().memory[0x300], 0);
+    assert_eq!(chip8.cycle_count(), 1);
+}
+
+#[test]
+fn history_only_reaches_back_as_far_as_its_size() {
+    let mut chip8 = common::machine_with(
+        &common::config_with(|b| b.history_size(2)),
+        &assemble(PROGRAM).unwrap(),
+        |_| {},
+    );
+
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+
+    chip8.step_back().unwrap();
+    chip8.step_back().unwrap();
+    assert_eq!(chip8.state().pc, 0x202);
+    assert_eq!(chip8.step_back(), Err(Chip8Error::NoHistory));
+}
+
+#[test]
+fn history_is_off_by_default() {
+    let mut chip8 = common::machine(&assemble(PROGRAM).unwrap());
+
+    chip8.step().unwrap();
+
+    assert_eq!(chip8.step_back(), Err(Chip8Error::NoHistory));
+    assert_eq!(chip8.state().pc, 0x202);
+}
+
+#[test]
+fn failed_instructions_are_not_kept() {
+    // LD V0, 1; RET with nothing on the stack
+    let mut chip8 = common::machine_with(
+        &common::config_with(|b| b.history_size(8)),
+        &[0x60, 0x01, 0x00, 0xEE],
+        |_| {},
+    );
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.step(), Err(Chip8Error::StackUnderflow));
+
+    // The failed RET left nothing to undo, so this undoes the LD
+    chip8.step_back().unwrap();
+    assert_eq!(chip8.state().pc, 0x200);
+    assert_eq!(chip8.state().vx[0], 0);
+    assert_eq!(chip8.cycle_count(), 0);
+    assert_eq!(chip8.step_back(), Err(Chip8Error::NoHistory));
+}
+
+#[test]
+fn unknown_opcodes_are_not_kept() {
+    let mut chip8 = common::machine_with(
+        &common::config_with(|b| b.history_size(8)),
+        &[0xFF, 0xFF],
+        |_| {},
+    );
+
+    assert_eq!(chip8.step(), Err(Chip8Error::UnknownOpcode(0xFFFF)));
+    assert_eq!(chip8.step_back(), Err(Chip8Error::NoHistory));
+    assert_eq!(chip8.cycle_count(), 0);
+}
+
+#[test]
+fn refused_opcodes_are_not_kept() {
+    let config = common::config_with(|b| {
+        b.history_size(8)
+            .allowed_opcodes(|opcode| opcode >> 12 == 0x6)
+    });
+    // LD V0, 1; JP 0x200
+    let mut chip8 = common::machine_with(&config, &[0x60, 0x01, 0x12, 0x00], |_| {});
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.step(), Err(Chip8Error::DisallowedOpcode(0x1200)));
+    assert_eq!(chip8.cycle_count(), 1);
+
+    chip8.step_back().unwrap();
+    assert_eq!(chip8.state().pc, 0x200);
+    assert_eq!(chip8.state().vx[0], 0);
+    assert_eq!(chip8.cycle_count(), 0);
+}
@@ -0,0 +1,81 @@
+mod common;
+
+#[test]
+fn state_report_shows_registers_and_the_next_instruction() {
+    // CALL 0x204; JP 0x204; LD V0, 0x2A
+    let program = [0x22, 0x04, 0x12, 0x04, 0x60, 0x2A];
+    let mut chip8 = common::machine_with(&common::config(), &program, |state| {
+        state.vx[0xA] = 0x5C;
+        state.i = 0x321;
+        state.dt = 0x10;
+        state.st = 0x03;
+    });
+    chip8.step().unwrap();
+
+    let report = chip8.state_report();
+    for expected in [
+        "PC: 0x0204",
+        "I: 0x0321",
+        "SP: 0x01",
+        "DT: 0x10",
+        "ST: 0x03",
+        "5C",
+        "0202",
+        "Next: 602A  LD V0, 0x2A",
+    ] {
+        assert!(
+            report.contains(expected),
+            "{:?} not in\n{}",
+            expected,
+            report
+        );
+    }
+}
+
+#[test]
+fn state_report_handles_a_pc_at_the_end_of_memory() {
+    let chip8 = common::machine_with(&common::config(), &[], |state| {
+        state.pc = 0xFFF;
+    });
+
+    assert!(chip8.state_report().contains("Next: out of bounds"));
+}
+
+#[test]
+fn disasm_window_is_centred_on_the_pc() {
+    // LD V0, 1; LD V1, 2; LD V2, 3; LD V3, 4; LD V4, 5
+    let program = [0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0x63, 0x04, 0x64, 0x05];
+    let chip8 = common::machine_with(&common::config(), &program, |state| {
+        state.pc = 0x204;
+    });
+
+    let window = chip8.disasm_window(2);
+    let addresses: Vec<u16> = window.iter().map(|(address, _)| *address).collect();
+    assert_eq!(addresses, [0x200, 0x202, 0x204, 0x206, 0x208]);
+    assert_eq!(window[2].1, "LD V2, 0x03");
+    assert_eq!(window[4].1, "LD V4, 0x05");
+}
+
+#[test]
+fn disasm_window_is_cut_short_at_the_start_of_memory() {
+    let chip8 = common::machine_with(&common::config(), &[], |state| {
+        state.pc = 0x002;
+    });
+
+    let window = chip8.disasm_window(3);
+    assert_eq!(window.first().unwrap().0, 0x000);
+    assert_eq!(window.last().unwrap().0, 0x008);
+    assert_eq!(window.len(), 5);
+}
+
+#[test]
+fn disasm_window_is_cut_short_at_the_end_of_memory() {
+    let chip8 = common::machine_with(&common::config(), &[], |state| {
+        state.pc = 0xFFC;
+    });
+
+    let window = chip8.disasm_window(3);
+    assert_eq!(window.first().unwrap().0, 0xFF6);
+    assert_eq!(window.last().unwrap().0, 0xFFE);
+    assert_eq!(window.len(), 5);
+}
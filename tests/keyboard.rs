@@ -0,0 +1,214 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use chip8rs::{Backend, Chip8, Chip8Error, Config};
+use minifb::Key;
+
+// LD V0, 5 then SKP V0
+const SKIP_IF_5: [u8; 4] = [0x60, 0x05, 0xE0, 0x9E];
+
+#[test]
+fn set_key_is_seen_by_skp() {
+    let mut chip8 = common::machine(&SKIP_IF_5);
+    chip8.set_key(5, true);
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().pc, 0x206);
+}
+
+#[test]
+fn released_key_is_not_seen_by_skp() {
+    let mut chip8 = common::machine(&SKIP_IF_5);
+    chip8.set_key(5, true);
+    chip8.set_key(5, false);
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().pc, 0x204);
+}
+
+/// Where PC ends up after `LD V0, 0xFF` and then `opcode`, with only `key`
+/// held
+fn pc_after_key_skip(opcode: [u8; 2], key: u8) -> u16 {
+    let mut chip8 = common::machine(&[0x60, 0xFF, opcode[0], opcode[1]]);
+    chip8.set_key(key, true);
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    chip8.state().pc
+}
+
+#[test]
+fn out_of_range_vx_queries_the_masked_key() {
+    // SKP V0 and SKNP V0 with V0 = 0xFF look at key F
+    assert_eq!(pc_after_key_skip([0xE0, 0x9E], 0xF), 0x206);
+    assert_eq!(pc_after_key_skip([0xE0, 0x9E], 0x0), 0x204);
+    assert_eq!(pc_after_key_skip([0xE0, 0xA1], 0xF), 0x204);
+    assert_eq!(pc_after_key_skip([0xE0, 0xA1], 0x0), 0x206);
+}
+
+#[test]
+fn key_tapped_between_polls_is_seen_by_skp() {
+    let mut chip8 = common::machine(&SKIP_IF_5);
+    chip8.set_key(5, true);
+    chip8.set_key(5, false);
+    chip8.poll_keyboard();
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().pc, 0x206);
+}
+
+#[test]
+fn latched_key_is_released_by_the_following_poll() {
+    let mut chip8 = common::machine(&SKIP_IF_5);
+    chip8.set_key(5, true);
+    chip8.set_key(5, false);
+    chip8.poll_keyboard();
+    chip8.poll_keyboard();
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().pc, 0x204);
+}
+
+#[test]
+fn key_held_frames_counts_polls_while_down() {
+    let mut chip8 = common::machine(&SKIP_IF_5);
+    chip8.set_key(3, true);
+
+    for _ in 0..5 {
+        chip8.poll_keyboard();
+    }
+    assert_eq!(chip8.key_held_frames(3), 5);
+    assert_eq!(chip8.key_held_frames(4), 0);
+
+    chip8.set_key(3, false);
+    chip8.poll_keyboard();
+    assert_eq!(chip8.key_held_frames(3), 0);
+}
+
+/// A backend where only the given physical key is held down
+struct HeldKey(Key);
+
+impl Backend for HeldKey {
+    fn is_open(&self) -> bool {
+        true
+    }
+
+    fn is_key_down(&self, key: Key) -> bool {
+        key == self.0
+    }
+
+    fn is_key_pressed(&self, key: Key) -> bool {
+        key == self.0
+    }
+
+    fn present(&mut self, _pixels: &[u8], _width: usize, _height: usize) -> Result<(), Chip8Error> {
+        Ok(())
+    }
+}
+
+/// Runs LD V0, `key` then SKP V0 with only `held` down, and returns whether
+/// the skip was taken
+fn skp_sees(numeric_keypad: bool, held: Key, key: u8) -> bool {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .numeric_keypad(numeric_keypad)
+        .build()
+        .unwrap();
+    let mut chip8 = common::machine_with(&config, &[0x60, key, 0xE0, 0x9E], |_| {});
+    chip8.set_backend(Box::new(HeldKey(held)));
+    chip8.poll_keyboard();
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    chip8.state().pc == 0x206
+}
+
+#[test]
+fn numeric_keypad_binds_keys_to_their_labels() {
+    assert!(skp_sees(true, Key::Key1, 1));
+    assert!(skp_sees(true, Key::Key4, 4));
+    assert!(skp_sees(true, Key::Key0, 0));
+    assert!(skp_sees(true, Key::F, 0xF));
+    assert!(!skp_sees(true, Key::Q, 4));
+}
+
+#[test]
+fn default_keypad_follows_the_cosmac_layout() {
+    assert!(skp_sees(false, Key::Key1, 1));
+    assert!(skp_sees(false, Key::Q, 4));
+    assert!(skp_sees(false, Key::X, 0));
+    assert!(!skp_sees(false, Key::Key4, 4));
+}
+
+/// A machine waiting on LD V0, K, with the given debounce
+fn key_wait_machine(key_debounce_ticks: u32) -> Chip8 {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .key_debounce_ticks(key_debounce_ticks)
+        .build()
+        .unwrap();
+    let mut chip8 = common::machine_with(&config, &[0xF0, 0x0A], |_| {});
+
+    chip8.poll_keyboard();
+    chip8.step().unwrap();
+    chip8
+}
+
+/// Polls the keypad, runs the instruction and ticks the timers, like the
+/// main loop does once a frame
+fn key_wait_frame(chip8: &mut Chip8) {
+    chip8.poll_keyboard();
+    chip8.step().unwrap();
+    chip8.tick_timers();
+}
+
+#[test]
+fn key_wait_ignores_a_press_shorter_than_a_tick() {
+    let mut chip8 = key_wait_machine(1);
+
+    chip8.set_key(7, true);
+    chip8.step().unwrap();
+    chip8.set_key(7, false);
+    chip8.tick_timers();
+
+    for _ in 0..5 {
+        key_wait_frame(&mut chip8);
+    }
+    assert_eq!(chip8.state().pc, 0x200);
+    assert_eq!(chip8.state().vx[0], 0);
+}
+
+#[test]
+fn key_wait_completes_once_the_key_is_held_for_a_full_tick() {
+    let mut chip8 = key_wait_machine(1);
+    chip8.set_key(7, true);
+
+    // The first tick only finishes the frame the key went down in
+    chip8.step().unwrap();
+    chip8.tick_timers();
+    assert_eq!(chip8.state().pc, 0x200);
+
+    key_wait_frame(&mut chip8);
+    assert_eq!(chip8.state().pc, 0x200);
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().pc, 0x202);
+    assert_eq!(chip8.state().vx[0], 7);
+}
+
+#[test]
+fn key_wait_without_debounce_completes_straight_away() {
+    let mut chip8 = key_wait_machine(0);
+    chip8.set_key(7, true);
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().pc, 0x202);
+    assert_eq!(chip8.state().vx[0], 7);
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "std")]
+
+use chip8rs::asm::assemble;
+use chip8rs::lint::{lint_rom, LintKind, LintWarning};
+
+#[test]
+fn clean_rom_has_no_warnings() {
+    let rom = assemble(
+        "
+            LD I, sprite
+            CALL draw
+        halt:
+            JP halt
+        draw:
+            DRW V0, V1, 1
+            RET
+        sprite:
+            DW 0xFFFF   ; data after the code isn't linted
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(lint_rom(&rom), vec![]);
+}
+
+#[test]
+fn reports_unknown_opcodes_and_jumps_out_of_the_rom() {
+    let rom = assemble(
+        "
+            SE V0, 0
+            DW 0x8008   ; not an instruction
+            JP 0x800    ; past the end of the ROM
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(
+        lint_rom(&rom),
+        vec![
+            LintWarning {
+                address: 0x202,
+                kind: LintKind::UnknownOpcode(0x8008),
+            },
+            LintWarning {
+                address: 0x204,
+                kind: LintKind::JumpOutOfRom(0x800),
+            },
+        ]
+    );
+}
+
+#[test]
+fn reports_calls_out_of_the_rom() {
+    let rom = assemble("CALL 0x100\nhalt: JP halt").unwrap();
+
+    assert_eq!(
+        lint_rom(&rom),
+        vec![LintWarning {
+            address: 0x200,
+            kind: LintKind::CallOutOfRom(0x100),
+        }]
+    );
+}
+
+#[test]
+fn reports_unbounded_recursion_and_stray_returns() {
+    let rom = assemble(
+        "
+        start:
+            CALL start
+            RET
+        ",
+    )
+    .unwrap();
+
+    let kinds: Vec<LintKind> = lint_rom(&rom).iter().map(|warning| warning.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![LintKind::StackOverflow, LintKind::StackUnderflow]
+    );
+}
+
+#[test]
+fn warnings_display_their_address() {
+    let warning = LintWarning {
+        address: 0x2A4,
+        kind: LintKind::UnknownOpcode(0xE0FF),
+    };
+
+    assert_eq!(warning.to_string(), "0x02A4: Unknown opcode 0xE0FF");
+}
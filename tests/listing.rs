@@ -0,0 +1,35 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::fs;
+
+use chip8rs::asm::assemble;
+
+#[test]
+fn export_listing_writes_one_line_per_instruction() {
+    let chip8 = common::machine(&assemble("LD V0, 0x05\nLD F, V0\nhalt: JP halt").unwrap());
+    let path = std::env::temp_dir().join("chip8rs_export_listing.txt");
+
+    chip8.export_listing(&path, 0x200, 0x204).unwrap();
+    let listing = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let lines: Vec<&str> = listing.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "0x0200  6005  LD V0, 0x05");
+    assert_eq!(lines[2], "0x0204  1204  JP 0x204");
+}
+
+#[test]
+fn export_listing_stops_at_the_end_of_ram() {
+    let chip8 = common::machine(&[]);
+    let path = std::env::temp_dir().join("chip8rs_export_listing_end.txt");
+
+    chip8.export_listing(&path, 0xFFA, 0xFFFF).unwrap();
+    let listing = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(listing.lines().count(), 3);
+    assert!(listing.lines().last().unwrap().starts_with("0x0FFE  0000"));
+}
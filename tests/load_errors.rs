@@ -0,0 +1,83 @@
+//! `Chip8::new` reports problems with the ROM and data files as errors
+//! rather than exiting, so embedders can handle them
+
+#![cfg(feature = "std")]
+
+use std::fs;
+use std::path::PathBuf;
+
+use chip8rs::{Chip8, Chip8Error, Config, ConfigBuilder};
+
+/// Writes `contents` to a file in the temp directory and returns its path
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn new_machine(rom_path: &str, data_path: Option<&str>) -> Result<Chip8, Chip8Error> {
+    new_machine_with(rom_path, |builder| match data_path {
+        Some(data_path) => builder.data_file(data_path).data_offset(0x400),
+        None => builder,
+    })
+}
+
+/// Builds a machine with `Chip8::new`, letting the caller adjust the config
+fn new_machine_with<F>(rom_path: &str, setup: F) -> Result<Chip8, Chip8Error>
+where
+    F: FnOnce(ConfigBuilder) -> ConfigBuilder,
+{
+    let builder = setup(Config::builder().rom_path(rom_path).headless(true));
+
+    Chip8::new(&builder.build().unwrap())
+}
+
+#[test]
+fn missing_rom_is_an_error() {
+    let result = new_machine("/nonexistent/chip8rs_missing.ch8", None);
+
+    assert_eq!(result.err(), Some(Chip8Error::RomReadFailed));
+}
+
+#[test]
+fn missing_data_file_is_an_error() {
+    let rom = temp_file("chip8rs_load_errors_data_rom.ch8", &[0x12, 0x00]);
+    let result = new_machine(
+        rom.to_str().unwrap(),
+        Some("/nonexistent/chip8rs_missing.bin"),
+    );
+    fs::remove_file(&rom).unwrap();
+
+    assert_eq!(result.err(), Some(Chip8Error::DataReadFailed));
+}
+
+#[test]
+fn empty_rom_is_an_error() {
+    let rom = temp_file("chip8rs_load_errors_empty.ch8", &[]);
+    let result = new_machine(rom.to_str().unwrap(), None);
+    fs::remove_file(&rom).unwrap();
+
+    assert_eq!(result.err(), Some(Chip8Error::EmptyRom));
+}
+
+#[test]
+fn rom_loaded_over_the_font_is_an_error() {
+    let rom = temp_file("chip8rs_load_errors_low.ch8", &[0x12, 0x00]);
+    let result = new_machine_with(rom.to_str().unwrap(), |builder| builder.load_address(0x100));
+    fs::remove_file(&rom).unwrap();
+
+    assert_eq!(result.err(), Some(Chip8Error::RomOverlapsFont(0x100)));
+}
+
+#[test]
+fn data_file_over_the_rom_is_an_error() {
+    let rom = temp_file("chip8rs_load_errors_overlap_rom.ch8", &[0x12, 0x00]);
+    let data = temp_file("chip8rs_load_errors_overlap.bin", &[1, 2]);
+    let result = new_machine_with(rom.to_str().unwrap(), |builder| {
+        builder.data_file(data.to_str().unwrap()).data_offset(0x200)
+    });
+    fs::remove_file(&rom).unwrap();
+    fs::remove_file(&data).unwrap();
+
+    assert_eq!(result.err(), Some(Chip8Error::DataOverlaps(0x200)));
+}
@@ -0,0 +1,75 @@
+use chip8rs::{parse_cheats, Chip8, Config, PROGRAM_START};
+
+/// Selects plane 2, then draws the "0" font sprite at (0, 0)
+const DRAWING_ROM: [u8; 8] = [0xF2, 0x01, 0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05];
+
+fn config(cheats: &str) -> Config {
+    Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .cheats(parse_cheats(cheats).unwrap())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn swapping_roms_resets_the_pc_and_replaces_the_program() {
+    let mut chip8 = Chip8::from_rom(&config(""), &[0x60, 0x01, 0x61, 0x02, 0x12, 0x00]).unwrap();
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+
+    chip8.load_rom(&[0x63, 0x07, 0x12, 0x00]).unwrap();
+
+    let state = chip8.state();
+    assert_eq!(state.pc as usize, PROGRAM_START);
+    assert_eq!(state.vx[0], 0);
+    assert_eq!(
+        &state.memory[PROGRAM_START..PROGRAM_START + 6],
+        &[0x63, 0x07, 0x12, 0x00, 0x00, 0x00]
+    );
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().vx[3], 0x07);
+}
+
+#[test]
+fn swapping_roms_blanks_every_plane() {
+    let mut chip8 = Chip8::from_rom(&config(""), &DRAWING_ROM).unwrap();
+    for _ in 0..4 {
+        chip8.step().unwrap();
+    }
+    assert!(chip8.is_pixel_lit(0, 0));
+
+    chip8.load_rom(&[0x12, 0x00]).unwrap();
+
+    assert!(!chip8.framebuffer_ascii().contains('#'));
+}
+
+#[test]
+fn swapping_roms_writes_the_cheats_again() {
+    let mut chip8 = Chip8::from_rom(&config("0x300=0x42"), &[0x12, 0x00]).unwrap();
+    assert_eq!(chip8.read_byte_checked(0x300), Ok(0x42));
+
+    chip8.load_rom(&[0x13, 0x00]).unwrap();
+
+    assert_eq!(chip8.read_byte_checked(0x300), Ok(0x42));
+}
+
+#[test]
+fn swapping_roms_keeps_the_data_file() {
+    let mut chip8 = Chip8::from_rom(&config(""), &[0x12, 0x00]).unwrap();
+    chip8.load_data(&[0xAB, 0xCD], 0x400).unwrap();
+
+    chip8.load_rom(&[0x13, 0x00]).unwrap();
+
+    assert_eq!(chip8.memory_slice(0x400..0x402), Ok(&[0xAB, 0xCD][..]));
+}
+
+#[test]
+fn a_rom_overlapping_the_data_file_is_refused() {
+    let mut chip8 = Chip8::from_rom(&config(""), &[0x12, 0x00]).unwrap();
+    chip8.load_data(&[0xAB], 0x202).unwrap();
+
+    assert!(chip8.load_rom(&[0x00; 4]).is_err());
+    assert_eq!(chip8.read_byte_checked(0x202), Ok(0xAB));
+}
@@ -0,0 +1,212 @@
+mod common;
+
+use chip8rs::{font_address, Chip8, Chip8Error, Ram, StepOutcome, FONT, RAM_SIZE};
+
+#[test]
+fn drawing_past_the_end_of_ram_is_an_error() {
+    // DRW V0, V0, 5 with only two bytes of RAM left
+    let mut chip8 = common::machine_with(&common::config(), &[0xD0, 0x05], |state| state.i = 0xFFE);
+
+    assert_eq!(chip8.step(), Err(Chip8Error::MemoryOutOfBounds(0xFFE)));
+    assert_eq!(chip8.state().pc, 0x200);
+}
+
+#[test]
+fn drawing_up_to_the_end_of_ram_is_fine() {
+    let mut chip8 = common::machine_with(&common::config(), &[0xD0, 0x02], |state| state.i = 0xFFE);
+
+    assert_eq!(chip8.step(), Ok(StepOutcome::Executed));
+    assert_eq!(chip8.state().pc, 0x202);
+}
+
+#[test]
+fn patched_instruction_is_used_on_the_next_fetch() {
+    // LD V0, 1; JP 0x200
+    let mut chip8 = common::machine(&[0x60, 0x01, 0x12, 0x00]);
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().vx[0], 1);
+
+    // Patch to LD V0, 7
+    chip8.write_byte(0x201, 0x07).unwrap();
+    assert_eq!(chip8.read_byte_checked(0x201), Ok(0x07));
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().vx[0], 7);
+}
+
+#[test]
+fn patching_past_the_end_of_ram_is_an_error() {
+    let mut chip8 = common::machine(&[]);
+
+    assert_eq!(
+        chip8.write_byte(0x1000, 0xFF),
+        Err(Chip8Error::MemoryOutOfBounds(0x1000))
+    );
+    assert_eq!(
+        chip8.read_byte_checked(0x1000),
+        Err(Chip8Error::MemoryOutOfBounds(0x1000))
+    );
+}
+
+#[test]
+fn storing_registers_past_the_end_of_ram_is_an_error() {
+    // LD [I], V3 with only two bytes of RAM left
+    let mut chip8 = common::machine_with(&common::config(), &[0xF3, 0x55], |state| state.i = 0xFFE);
+
+    assert_eq!(chip8.step(), Err(Chip8Error::MemoryOutOfBounds(0xFFE)));
+    assert_eq!(chip8.state().pc, 0x200);
+}
+
+#[test]
+fn storing_bcd_past_the_end_of_ram_is_an_error() {
+    // LD B, V0 with only one byte of RAM left
+    let mut chip8 = common::machine_with(&common::config(), &[0xF0, 0x33], |state| {
+        state.i = 0xFFF;
+        state.vx[0] = 255;
+    });
+
+    assert_eq!(chip8.step(), Err(Chip8Error::MemoryOutOfBounds(0xFFF)));
+    assert_eq!(chip8.state().pc, 0x200);
+    assert_eq!(chip8.state().memory[0xFFF], 0);
+}
+
+#[test]
+fn storing_bcd_up_to_the_end_of_ram_is_fine() {
+    let mut chip8 = common::machine_with(&common::config(), &[0xF0, 0x33], |state| {
+        state.i = 0xFFD;
+        state.vx[0] = 255;
+    });
+
+    chip8.step().unwrap();
+    assert_eq!(&chip8.state().memory[0xFFD..], &[2, 5, 5]);
+}
+
+#[test]
+fn watchpoint_reports_a_write_to_the_watched_address() {
+    // LD [I], V2 with I = 0x2FF, so V1 lands on 0x300
+    let mut chip8 = common::machine_with(&common::config(), &[0xF2, 0x55], |state| {
+        state.i = 0x2FF;
+        state.vx[..3].copy_from_slice(&[0x11, 0x22, 0x33]);
+        state.memory[0x300] = 0xAB;
+    });
+    chip8.watch_address(0x300);
+
+    assert_eq!(
+        chip8.step(),
+        Ok(StepOutcome::WatchpointHit(0x300, 0xAB, 0x22))
+    );
+    assert_eq!(chip8.read_byte_checked(0x300), Ok(0x22));
+}
+
+#[test]
+fn watchpoint_ignores_writes_elsewhere_and_can_be_removed() {
+    // LD [I], V0; LD [I], V0
+    let mut chip8 = common::machine_with(&common::config(), &[0xF0, 0x55, 0xF0, 0x55], |state| {
+        state.i = 0x300
+    });
+    chip8.watch_address(0x301);
+    assert_eq!(chip8.step(), Ok(StepOutcome::Executed));
+
+    chip8.watch_address(0x300);
+    chip8.unwatch_address(0x300);
+    assert_eq!(chip8.step(), Ok(StepOutcome::Executed));
+}
+
+#[test]
+fn memory_slice_reads_the_font() {
+    let chip8 = Chip8::from_rom(&common::config(), &[0x12, 0x00]).unwrap();
+    let start = font_address(0) as usize;
+
+    assert_eq!(chip8.memory_slice(start..start + 16), Ok(&FONT[..16]));
+}
+
+#[test]
+fn memory_slice_is_bounds_checked() {
+    let chip8 = common::machine(&[]);
+
+    assert_eq!(
+        chip8
+            .memory_slice(RAM_SIZE - 2..RAM_SIZE)
+            .map(|bytes| bytes.len()),
+        Ok(2)
+    );
+    assert_eq!(
+        chip8.memory_slice(RAM_SIZE - 2..RAM_SIZE + 1),
+        Err(Chip8Error::MemoryOutOfBounds(RAM_SIZE))
+    );
+    assert_eq!(
+        chip8.memory_slice(RAM_SIZE + 4..RAM_SIZE + 8),
+        Err(Chip8Error::MemoryOutOfBounds(RAM_SIZE + 4))
+    );
+    let (start, end) = (0x300, 0x200);
+    assert_eq!(
+        chip8.memory_slice(start..end),
+        Err(Chip8Error::MemoryOutOfBounds(end))
+    );
+}
+
+#[test]
+fn iter_memory_covers_all_of_ram() {
+    let chip8 = common::machine(&[0xAB, 0xCD]);
+    let bytes: Vec<_> = chip8.iter_memory().collect();
+
+    assert_eq!(bytes.len(), RAM_SIZE);
+    assert_eq!(bytes[0x200], (0x200, 0xAB));
+    assert_eq!(bytes[0x201], (0x201, 0xCD));
+}
+
+#[test]
+fn words_are_read_big_endian() {
+    let mut memory = vec![0; RAM_SIZE];
+    memory[0x300] = 0x12;
+    memory[0x301] = 0x34;
+    let ram = Ram::from_memory(&memory);
+
+    assert_eq!(ram.read_word(0x300), 0x1234);
+    assert_eq!(ram.read_word_checked(0x300), Ok(0x1234));
+}
+
+#[test]
+fn word_reads_are_bounds_checked() {
+    let ram = Ram::from_memory(&vec![0xAB; RAM_SIZE]);
+
+    assert_eq!(ram.read_word_checked(RAM_SIZE - 2), Ok(0xABAB));
+    assert_eq!(
+        ram.read_word_checked(RAM_SIZE - 1),
+        Err(Chip8Error::MemoryOutOfBounds(RAM_SIZE - 1))
+    );
+    assert_eq!(
+        ram.read_word_checked(RAM_SIZE),
+        Err(Chip8Error::MemoryOutOfBounds(RAM_SIZE))
+    );
+}
+
+#[test]
+fn sprite_bytes_are_read_across_the_start_of_the_program() {
+    let mut memory = vec![0; RAM_SIZE];
+    memory[0x1FD..0x202].copy_from_slice(&[0x80, 0x40, 0x20, 0x10, 0x08]);
+    let ram = Ram::from_memory(&memory);
+
+    assert_eq!(
+        ram.read_bytes(0x1FD, 5),
+        Ok(&[0x80, 0x40, 0x20, 0x10, 0x08][..])
+    );
+}
+
+#[test]
+fn sprite_drawn_across_the_start_of_the_program_uses_both_sides() {
+    // DRW V0, V0, 5 reads three bytes below 0x200 and the instruction itself
+    let mut chip8 = common::machine_with(&common::config(), &[0xD0, 0x05], |state| {
+        state.memory[0x1FD..0x200].copy_from_slice(&[0x80, 0x40, 0x20]);
+        state.i = 0x1FD;
+    });
+
+    assert_eq!(chip8.step(), Ok(StepOutcome::Executed));
+    let rows = [0x80, 0x40, 0x20, 0xD0, 0x05];
+    for (y, row) in rows.iter().enumerate() {
+        for x in 0..8 {
+            assert_eq!(chip8.is_pixel_lit(x, y), row & (0x80 >> x) != 0);
+        }
+    }
+}
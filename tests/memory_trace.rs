@@ -0,0 +1,49 @@
+mod common;
+
+use chip8rs::{AccessKind, Config, MemoryAccess, ACCESS_LOG_LIMIT};
+
+fn config() -> Config {
+    Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .trace_memory(true)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn fx33_logs_three_consecutive_writes() {
+    // LD V0, 123; LD I, 0x300; LD B, V0
+    let program = [0x60, 0x7B, 0xA3, 0x00, 0xF0, 0x33];
+    let mut chip8 = common::machine_with(&config(), &program, |_| {});
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    chip8.clear_memory_access_log();
+
+    chip8.step().unwrap();
+
+    let write = |address| MemoryAccess {
+        address,
+        write: true,
+        kind: AccessKind::Data,
+    };
+    let log = chip8.memory_access_log().unwrap();
+    assert_eq!(log.len(), 5);
+    assert_eq!(&log[2..], &[write(0x300), write(0x301), write(0x302)]);
+    assert_eq!(chip8.memory_slice(0x300..0x303), Ok(&[1, 2, 3][..]));
+}
+
+#[test]
+fn the_access_log_keeps_only_the_most_recent_entries() {
+    // loop: ADD V0, 1; JP loop
+    let mut chip8 = common::machine_with(&config(), &[0x70, 0x01, 0x12, 0x00], |_| {});
+
+    // Each instruction fetches two bytes
+    for _ in 0..ACCESS_LOG_LIMIT {
+        chip8.step().unwrap();
+    }
+
+    let log = chip8.memory_access_log().unwrap();
+    assert!(log.len() <= ACCESS_LOG_LIMIT);
+    assert_eq!(log.last().unwrap().address, 0x203);
+}
@@ -0,0 +1,34 @@
+//! Checks that the interpreter core still builds with default features off,
+//! which makes the crate `no_std`, and that the tests still compile then.
+//! These run nested cargo builds with their own target directory, so they
+//! don't wait on the outer build's lock.
+
+use std::env;
+use std::process::Command;
+
+/// Runs `cargo check` with default features off and the given arguments
+fn check_without_std(args: &[&str]) -> bool {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let cargo = env::var("CARGO").unwrap_or_else(|_| String::from("cargo"));
+
+    Command::new(cargo)
+        .args(["check", "--no-default-features", "--quiet"])
+        .args(args)
+        .arg("--target-dir")
+        .arg(format!("{}/target/no_std", manifest_dir))
+        .current_dir(manifest_dir)
+        .status()
+        .unwrap()
+        .success()
+}
+
+#[test]
+fn core_builds_without_std() {
+    assert!(check_without_std(&["--lib"]));
+}
+
+/// Tests that need std opt out with `#![cfg(feature = "std")]`
+#[test]
+fn tests_build_without_std() {
+    assert!(check_without_std(&["--tests"]));
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use chip8rs::asm::assemble;
+use chip8rs::timing::Clock;
+use chip8rs::RunOutcome;
+
+/// A clock that moves forward by a microsecond every time it is read
+struct FakeClock(Rc<Cell<Duration>>);
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        let now = self.0.get() + Duration::from_micros(1);
+        self.0.set(now);
+        now
+    }
+}
+
+#[test]
+fn profiler_times_the_instructions_that_run() {
+    let program = assemble("LD V0, 1\nLD V1, 2\nADD V0, V1\nhalt: JP halt").unwrap();
+    let mut chip8 = common::machine(&program);
+    chip8.set_profile_clock(Box::new(FakeClock(Rc::new(Cell::new(Duration::default())))));
+
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+
+    let report = chip8.profile_report();
+    let find = |name| report.iter().find(|(mnemonic, _, _)| *mnemonic == name);
+
+    assert_eq!(find("LD"), Some(&("LD", Duration::from_micros(2), 2)));
+    assert_eq!(find("ADD"), Some(&("ADD", Duration::from_micros(1), 1)));
+    assert_eq!(find("JP"), None);
+    assert_eq!(report.len(), 2);
+}
+
+#[test]
+fn profiler_is_off_by_default() {
+    let mut chip8 = common::machine(&assemble("LD V0, 1").unwrap());
+
+    chip8.step().unwrap();
+
+    assert!(chip8.profile_report().is_empty());
+}
+
+#[test]
+fn opcode_counts_cover_a_run_after_a_reset() {
+    let source = "
+        LD V0, 1
+        ADD V0, 2
+        SE V0, 3
+        LD V2, 1
+        CALL sub
+        LD I, sub
+        DRW V0, V0, 1
+    halt:
+        JP halt
+    sub:
+        XOR V3, V3
+        RET
+    ";
+    let mut chip8 = common::machine(&assemble(source).unwrap());
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.opcode_counts().get("LD"), Some(&1));
+    chip8.reset_opcode_counts();
+    assert!(chip8.opcode_counts().is_empty());
+
+    let outcome = chip8.run_until(|chip8| chip8.is_halted(), 100).unwrap();
+    assert_eq!(outcome, RunOutcome::Reached);
+
+    let counts = chip8.opcode_counts();
+    for mnemonic in ["ADD", "SE", "CALL", "XOR", "RET", "LD", "DRW", "JP"] {
+        assert!(counts.contains_key(mnemonic), "{} wasn't counted", mnemonic);
+    }
+    // The LD V0 before the reset is gone and the skipped LD V2 never ran
+    assert_eq!(counts["LD"], 1);
+    assert_eq!(counts["CALL"], 1);
+}
@@ -0,0 +1,474 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::cell::Cell;
+use std::time::Duration;
+
+use chip8rs::asm::assemble;
+use chip8rs::timing::Clock;
+use chip8rs::{Chip8, Chip8Error, Config, MemoryIncrement, Quirks};
+
+fn config(quirks: Quirks) -> Config {
+    common::config_with(|b| b.quirks(quirks))
+}
+
+/// Draws the 5 row "0" font sprite at (0, 30) on an empty display, so the
+/// bottom three rows fall off the edge, and returns VF
+fn vf_after_bottom_edge_draw(quirks: Quirks) -> u8 {
+    // LD V0, 0; LD V1, 30; LD F, V0; DRW V0, V1, 5
+    let program = [0x60, 0x00, 0x61, 0x1E, 0xF0, 0x29, 0xD0, 0x15];
+    let mut chip8 = common::machine_with(&config(quirks), &program, |_| {});
+
+    for _ in 0..4 {
+        chip8.step().unwrap();
+    }
+
+    chip8.state().vx[0xF]
+}
+
+#[test]
+fn clipped_rows_collide_when_enabled() {
+    let quirks = Quirks {
+        wrap_y: false,
+        clipped_rows_collide: true,
+        ..Quirks::default()
+    };
+
+    assert_eq!(vf_after_bottom_edge_draw(quirks), 1);
+}
+
+#[test]
+fn clipped_rows_dont_collide_when_disabled() {
+    let quirks = Quirks {
+        wrap_y: false,
+        clipped_rows_collide: false,
+        ..Quirks::default()
+    };
+
+    assert_eq!(vf_after_bottom_edge_draw(quirks), 0);
+}
+
+#[test]
+fn wrapped_rows_never_collide() {
+    let quirks = Quirks {
+        wrap_y: true,
+        clipped_rows_collide: true,
+        ..Quirks::default()
+    };
+
+    assert_eq!(vf_after_bottom_edge_draw(quirks), 0);
+}
+
+/// Loads V0 to V4 from I = 0x300 with the given increment, and returns I
+fn i_after_loading_five_registers(memory_increment: MemoryIncrement) -> u16 {
+    let quirks = Quirks {
+        memory_increment,
+        ..Quirks::default()
+    };
+
+    // LD V4, [I]
+    let mut chip8 = common::machine_with(&config(quirks), &[0xF4, 0x65], |state| {
+        state.i = 0x300;
+        state.memory[0x300..0x305].copy_from_slice(&[1, 2, 3, 4, 5]);
+    });
+    chip8.step().unwrap();
+
+    let state = chip8.state();
+    assert_eq!(&state.vx[..5], &[1, 2, 3, 4, 5]);
+
+    state.i
+}
+
+#[test]
+fn memory_increment_none_leaves_i() {
+    assert_eq!(i_after_loading_five_registers(MemoryIncrement::None), 0x300);
+}
+
+#[test]
+fn memory_increment_x_points_at_the_last_register() {
+    assert_eq!(i_after_loading_five_registers(MemoryIncrement::X), 0x304);
+}
+
+#[test]
+fn memory_increment_x_plus_one_points_past_the_last_register() {
+    assert_eq!(
+        i_after_loading_five_registers(MemoryIncrement::XPlusOne),
+        0x305
+    );
+}
+
+#[test]
+fn memory_increment_applies_to_stores_too() {
+    let quirks = Quirks {
+        memory_increment: MemoryIncrement::X,
+        ..Quirks::default()
+    };
+
+    // LD [I], V4
+    let mut chip8 = common::machine_with(&config(quirks), &[0xF4, 0x55], |state| {
+        state.i = 0x300;
+    });
+    chip8.step().unwrap();
+
+    assert_eq!(chip8.state().i, 0x304);
+}
+
+#[test]
+fn sys_is_ignored_by_default() {
+    let mut chip8 = common::machine(&[0x01, 0x23]);
+
+    chip8.step().unwrap();
+
+    assert_eq!(chip8.state().pc, 0x202);
+}
+
+#[test]
+fn sys_is_an_error_with_strict_sys() {
+    let quirks = Quirks {
+        strict_sys: true,
+        ..Quirks::default()
+    };
+    let mut chip8 = common::machine_with(&config(quirks), &[0x01, 0x23], |_| {});
+
+    assert_eq!(chip8.step(), Err(Chip8Error::UnknownOpcode(0x0123)));
+    assert_eq!(chip8.state().pc, 0x200);
+}
+
+/// Draws a row of 8 lit pixels at (x, 0) and returns the top row of the
+/// display as text
+fn top_row_after_draw(quirks: Quirks, x: u8) -> String {
+    // LD V0, x; LD V1, 0; LD I, 0x208; DRW V0, V1, 1; DW 0xFF00
+    let program = [0x60, x, 0x61, 0x00, 0xA2, 0x08, 0xD0, 0x11, 0xFF, 0x00];
+    let mut chip8 = common::machine_with(&config(quirks), &program, |_| {});
+
+    for _ in 0..4 {
+        chip8.step().unwrap();
+    }
+
+    chip8
+        .framebuffer_ascii()
+        .lines()
+        .next()
+        .unwrap()
+        .to_string()
+}
+
+/// The top row with only the given columns lit
+fn row_with(lit: &[usize]) -> String {
+    (0..64)
+        .map(|x| if lit.contains(&x) { '#' } else { '.' })
+        .collect()
+}
+
+#[test]
+fn full_wrap_carries_sprite_pixels_round_the_edge() {
+    let quirks = Quirks {
+        wrap_x: true,
+        ..Quirks::default()
+    };
+
+    assert_eq!(
+        top_row_after_draw(quirks, 63),
+        row_with(&[63, 0, 1, 2, 3, 4, 5, 6])
+    );
+}
+
+#[test]
+fn sprites_can_wrap_horizontally_while_clipped_at_the_bottom() {
+    let quirks = Quirks {
+        wrap_x: true,
+        wrap_y: false,
+        ..Quirks::default()
+    };
+    // LD V0, 60; LD V1, 31; LD I, 0x20A; DRW V0, V1, 2; DW 0xFFFF
+    let program = [
+        0x60, 0x3C, 0x61, 0x1F, 0xA2, 0x0A, 0xD0, 0x12, 0x00, 0x00, 0xFF, 0xFF,
+    ];
+    let mut chip8 = common::machine_with(&config(quirks), &program, |_| {});
+
+    for _ in 0..4 {
+        chip8.step().unwrap();
+    }
+
+    let ascii = chip8.framebuffer_ascii();
+    let rows: Vec<&str> = ascii.lines().collect();
+    assert_eq!(rows[31], row_with(&[60, 61, 62, 63, 0, 1, 2, 3]));
+    // The second row would wrap to the top, so it is dropped
+    assert_eq!(rows[0], row_with(&[]));
+}
+
+#[test]
+fn start_wrap_then_clip_cuts_pixels_at_the_edge() {
+    let quirks = Quirks {
+        wrap_x: false,
+        wrap_start: true,
+        ..Quirks::default()
+    };
+
+    assert_eq!(top_row_after_draw(quirks, 63), row_with(&[63]));
+    // 127 wraps round to 63 before clipping
+    assert_eq!(top_row_after_draw(quirks, 127), row_with(&[63]));
+}
+
+#[test]
+fn without_start_wrap_an_off_screen_sprite_is_clipped_away() {
+    let quirks = Quirks {
+        wrap_x: false,
+        wrap_start: false,
+        ..Quirks::default()
+    };
+
+    assert_eq!(top_row_after_draw(quirks, 63), row_with(&[63]));
+    assert_eq!(top_row_after_draw(quirks, 127), row_with(&[]));
+}
+
+/// Adds 0x20 to an I of 0xFF0 with Fx1E, then reads V0 back from [I]
+fn add_i_past_end_of_ram(quirks: Quirks) -> (Chip8, Result<(), Chip8Error>) {
+    // LD V0, 0x20; LD I, 0xFF0; ADD I, V0; LD V0, [I]
+    let program = [0x60, 0x20, 0xAF, 0xF0, 0xF0, 0x1E, 0xF0, 0x65];
+    let mut chip8 = common::machine_with(&config(quirks), &program, |state| {
+        state.memory[0x010] = 0x77;
+    });
+
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+    let result = chip8.step().map(|_| ());
+
+    (chip8, result)
+}
+
+#[test]
+fn add_i_wraps_at_the_end_of_ram_with_i_wrap() {
+    let quirks = Quirks {
+        i_overflow: true,
+        i_wrap: true,
+        ..Quirks::default()
+    };
+
+    let (chip8, result) = add_i_past_end_of_ram(quirks);
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(chip8.state().vx[0], 0x77);
+    assert_eq!(chip8.state().vx[0xF], 1);
+    assert_eq!(chip8.state().i, 0x010);
+}
+
+#[test]
+fn add_i_wraps_at_the_configured_ram_size() {
+    let quirks = Quirks {
+        i_overflow: true,
+        i_wrap: true,
+        ..Quirks::default()
+    };
+    let config = common::config_with(|b| b.quirks(quirks).ram_size(0x800));
+
+    // LD V0, 0x20; LD I, 0x7F0; ADD I, V0; LD V0, [I]
+    let program = [0x60, 0x20, 0xA7, 0xF0, 0xF0, 0x1E, 0xF0, 0x65];
+    let mut chip8 = common::machine_with(&config, &program, |state| {
+        state.memory[0x010] = 0x77;
+    });
+
+    for _ in 0..4 {
+        chip8.step().unwrap();
+    }
+
+    assert_eq!(chip8.state().memory.len(), 0x800);
+    assert_eq!(chip8.state().vx[0], 0x77);
+    assert_eq!(chip8.state().vx[0xF], 1);
+    assert_eq!(chip8.state().i, 0x010);
+}
+
+#[test]
+fn add_i_can_leave_i_past_the_end_of_ram_by_default() {
+    let (chip8, result) = add_i_past_end_of_ram(Quirks::default());
+
+    assert_eq!(result, Err(Chip8Error::MemoryOutOfBounds(0x1010)));
+    assert_eq!(chip8.state().i, 0x1010);
+}
+
+/// A clock that moves forward by the next of a list of uneven steps every
+/// time it is read, starting again from the first step after the last
+struct ScriptedClock {
+    steps: Vec<Duration>,
+    reads: Cell<usize>,
+    time: Cell<Duration>,
+}
+
+impl Clock for ScriptedClock {
+    fn now(&self) -> Duration {
+        let reads = self.reads.get();
+        self.reads.set(reads + 1);
+        self.time
+            .set(self.time.get() + self.steps[reads % self.steps.len()]);
+        self.time.get()
+    }
+}
+
+/// Runs a sprite drawing loop with display wait on, syncing the timers to a
+/// scripted clock between batches of steps. Returns the instructions run
+/// and the framebuffer hash
+fn display_wait_run() -> (u64, u64) {
+    let quirks = Quirks {
+        display_wait: true,
+        ..Quirks::default()
+    };
+    let program = assemble(
+        "LD I, 0x000
+        loop:
+            DRW V0, V1, 5
+            ADD V0, 3
+            ADD V1, 1
+            JP loop",
+    )
+    .unwrap();
+    let mut chip8 = common::machine_with(&config(quirks), &program, |state| {
+        state.memory[..5].copy_from_slice(&[0xF0, 0x90, 0x90, 0x90, 0xF0]);
+    });
+    chip8.set_timer_clock(Box::new(ScriptedClock {
+        steps: [3, 17, 9, 40, 1, 25]
+            .iter()
+            .map(|millis| Duration::from_millis(*millis))
+            .collect(),
+        reads: Cell::new(0),
+        time: Cell::new(Duration::default()),
+    }));
+
+    for _ in 0..200 {
+        for _ in 0..7 {
+            chip8.step().unwrap();
+        }
+        chip8.sync_timers();
+    }
+
+    (chip8.cycle_count(), chip8.framebuffer_hash())
+}
+
+#[test]
+fn display_wait_follows_the_emulated_clock() {
+    let first = display_wait_run();
+
+    assert_eq!(display_wait_run(), first);
+    // Batches that start with no tick since the last draw run nothing
+    assert!(first.0 < 200 * 7, "{} instructions ran", first.0);
+}
+
+/// Runs `opcode` with VF = 0x81 and returns VF afterwards
+fn vf_after_shifting_vf(shift_vf_first: bool, opcode: [u8; 2]) -> u8 {
+    let quirks = Quirks {
+        shift_vf_first,
+        ..Quirks::default()
+    };
+    let mut chip8 = common::machine_with(&config(quirks), &opcode, |state| state.vx[0xF] = 0x81);
+    chip8.step().unwrap();
+
+    chip8.state().vx[0xF]
+}
+
+#[test]
+fn shifting_vf_leaves_the_flag_by_default() {
+    // SHR VF; SHL VF
+    assert_eq!(vf_after_shifting_vf(false, [0x8F, 0xF6]), 1);
+    assert_eq!(vf_after_shifting_vf(false, [0x8F, 0xFE]), 1);
+}
+
+#[test]
+fn shifting_vf_leaves_the_result_when_the_flag_goes_first() {
+    assert_eq!(vf_after_shifting_vf(true, [0x8F, 0xF6]), 0x40);
+    assert_eq!(vf_after_shifting_vf(true, [0x8F, 0xFE]), 0x02);
+}
+
+#[test]
+fn cosmac_preset_values() {
+    let quirks = Quirks::cosmac();
+
+    assert!(!quirks.shift);
+    assert!(!quirks.jump);
+    assert!(quirks.vf_reset);
+    assert_eq!(quirks.memory_increment, MemoryIncrement::XPlusOne);
+    assert!(!quirks.wrap_x);
+    assert!(!quirks.wrap_y);
+    assert!(quirks.display_wait);
+    assert!(!quirks.clipped_rows_collide);
+}
+
+#[test]
+fn chip48_preset_values() {
+    let quirks = Quirks::chip48();
+
+    assert!(quirks.shift);
+    assert!(quirks.jump);
+    assert!(!quirks.vf_reset);
+    assert_eq!(quirks.memory_increment, MemoryIncrement::X);
+    assert!(!quirks.wrap_x);
+    assert!(!quirks.wrap_y);
+    assert!(!quirks.display_wait);
+}
+
+#[test]
+fn schip_preset_values() {
+    let quirks = Quirks::schip();
+
+    assert!(quirks.shift);
+    assert!(quirks.jump);
+    assert!(!quirks.vf_reset);
+    assert_eq!(quirks.memory_increment, MemoryIncrement::None);
+    assert!(!quirks.wrap_x);
+    assert!(!quirks.wrap_y);
+    assert!(!quirks.display_wait);
+    assert!(quirks.clipped_rows_collide);
+}
+
+#[test]
+fn xochip_preset_values() {
+    let quirks = Quirks::xochip();
+
+    assert!(!quirks.shift);
+    assert!(!quirks.jump);
+    assert!(!quirks.vf_reset);
+    assert_eq!(quirks.memory_increment, MemoryIncrement::XPlusOne);
+    assert!(quirks.wrap_x);
+    assert!(quirks.wrap_y);
+    assert!(!quirks.display_wait);
+}
+
+#[test]
+fn presets_are_found_by_their_command_line_names() {
+    assert_eq!(Quirks::preset("cosmac"), Some(Quirks::cosmac()));
+    assert_eq!(Quirks::preset("chip48"), Some(Quirks::chip48()));
+    assert_eq!(Quirks::preset("schip"), Some(Quirks::schip()));
+    assert_eq!(Quirks::preset("xochip"), Some(Quirks::xochip()));
+    assert_eq!(Quirks::preset("vip"), None);
+
+    let args = ["chip8rs", "--preset", "chip48", "game.ch8"];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+    assert_eq!(config.quirks, Quirks::chip48());
+}
+
+// JP 0x203, a padding byte, then LD V0, 0x2A at the odd address
+const ODD_JUMP: [u8; 5] = [0x12, 0x03, 0x00, 0x60, 0x2A];
+
+#[test]
+fn odd_pc_fetches_from_the_odd_address_by_default() {
+    let mut chip8 = common::machine(&ODD_JUMP);
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().vx[0], 0x2A);
+    assert_eq!(chip8.state().pc, 0x205);
+}
+
+#[test]
+fn odd_pc_is_an_error_with_strict_alignment() {
+    let quirks = Quirks {
+        strict_alignment: true,
+        ..Quirks::default()
+    };
+    let mut chip8 = common::machine_with(&config(quirks), &ODD_JUMP, |_| {});
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.step(), Err(Chip8Error::MisalignedPc(0x203)));
+    assert_eq!(chip8.state().pc, 0x203);
+    assert_eq!(chip8.state().vx[0], 0);
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use chip8rs::asm::assemble;
+use chip8rs::FixedSequence;
+
+#[test]
+fn rnd_uses_the_random_source() {
+    let mut chip8 = common::machine(&assemble("RND V0, 0xF0\nRND V0, 0xF0").unwrap());
+    chip8.set_random_source(Box::new(FixedSequence::new(vec![0xFF, 0x0F])));
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().vx[0], 0xF0);
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().vx[0], 0x00);
+}
+
+#[test]
+fn fixed_sequence_starts_again_once_it_runs_out() {
+    let program = assemble("RND V0, 0xFF\nRND V1, 0xFF\nRND V2, 0xFF").unwrap();
+    let mut chip8 = common::machine(&program);
+    chip8.set_random_source(Box::new(FixedSequence::new(vec![0x12, 0x34])));
+
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+
+    assert_eq!(&chip8.state().vx[..3], &[0x12, 0x34, 0x12]);
+}
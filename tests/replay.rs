@@ -0,0 +1,54 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::fs;
+
+use chip8rs::asm::assemble;
+use chip8rs::{Chip8, Replay};
+
+/// Adds up random numbers, and counts the instructions that ran while key 5
+/// was held
+const SOURCE: &str = "
+loop:
+    RND V0, 0xFF
+    ADD V1, V0
+    LD V2, 5
+    SKNP V2
+    ADD V3, 1
+    JP loop
+";
+
+fn machine() -> Chip8 {
+    common::machine(&assemble(SOURCE).unwrap())
+}
+
+#[test]
+fn replay_reaches_the_recorded_state() {
+    let path = std::env::temp_dir().join("chip8rs_replay_session.c8r");
+
+    let mut recorded = machine();
+    recorded.start_recording(&path);
+    for frame in 0..30 {
+        recorded.set_key(5, frame % 3 != 0);
+        recorded.poll_keyboard();
+        recorded.run_frame(5 + frame % 4).unwrap();
+    }
+    recorded.stop_recording().unwrap();
+
+    let replay = Replay::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let mut played = machine();
+    played.start_playback(replay);
+    loop {
+        played.poll_keyboard();
+        match played.playback_cycles() {
+            Some(cycles) => played.run_frame(cycles).unwrap(),
+            None => break,
+        }
+    }
+
+    assert_ne!(recorded.state().vx[3], 0);
+    assert_eq!(played.state(), recorded.state());
+    assert_eq!(played.cycle_count(), recorded.cycle_count());
+}
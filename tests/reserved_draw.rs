@@ -0,0 +1,55 @@
+mod common;
+
+use chip8rs::{Chip8, Config, ReservedDraw};
+
+/// Draws a 5 byte sprite with I at `i`
+fn draw_from(i: u16, warn_draw_reserved: bool) -> Chip8 {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .warn_draw_reserved(warn_draw_reserved)
+        .build()
+        .unwrap();
+    // LD I, i; DRW V0, V0, 5
+    let [high, low] = (0xA000 | i).to_be_bytes();
+    let mut chip8 = Chip8::from_rom(&config, &[high, low, 0xD0, 0x05]).unwrap();
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    chip8
+}
+
+#[test]
+fn draw_from_reserved_memory_is_reported_when_enabled() {
+    let chip8 = draw_from(0x150, true);
+
+    assert_eq!(
+        chip8.reserved_draws(),
+        [ReservedDraw {
+            pc: 0x202,
+            i: 0x150
+        }]
+    );
+}
+
+#[test]
+fn draw_from_reserved_memory_is_ignored_when_disabled() {
+    assert_eq!(draw_from(0x150, false).reserved_draws(), []);
+}
+
+#[test]
+fn draws_from_the_font_and_program_are_fine() {
+    assert_eq!(draw_from(0x0A, true).reserved_draws(), []);
+    assert_eq!(draw_from(0x200, true).reserved_draws(), []);
+}
+
+#[test]
+fn sprite_running_off_the_end_of_the_font_is_reported() {
+    // The last font character ends at 0x50
+    assert_eq!(draw_from(0x4E, true).reserved_draws().len(), 1);
+}
+
+#[test]
+fn sprite_running_into_the_program_is_reported() {
+    assert_eq!(draw_from(0x1FD, true).reserved_draws().len(), 1);
+}
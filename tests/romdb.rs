@@ -0,0 +1,74 @@
+#![cfg(feature = "std")]
+
+use std::fs;
+
+use chip8rs::romdb::{self, KnownRom, KNOWN_ROMS};
+use chip8rs::{Config, Quirks};
+
+const ROM: [u8; 4] = [0x00, 0xE0, 0x12, 0x00];
+
+fn fake_table() -> [KnownRom; 1] {
+    [KnownRom {
+        hash: romdb::rom_hash(&ROM),
+        name: "Fake ROM",
+        quirks: Quirks::cosmac,
+    }]
+}
+
+fn args_config(flags: &[&str]) -> Config {
+    let args = ["chip8rs", "fake.ch8"].iter().chain(flags);
+    Config::new(args.map(|arg| arg.to_string())).unwrap()
+}
+
+#[test]
+fn known_rom_gets_its_quirks() {
+    let hash = Some(romdb::rom_hash(&ROM));
+
+    assert_eq!(
+        romdb::select_quirks(&fake_table(), hash, &args_config(&[])),
+        Quirks::cosmac()
+    );
+}
+
+#[test]
+fn unknown_rom_keeps_the_defaults() {
+    let hash = Some(romdb::rom_hash(&[0x12, 0x00]));
+
+    assert_eq!(
+        romdb::select_quirks(&fake_table(), hash, &args_config(&[])),
+        Quirks::default()
+    );
+}
+
+#[test]
+fn quirk_flags_override_a_known_rom() {
+    let hash = Some(romdb::rom_hash(&ROM));
+
+    assert_eq!(
+        romdb::select_quirks(&fake_table(), hash, &args_config(&["--preset", "schip"])),
+        Quirks::schip()
+    );
+    assert_eq!(
+        romdb::select_quirks(&fake_table(), hash, &args_config(&["--clip-x"])),
+        Quirks {
+            wrap_x: false,
+            ..Quirks::default()
+        }
+    );
+}
+
+#[test]
+fn bundled_test_roms_are_known() {
+    for (name, quirks) in [
+        ("arithmetic.ch8", Quirks::schip()),
+        ("drawing.ch8", Quirks::xochip()),
+        ("jumps.ch8", Quirks::cosmac()),
+    ] {
+        let path = format!("{}/tests/roms/{}", env!("CARGO_MANIFEST_DIR"), name);
+        let hash = romdb::rom_hash(&fs::read(path).unwrap());
+
+        let known = romdb::find(KNOWN_ROMS, hash).unwrap();
+        assert_eq!(known.name, name);
+        assert_eq!((known.quirks)(), quirks);
+    }
+}
@@ -0,0 +1,186 @@
+//! Runs the small ROMs in tests/roms headless until they reach their final
+//! `JP` to self, then checks the screen and registers. The source for each
+//! ROM is given above its test.
+
+#![cfg(feature = "std")]
+
+use chip8rs::{Chip8, Config, RunOutcome};
+
+/// Seed used for every run, so RND gives the same values each time
+const SEED: u64 = 0x5EED;
+
+/// More instructions than any of the ROMs need to reach their halt loop
+const MAX_CYCLES: u64 = 10_000;
+
+/// framebuffer_hash of a screen with nothing drawn on it
+const BLANK_SCREEN: u64 = 2937223237197546277;
+
+/// Runs a ROM from tests/roms headless until it halts and returns the machine
+/// for inspection. Panics if the ROM is still running after MAX_CYCLES
+fn run_to_halt(name: &str) -> Chip8 {
+    let rom_path = format!("{}/tests/roms/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let config = Config::builder()
+        .rom_path(&rom_path)
+        .headless(true)
+        .seed(SEED)
+        .build()
+        .unwrap();
+
+    let mut chip8 = Chip8::new(&config).unwrap();
+    let outcome = chip8
+        .run_until(|chip8| chip8.is_halted(), MAX_CYCLES)
+        .unwrap();
+    assert_eq!(
+        outcome,
+        RunOutcome::Reached,
+        "{} didn't halt within {} instructions",
+        name,
+        MAX_CYCLES
+    );
+
+    chip8
+}
+
+/// golden.ch8 draws the hex digits 0 to B along the top of the screen, then
+/// a random byte in decimal below them, and loops forever
+#[test]
+fn golden_rom_framebuffer() {
+    let chip8 = run_to_halt("golden.ch8");
+
+    assert_eq!(chip8.framebuffer_hash(), 17866591976772995886);
+}
+
+/// arithmetic.ch8 exercises the 8xyN ALU instructions, copying VF out after
+/// each one that sets it. It draws nothing.
+///
+/// ```text
+///     LD V0, 200
+///     LD V1, 100
+///     ADD V0, V1      ; V0 = 44, carry
+///     LD V2, VF       ; V2 = 1
+///     LD V3, 10
+///     LD V4, 3
+///     SUB V3, V4      ; V3 = 7, no borrow
+///     LD V5, VF       ; V5 = 1
+///     LD V6, 0x0F
+///     LD V7, 0xF0
+///     OR V6, V7       ; V6 = 0xFF
+///     LD V8, 0x3C
+///     AND V8, V7      ; V8 = 0x30
+///     LD V9, 0x55
+///     XOR V9, V7      ; V9 = 0xA5
+///     LD VA, 0x81
+///     SHR VA          ; VA = 0x40, bit 0 was set
+///     LD VB, VF       ; VB = 1
+///     LD VC, 5
+///     SUBN VC, V4     ; VC = 3 - 5 = 0xFE, borrow
+///     LD VD, 0x81
+///     SHL VD          ; VD = 0x02, bit 7 was set
+///     LD VE, VF       ; VE = 1
+/// halt:
+///     JP halt
+/// ```
+#[test]
+fn arithmetic_rom() {
+    let chip8 = run_to_halt("arithmetic.ch8");
+    let state = chip8.state();
+
+    assert_eq!(
+        state.vx,
+        [44, 100, 1, 7, 3, 1, 0xFF, 0xF0, 0x30, 0xA5, 0x40, 1, 0xFE, 0x02, 1, 1]
+    );
+    assert_eq!(state.pc, 0x22E);
+    assert_eq!(chip8.framebuffer_hash(), BLANK_SCREEN);
+}
+
+/// jumps.ch8 takes each kind of skip, a subroutine call and a computed jump,
+/// counting the checks that pass in V1. Any skip that goes the wrong way ends
+/// up at `fail`, which sets VE.
+///
+/// ```text
+///     LD V0, 5
+///     SE V0, 5        ; skips
+///     JP fail
+///     ADD V1, 1
+///     SNE V0, 6       ; skips
+///     JP fail
+///     ADD V1, 1
+///     LD V2, 5
+///     SE V0, V2       ; skips
+///     JP fail
+///     ADD V1, 1
+///     SNE V0, V2      ; doesn't skip
+///     ADD V1, 1
+///     CALL sub
+///     ADD V1, 1
+///     LD V0, 4
+///     JP V0, table    ; lands on the third entry
+/// table:
+///     LD V3, 0xAA
+///     JP done
+///     LD V3, 0xBB
+///     JP done
+/// sub:
+///     ADD V1, 1
+///     RET
+/// done:
+///     JP done
+/// fail:
+///     LD VE, 1
+/// fail_halt:
+///     JP fail_halt
+/// ```
+#[test]
+fn jumps_rom() {
+    let chip8 = run_to_halt("jumps.ch8");
+    let state = chip8.state();
+
+    assert_eq!(state.vx[0x1], 6);
+    assert_eq!(state.vx[0x3], 0xBB);
+    assert_eq!(state.vx[0xE], 0);
+    assert_eq!(state.pc, 0x22E);
+    assert_eq!(state.sp, 0);
+    assert_eq!(chip8.framebuffer_hash(), BLANK_SCREEN);
+}
+
+/// drawing.ch8 draws a 4x4 box, then the same box two pixels to the right so
+/// the overlap is erased, then a third box against the right edge whose bottom
+/// half wraps round to the top. Last it draws the font's "9" in the middle of
+/// the screen. VF is copied out after each box.
+///
+/// ```text
+///     CLS
+///     LD I, box
+///     LD V0, 10
+///     LD V1, 5
+///     DRW V0, V1, 4
+///     LD V2, VF       ; V2 = 0, nothing erased
+///     LD V0, 12
+///     DRW V0, V1, 4
+///     LD V3, VF       ; V3 = 1, columns 12 and 13 erased
+///     LD V0, 60
+///     LD V1, 30
+///     DRW V0, V1, 4
+///     LD V4, VF       ; V4 = 0, the edges were empty
+///     LD V5, 9
+///     LD F, V5
+///     LD V0, 30
+///     LD V1, 12
+///     DRW V0, V1, 5
+/// done:
+///     JP done
+/// box:
+///     DW 0xF0F0
+///     DW 0xF0F0
+/// ```
+#[test]
+fn drawing_rom() {
+    let chip8 = run_to_halt("drawing.ch8");
+    let state = chip8.state();
+
+    assert_eq!(state.vx[0x2], 0);
+    assert_eq!(state.vx[0x3], 1);
+    assert_eq!(state.vx[0x4], 0);
+    assert_eq!(state.pc, 0x224);
+    assert_eq!(chip8.framebuffer_hash(), 6042590276109150288);
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use chip8rs::asm::assemble;
+use chip8rs::timing::CostModel;
+use chip8rs::{Chip8, Chip8Error, Config, Quirks, Ram, RunOutcome};
+
+/// Counts up in V0 forever. It never halts, since the jump isn't to itself
+const ENDLESS: &str = "
+    loop:
+        ADD V0, 1
+        JP loop
+";
+
+#[test]
+fn run_until_stops_when_the_budget_runs_out() {
+    let mut chip8 = common::machine(&assemble(ENDLESS).unwrap());
+
+    let outcome = chip8.run_until(|chip8| chip8.is_halted(), 25).unwrap();
+
+    assert_eq!(outcome, RunOutcome::BudgetExhausted);
+    assert_eq!(chip8.cycle_count(), 25);
+}
+
+#[test]
+fn run_until_stops_when_the_predicate_is_met() {
+    let mut chip8 = common::machine(&assemble(ENDLESS).unwrap());
+
+    let outcome = chip8
+        .run_until(|chip8| chip8.state().vx[0] == 3, 100)
+        .unwrap();
+
+    assert_eq!(outcome, RunOutcome::Reached);
+    assert_eq!(chip8.cycle_count(), 5);
+}
+
+#[test]
+fn from_rom_runs_a_rom_held_in_memory() {
+    let rom = assemble("LD V0, 7\nhalt: JP halt").unwrap();
+    let mut chip8 = Chip8::from_rom(&common::config(), &rom).unwrap();
+
+    let outcome = chip8.run_until(|chip8| chip8.is_halted(), 10).unwrap();
+
+    assert_eq!(outcome, RunOutcome::Reached);
+    assert_eq!(chip8.state().vx[0], 7);
+}
+
+#[test]
+fn an_empty_rom_is_rejected_when_loaded() {
+    let config = common::config();
+
+    assert_eq!(
+        Chip8::from_rom(&config, &[]).err(),
+        Some(Chip8Error::EmptyRom)
+    );
+    assert_eq!(
+        Ram::with_fonts(&config).load_rom(&[]),
+        Err(Chip8Error::EmptyRom)
+    );
+}
+
+/// A headless config running at `cpu_hz`, with display wait on or off
+fn frame_config(cpu_hz: u32, display_wait: bool) -> Config {
+    Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .cpu_hz(cpu_hz)
+        .quirks(Quirks {
+            display_wait,
+            ..Quirks::cosmac()
+        })
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn step_frame_runs_a_frame_of_instructions_and_one_tick() {
+    let config = frame_config(600, false);
+    let mut chip8 = common::machine_with(&config, &assemble(ENDLESS).unwrap(), |state| {
+        state.dt = 5;
+    });
+
+    chip8.step_frame().unwrap();
+    assert_eq!(chip8.cycle_count(), 600 / 60);
+    assert_eq!(chip8.state().dt, 4);
+
+    chip8.step_frame().unwrap();
+    assert_eq!(chip8.cycle_count(), 2 * 600 / 60);
+    assert_eq!(chip8.state().dt, 3);
+}
+
+#[test]
+fn step_frame_runs_while_paused() {
+    let mut chip8 = common::machine_with(
+        &frame_config(600, false),
+        &assemble(ENDLESS).unwrap(),
+        |_| {},
+    );
+    chip8.pause();
+
+    chip8.step_frame().unwrap();
+    assert_eq!(chip8.cycle_count(), 10);
+}
+
+#[test]
+fn step_frame_stops_at_a_draw_with_display_wait() {
+    let program = assemble(
+        "
+        loop:
+            DRW V0, V0, 1
+            ADD V1, 1
+            JP loop
+    ",
+    )
+    .unwrap();
+    let mut chip8 = common::machine_with(&frame_config(600, true), &program, |_| {});
+
+    // The draw waits for the next frame, so only it runs in the first
+    chip8.step_frame().unwrap();
+    assert_eq!(chip8.cycle_count(), 1);
+
+    // The next frame picks up after the draw and runs until the next one
+    chip8.step_frame().unwrap();
+    assert_eq!(chip8.cycle_count(), 4);
+    assert_eq!(chip8.state().vx[1], 1);
+}
+
+/// Draws every other instruction. Runs `cycles` worth of it with the given
+/// cost model and returns how many instructions ran
+fn instructions_in_budget(cost_model: CostModel, cycles: u32) -> u64 {
+    let program = assemble(
+        "
+        loop:
+            DRW V0, V0, 1
+            ADD V1, 1
+            JP loop
+    ",
+    )
+    .unwrap();
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .cost_model(cost_model)
+        .build()
+        .unwrap();
+    let mut chip8 = common::machine_with(&config, &program, |_| {});
+
+    chip8.run_frame(cycles).unwrap();
+    chip8.cycle_count()
+}
+
+#[test]
+fn flat_cost_model_counts_instructions() {
+    assert_eq!(instructions_in_budget(CostModel::flat(), 30), 30);
+}
+
+#[test]
+fn costly_draws_leave_room_for_fewer_instructions() {
+    // Each pass round the loop costs 10 + 1 + 1, so 30 cycles is two passes
+    // plus a draw to use up the remainder
+    let model = CostModel::flat().with_cost(0xD, 10);
+    assert_eq!(instructions_in_budget(model, 30), 7);
+}
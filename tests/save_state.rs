@@ -0,0 +1,131 @@
+mod common;
+
+use chip8rs::{Chip8Error, Chip8State, RAM_SIZE};
+
+// LD V0, 0x2A; LD I, 0x300; LD [I], V0; JP 0x206
+const PROGRAM: [u8; 8] = [0x60, 0x2A, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x06];
+
+#[test]
+fn save_state_round_trips() {
+    let mut chip8 = common::machine(&PROGRAM);
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+    let saved = chip8.save_state();
+
+    let mut restored = common::machine(&PROGRAM);
+    restored.load_state(&saved).unwrap();
+
+    assert_eq!(restored.state(), chip8.state());
+    assert_eq!(restored.ram_checksum(), chip8.ram_checksum());
+}
+
+#[test]
+fn ram_checksum_changes_with_ram() {
+    let mut chip8 = common::machine(&PROGRAM);
+    let before = chip8.ram_checksum();
+
+    // Only the store to 0x300 changes RAM
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.ram_checksum(), before);
+    chip8.step().unwrap();
+    assert_ne!(chip8.ram_checksum(), before);
+}
+
+#[test]
+fn flipped_ram_byte_is_rejected() {
+    let chip8 = common::machine(&PROGRAM);
+    let mut saved = chip8.save_state();
+
+    // RAM is the last part of a save state
+    let index = saved.len() - RAM_SIZE + 0x300;
+    saved[index] ^= 0xFF;
+
+    assert_eq!(
+        Chip8State::from_bytes(&saved),
+        Err(Chip8Error::ChecksumMismatch)
+    );
+
+    let mut other = common::machine(&[0x12, 0x00]);
+    let before = other.state();
+    assert_eq!(other.load_state(&saved), Err(Chip8Error::ChecksumMismatch));
+    assert_eq!(other.state(), before);
+}
+
+#[test]
+fn truncated_save_state_is_rejected() {
+    let saved = common::machine(&PROGRAM).save_state();
+
+    assert_eq!(
+        Chip8State::from_bytes(&saved[..saved.len() - 1]),
+        Err(Chip8Error::BadSaveState)
+    );
+    assert_eq!(
+        Chip8State::from_bytes(b"not a save state"),
+        Err(Chip8Error::BadSaveState)
+    );
+}
+
+#[test]
+fn ram_checksum_is_standard_crc32() {
+    let chip8 = common::machine(&[]);
+
+    // zlib.crc32(bytes(4096))
+    assert_eq!(chip8.ram_checksum(), 0xC71C_0011);
+}
+
+#[test]
+fn save_state_keeps_a_deeper_stack() {
+    let config = chip8rs::Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .stack_size(24)
+        .build()
+        .unwrap();
+    // CALL 0x200, forever
+    let mut chip8 = common::machine_with(&config, &[0x22, 0x00], |_| {});
+    for _ in 0..20 {
+        chip8.step().unwrap();
+    }
+
+    let state = Chip8State::from_bytes(&chip8.save_state()).unwrap();
+    assert_eq!(state, chip8.state());
+    assert_eq!(state.stack.len(), 24);
+}
+
+#[test]
+fn stack_pointer_past_the_stack_is_rejected() {
+    let mut saved = common::machine(&PROGRAM).save_state();
+
+    // SP follows the magic, the checksum, V0-VF, I and PC
+    saved[4 + 4 + 16 + 2 + 2] = 17;
+
+    assert_eq!(
+        Chip8State::from_bytes(&saved),
+        Err(Chip8Error::InvalidState)
+    );
+}
+
+#[test]
+fn save_state_too_deep_for_the_configured_stack_is_rejected() {
+    let config = chip8rs::Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .stack_size(24)
+        .build()
+        .unwrap();
+    // CALL 0x200, forever
+    let mut deep = common::machine_with(&config, &[0x22, 0x00], |_| {});
+    for _ in 0..20 {
+        deep.step().unwrap();
+    }
+
+    let mut shallow = common::machine(&PROGRAM);
+    let before = shallow.state();
+    assert_eq!(
+        shallow.load_state(&deep.save_state()),
+        Err(Chip8Error::InvalidState)
+    );
+    assert_eq!(shallow.state(), before);
+}
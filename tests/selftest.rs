@@ -0,0 +1,66 @@
+mod common;
+
+use chip8rs::selftest::{self, ROM};
+use chip8rs::{MemoryIncrement, Quirks};
+
+#[test]
+fn every_preset_passes() {
+    for quirks in [
+        Quirks::default(),
+        Quirks::cosmac(),
+        Quirks::chip48(),
+        Quirks::schip(),
+        Quirks::xochip(),
+    ]
+    .iter()
+    {
+        let results = selftest::run(*quirks).unwrap();
+
+        assert_eq!(results.len(), 5);
+        for result in results {
+            assert!(result.passed(), "{:?} with {:?}", result, quirks);
+        }
+    }
+}
+
+#[test]
+fn mismatched_quirks_fail_only_where_they_differ() {
+    // Run the ROM as CHIP-48 from a hand made state, then check it against
+    // the default quirks, which only differ in jump and memory and clipping
+    let config = common::config_with(|b| b.quirks(Quirks::chip48()));
+    let mut chip8 = common::machine_with(&config, &ROM, |_| {});
+    for _ in 0..10 {
+        chip8.run_frame(100).unwrap();
+    }
+    assert!(chip8.is_halted());
+
+    let failed: Vec<_> = selftest::read_results(&chip8, &Quirks::default())
+        .into_iter()
+        .filter(|result| !result.passed())
+        .collect();
+
+    let names: Vec<_> = failed.iter().map(|result| result.name).collect();
+    assert_eq!(names, ["jump", "memory", "clipping"]);
+
+    assert_eq!(failed[0].expected, "nnn + V0");
+    assert_eq!(failed[0].observed, "nnn + Vx");
+}
+
+#[test]
+fn markers_are_read_from_the_display() {
+    // A machine that has drawn nothing reads as clipping, no shift in place
+    // and so on, whatever it was configured with
+    let quirks = Quirks {
+        shift: false,
+        jump: false,
+        vf_reset: false,
+        memory_increment: MemoryIncrement::None,
+        wrap_x: false,
+        ..Quirks::default()
+    };
+    let chip8 = common::machine(&[]);
+
+    assert!(selftest::read_results(&chip8, &quirks)
+        .iter()
+        .all(|result| result.passed()));
+}
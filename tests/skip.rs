@@ -0,0 +1,33 @@
+mod common;
+
+use chip8rs::Chip8Error;
+
+#[test]
+fn se_vx_vy_skips_when_equal() {
+    // SE V1, V2 with both 0
+    let mut chip8 = common::machine(&[0x51, 0x20]);
+    chip8.step().unwrap();
+
+    assert_eq!(chip8.state().pc, 0x204);
+}
+
+#[test]
+fn sne_vx_vy_skips_when_different() {
+    // SNE V1, V2 with V1 = 1
+    let mut chip8 = common::machine_with(&common::config(), &[0x91, 0x20], |state| {
+        state.vx[1] = 1;
+    });
+    chip8.step().unwrap();
+
+    assert_eq!(chip8.state().pc, 0x204);
+}
+
+#[test]
+fn register_skips_need_a_zero_low_nibble() {
+    for opcode in [0x5123, 0x512F, 0x9121, 0x912E] {
+        let mut chip8 = common::machine(&[(opcode >> 8) as u8, opcode as u8]);
+
+        assert_eq!(chip8.step(), Err(Chip8Error::UnknownOpcode(opcode)));
+        assert_eq!(chip8.state().pc, 0x200);
+    }
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use chip8rs::asm::assemble;
+use chip8rs::timing::Clock;
+
+#[test]
+fn sound_callback_reports_the_start_and_end_of_a_beep() {
+    let program = assemble("LD V0, 3\nLD ST, V0").unwrap();
+    let mut chip8 = common::machine(&program);
+
+    let changes = Rc::new(RefCell::new(Vec::new()));
+    let recorded = Rc::clone(&changes);
+    chip8.on_sound_change(move |beeping| recorded.borrow_mut().push(beeping));
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    for _ in 0..10 {
+        chip8.tick_timers();
+    }
+
+    assert!(!chip8.is_beeping());
+    assert_eq!(*changes.borrow(), vec![true, false]);
+}
+
+/// A clock that only moves when the test moves it
+struct ManualClock(Rc<Cell<Duration>>);
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.0.get()
+    }
+}
+
+#[test]
+fn sound_timer_counts_down_at_60_hz_on_the_timer_clock() {
+    let program = assemble("LD V0, 30\nLD ST, V0").unwrap();
+    let mut chip8 = common::machine(&program);
+
+    let time = Rc::new(Cell::new(Duration::default()));
+    chip8.set_timer_clock(Box::new(ManualClock(Rc::clone(&time))));
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.sound_remaining(), 30);
+
+    // Half a frame isn't a tick
+    time.set(Duration::from_nanos(8_000_000));
+    assert_eq!(chip8.sync_timers(), 0);
+    assert_eq!(chip8.sound_remaining(), 30);
+
+    // Each frame is rounded up to the nanosecond, which over 30 frames adds
+    // up to far less than another tick
+    for frame in 1..=30 {
+        time.set(Duration::from_nanos(16_666_667 * frame));
+        assert_eq!(chip8.sync_timers(), 1);
+        assert_eq!(chip8.sound_remaining(), 30 - frame as u8);
+        assert_eq!(chip8.is_beeping(), frame < 30);
+    }
+}
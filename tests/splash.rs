@@ -0,0 +1,40 @@
+#![cfg(feature = "std")]
+
+use chip8rs::{asm, Chip8, Config, PROGRAM_START, SPLASH_ROM};
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn no_rom_path_selects_the_splash_rom() {
+    let config = Config::new(args(&["chip8rs", "--headless"])).unwrap();
+    assert!(config.embedded_rom);
+
+    let mut chip8 = Chip8::new(&config).unwrap();
+    let start = PROGRAM_START;
+    assert_eq!(
+        chip8.memory_slice(start..start + SPLASH_ROM.len()),
+        Ok(SPLASH_ROM)
+    );
+
+    for _ in 0..10 {
+        chip8.run_frame(20).unwrap();
+    }
+    assert!(chip8.is_pixel_lit(27, 10));
+}
+
+#[test]
+fn rom_path_turns_the_splash_rom_off() {
+    let config = Config::new(args(&["chip8rs", "--headless", "game.ch8"])).unwrap();
+
+    assert!(!config.embedded_rom);
+    assert_eq!(config.rom_path, "game.ch8");
+}
+
+#[test]
+fn splash_rom_matches_its_source() {
+    let source = include_str!("../roms/splash.asm");
+
+    assert_eq!(asm::assemble(source).unwrap(), SPLASH_ROM);
+}
@@ -0,0 +1,172 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use chip8rs::asm::assemble;
+use chip8rs::{Chip8, Chip8Error, Config, Quirks};
+
+const NESTED_CALLS: &str = "
+        CALL first
+    done:
+        JP done
+    first:
+        CALL second
+        RET
+    second:
+        RET
+";
+
+#[test]
+fn call_stack_lists_nested_calls_in_order() {
+    let mut chip8 = common::machine(&assemble(NESTED_CALLS).unwrap());
+
+    assert_eq!(chip8.call_depth(), 0);
+    assert!(chip8.call_stack().is_empty());
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.call_depth(), 2);
+    assert_eq!(chip8.call_stack(), &[0x202, 0x206]);
+}
+
+#[test]
+fn returning_pops_the_call_stack() {
+    let mut chip8 = common::machine(&assemble(NESTED_CALLS).unwrap());
+
+    // CALL, CALL, RET
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+    assert_eq!(chip8.call_depth(), 1);
+    assert_eq!(chip8.call_stack(), &[0x202]);
+    assert_eq!(chip8.state().pc, 0x206);
+}
+
+#[test]
+fn full_call_stack_lists_every_slot() {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .stack_size(2)
+        .build()
+        .unwrap();
+    let mut chip8 = common::machine_with(&config, &assemble(NESTED_CALLS).unwrap(), |state| {
+        state.stack = vec![0; 2];
+    });
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(chip8.call_stack(), &[0x202, 0x206]);
+}
+
+/// Calls from 0x200, then recurses forever, so the first return address is
+/// 0x202 and every other one is 0x204
+const ENDLESS_RECURSION: &str = "
+        CALL recurse
+    recurse:
+        CALL recurse
+";
+
+fn stack_config(wrap_stack: bool) -> Config {
+    Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .quirks(Quirks {
+            wrap_stack,
+            ..Quirks::default()
+        })
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn a_17th_call_overflows_the_stack() {
+    let program = assemble(ENDLESS_RECURSION).unwrap();
+    let mut chip8 = common::machine_with(&stack_config(false), &program, |_| {});
+
+    for _ in 0..16 {
+        chip8.step().unwrap();
+    }
+    assert_eq!(chip8.step(), Err(Chip8Error::StackOverflow));
+}
+
+#[test]
+fn a_17th_call_overwrites_the_first_slot_with_wrap_stack() {
+    let program = assemble(ENDLESS_RECURSION).unwrap();
+    let mut chip8 = common::machine_with(&stack_config(true), &program, |_| {});
+
+    for _ in 0..16 {
+        chip8.step().unwrap();
+    }
+    assert_eq!(chip8.state().stack[0], 0x202);
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.call_depth(), 1);
+    assert_eq!(chip8.state().stack[0], 0x204);
+}
+
+#[test]
+fn returning_with_an_empty_stack_takes_the_last_slot_with_wrap_stack() {
+    let program = assemble("RET").unwrap();
+    let mut chip8 = common::machine_with(&stack_config(true), &program, |state| {
+        state.stack[15] = 0x300;
+    });
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.state().pc, 0x300);
+    assert_eq!(chip8.call_depth(), 15);
+}
+
+#[test]
+fn a_deeper_stack_allows_more_nested_calls() {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .stack_size(32)
+        .build()
+        .unwrap();
+    let program = assemble(ENDLESS_RECURSION).unwrap();
+    let mut chip8 = common::machine_with(&config, &program, |_| {});
+
+    for _ in 0..20 {
+        chip8.step().unwrap();
+    }
+    assert_eq!(chip8.call_stack().len(), 20);
+
+    for _ in 20..32 {
+        chip8.step().unwrap();
+    }
+    assert_eq!(chip8.step(), Err(Chip8Error::StackOverflow));
+    assert_eq!(chip8.state().stack.len(), 32);
+}
+
+#[test]
+fn stack_size_must_fit_the_stack_pointer() {
+    let build = |size| {
+        Config::builder()
+            .rom_path("unused.ch8")
+            .stack_size(size)
+            .build()
+    };
+
+    assert!(build(0).is_err());
+    assert!(build(255).is_ok());
+    assert!(build(256).is_err());
+}
+
+#[test]
+fn state_with_sp_past_the_configured_stack_is_rejected() {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .stack_size(8)
+        .build()
+        .unwrap();
+    let mut state = common::machine(&[]).state();
+    state.sp = 12;
+
+    assert_eq!(
+        Chip8::from_state(&config, state).err(),
+        Some(Chip8Error::InvalidState)
+    );
+}
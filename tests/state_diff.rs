@@ -0,0 +1,60 @@
+mod common;
+
+#[test]
+fn diff_lists_exactly_what_changed() {
+    let before = common::machine(&[]).state();
+    let mut after = before.clone();
+    after.vx[0x3] = 0x10;
+    after.vx[0xF] = 1;
+    after.memory[0x300] = 0xAB;
+
+    let mut diff = before.diff(&after);
+    assert_eq!(diff.registers, vec![(0x3, 0, 0x10), (0xF, 0, 1)]);
+    assert_eq!(diff.ram.pop(), Some(0x300..0x301));
+    assert!(diff.ram.is_empty());
+    diff.registers.clear();
+    assert!(diff.is_empty(), "{:?}", diff);
+
+    let diff = before.diff(&after);
+    assert_eq!(
+        diff.to_string(),
+        "V3: 0x00 -> 0x10\nVF: 0x00 -> 0x01\nRAM: 0x0300..0x0301\n"
+    );
+}
+
+#[test]
+fn neighbouring_ram_changes_are_one_range() {
+    let before = common::machine(&[]).state();
+    let mut after = before.clone();
+    after.memory[0x400..0x404].copy_from_slice(&[1, 2, 3, 4]);
+    after.memory[0x410] = 5;
+
+    assert_eq!(before.diff(&after).ram, vec![0x400..0x404, 0x410..0x411]);
+}
+
+#[test]
+fn pc_i_and_stack_changes_are_listed_as_pairs() {
+    let before = common::machine(&[]).state();
+    let mut after = before.clone();
+    after.pc = 0x204;
+    after.i = 0x300;
+    after.sp = 1;
+    after.stack[0] = 0x202;
+
+    let diff = before.diff(&after);
+    assert_eq!(diff.pc, Some((0x200, 0x204)));
+    assert_eq!(diff.i, Some((0, 0x300)));
+    assert_eq!(diff.sp, Some((0, 1)));
+    assert_eq!(diff.stack, vec![(0, 0, 0x202)]);
+    assert!(diff.registers.is_empty());
+    assert!(diff.ram.is_empty());
+}
+
+#[test]
+fn identical_states_have_no_differences() {
+    let state = common::machine(&[0x12, 0x00]).state();
+    let diff = state.diff(&state.clone());
+
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "No differences\n");
+}
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use chip8rs::timing::{
+    cycles_due, cycles_per_frame, frame_wait, FrameWait, RateMeter, Rates, SyncMode, MAX_CATCH_UP,
+};
+
+#[test]
+fn rates_are_reported_once_a_second_has_passed() {
+    let mut meter = RateMeter::new();
+    let frame = Duration::from_millis(20);
+
+    // 49 frames of 20ms is still short of a second
+    for _ in 0..49 {
+        assert_eq!(meter.frame(frame, 10), None);
+    }
+
+    assert_eq!(
+        meter.frame(frame, 10),
+        Some(Rates {
+            frames_per_sec: 50.0,
+            cycles_per_sec: 500.0,
+        })
+    );
+}
+
+#[test]
+fn measuring_restarts_after_each_report() {
+    let mut meter = RateMeter::new();
+
+    let first = meter.frame(Duration::from_secs(2), 1000).unwrap();
+    assert_eq!(first.frames_per_sec, 0.5);
+    assert_eq!(first.cycles_per_sec, 500.0);
+
+    assert_eq!(meter.frame(Duration::from_millis(500), 100), None);
+    let second = meter.frame(Duration::from_millis(500), 100).unwrap();
+    assert_eq!(second.frames_per_sec, 2.0);
+    assert_eq!(second.cycles_per_sec, 200.0);
+}
+
+#[test]
+fn sleep_mode_sleeps_out_the_rest_of_the_frame() {
+    // 20ms into a 50 FPS frame leaves nothing, 5ms in leaves 15ms
+    assert_eq!(
+        frame_wait(SyncMode::Sleep, 50, Duration::from_millis(5)),
+        FrameWait::Sleep(Duration::from_millis(15))
+    );
+    assert_eq!(
+        frame_wait(SyncMode::Sleep, 50, Duration::from_millis(20)),
+        FrameWait::None
+    );
+}
+
+#[test]
+fn spin_mode_busy_waits_out_the_rest_of_the_frame() {
+    assert_eq!(
+        frame_wait(SyncMode::Spin, 50, Duration::from_millis(5)),
+        FrameWait::Spin(Duration::from_millis(15))
+    );
+    assert_eq!(
+        frame_wait(SyncMode::Spin, 50, Duration::from_millis(30)),
+        FrameWait::None
+    );
+}
+
+#[test]
+fn vsync_mode_leaves_the_wait_to_the_window() {
+    assert_eq!(
+        frame_wait(SyncMode::Vsync, 50, Duration::from_millis(5)),
+        FrameWait::None
+    );
+}
+
+#[test]
+fn no_mode_waits_when_the_frame_rate_is_unlimited() {
+    for mode in [SyncMode::Sleep, SyncMode::Spin, SyncMode::Vsync] {
+        assert_eq!(
+            frame_wait(mode, 0, Duration::from_millis(5)),
+            FrameWait::None
+        );
+    }
+}
+
+/// Total instructions `cycles_due` hands out over a run of frames
+fn total_cycles(frames: &[u64], hz: u32) -> u32 {
+    let mut leftover = Duration::from_secs(0);
+
+    frames
+        .iter()
+        .map(|millis| cycles_due(Duration::from_millis(*millis), hz, &mut leftover))
+        .sum()
+}
+
+/// A second of frames that don't divide evenly into instructions
+fn uneven_second() -> Vec<u64> {
+    let mut frames = Vec::new();
+    for _ in 0..20 {
+        frames.extend([16, 17, 17]);
+    }
+    frames
+}
+
+#[test]
+fn cycles_due_tracks_real_time() {
+    // The fractions left over each frame add up to whole instructions
+    // rather than being dropped. A nanosecond or so can be lost to rounding
+    let cycles = total_cycles(&uneven_second(), 700);
+    assert!((699..=700).contains(&cycles), "{} cycles", cycles);
+
+    assert_eq!(total_cycles(&uneven_second(), 500), 500);
+}
+
+#[test]
+fn turbo_runs_the_multiplied_clock() {
+    let turbo = total_cycles(&uneven_second(), 700 * 4);
+    assert!((2799..=2800).contains(&turbo), "{} cycles", turbo);
+
+    assert_eq!(cycles_per_frame(700, 1), 11);
+    assert_eq!(cycles_per_frame(700, 4), 46);
+}
+
+#[test]
+fn cycles_due_drops_time_past_the_catch_up_limit() {
+    let mut leftover = Duration::from_secs(0);
+
+    // A one second stall only catches up on MAX_CATCH_UP, at either speed
+    assert_eq!(cycles_due(Duration::from_secs(1), 700, &mut leftover), 70);
+    assert_eq!(cycles_due(Duration::from_secs(0), 700, &mut leftover), 0);
+    assert_eq!(cycles_due(Duration::from_secs(1), 2800, &mut leftover), 280);
+    assert!(leftover < MAX_CATCH_UP);
+
+    // Normal frames afterwards are unaffected
+    let cycles = (0..60)
+        .map(|_| cycles_due(Duration::from_millis(50), 700, &mut leftover))
+        .sum::<u32>();
+    assert!((2099..=2100).contains(&cycles), "{} cycles", cycles);
+}
+
+#[test]
+fn very_slow_clock_still_runs_an_instruction_a_frame() {
+    assert_eq!(cycles_per_frame(30, 1), 1);
+}
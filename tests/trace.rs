@@ -0,0 +1,43 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::fs;
+
+use chip8rs::asm::assemble;
+
+#[test]
+fn trace_csv_has_a_row_per_frame() {
+    let dir = std::env::temp_dir();
+    let replay_path = dir.join("chip8rs_trace.c8r");
+    let csv_path = dir.join("chip8rs_trace.csv");
+
+    let mut chip8 = common::machine(&assemble("RND V0, 0xFF\nhalt: JP halt").unwrap());
+    chip8.set_key(3, true);
+    chip8.start_recording(&replay_path);
+    chip8.run_frame(1).unwrap();
+
+    chip8.set_key(3, false);
+    chip8.set_key(1, true);
+    chip8.poll_keyboard();
+    chip8.run_frame(1).unwrap();
+
+    chip8.export_trace_csv(&csv_path).unwrap();
+    let csv = fs::read_to_string(&csv_path).unwrap();
+    fs::remove_file(&csv_path).unwrap();
+
+    let random = chip8.state().vx[0];
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "frame,keys_bitmask,rng_byte");
+    assert_eq!(lines[1], format!("0,8,{}", random));
+    assert_eq!(lines[2], "1,2,");
+}
+
+#[test]
+fn trace_csv_needs_a_session() {
+    let chip8 = common::machine(&[]);
+    let path = std::env::temp_dir().join("chip8rs_no_trace.csv");
+
+    assert!(chip8.export_trace_csv(&path).is_err());
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use chip8rs::asm::assemble;
+use chip8rs::{Chip8, Config, UninitRead};
+
+const PROGRAM: &str = "
+    LD V1, 2
+    ADD V1, V5
+    ADD V1, V5
+";
+
+fn run(strict_uninit: bool) -> Chip8 {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .strict_uninit(strict_uninit)
+        .build()
+        .unwrap();
+    let mut chip8 = Chip8::from_rom(&config, &assemble(PROGRAM).unwrap()).unwrap();
+
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+
+    chip8
+}
+
+#[test]
+fn reading_an_unwritten_register_is_reported_once_in_strict_mode() {
+    let chip8 = run(true);
+
+    assert_eq!(
+        chip8.uninit_reads(),
+        &[UninitRead {
+            pc: 0x202,
+            register: 5,
+        }]
+    );
+}
+
+#[test]
+fn unwritten_reads_are_not_tracked_by_default() {
+    let chip8 = run(false);
+
+    assert!(chip8.uninit_reads().is_empty());
+}
+
+fn run_program(source: &str, steps: usize) -> Chip8 {
+    let config = Config::builder()
+        .rom_path("unused.ch8")
+        .headless(true)
+        .strict_uninit(true)
+        .build()
+        .unwrap();
+    let mut chip8 = Chip8::from_rom(&config, &assemble(source).unwrap()).unwrap();
+
+    for _ in 0..steps {
+        chip8.step().unwrap();
+    }
+
+    chip8
+}
+
+#[test]
+fn storing_every_register_reports_each_unwritten_one() {
+    let chip8 = run_program("LD [I], VF", 1);
+
+    let registers: Vec<u8> = chip8
+        .uninit_reads()
+        .iter()
+        .map(|read| read.register)
+        .collect();
+    assert_eq!(registers, (0..16).collect::<Vec<u8>>());
+}
+
+#[test]
+fn loading_every_register_counts_as_writing_them() {
+    let chip8 = run_program("LD VF, [I]\nADD V1, VF", 2);
+
+    assert!(chip8.uninit_reads().is_empty());
+}